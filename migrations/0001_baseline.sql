@@ -0,0 +1,46 @@
+-- schema of the last release, before versioned migrations
+CREATE TABLE IF NOT EXISTS users (
+  id INTEGER PRIMARY KEY NOT NULL,
+  password_hash TEXT NOT NULL,
+  name TEXT NOT NULL UNIQUE,
+  role TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS directories (
+  id ULID PRIMARY KEY NOT NULL,
+  name TEXT NOT NULL,
+  parent_path TEXT NOT NULL,
+  file_count INTEGER DEFAULT NULL
+);
+CREATE TABLE IF NOT EXISTS files (
+  id ULID PRIMARY KEY NOT NULL,
+  name TEXT NOT NULL,
+  library_name TEXT NOT NULL,
+  parent_path TEXT NOT NULL,
+  scan_me BOOLEAN DEFAULT TRUE,
+  added_date INTEGER NOT NULL,
+  format TEXT DEFAULT NULL,
+  size INTEGER NOT NULL DEFAULT 0,
+  total_pages INTEGER NOT NULL DEFAULT 0,
+  read_by TEXT DEFAULT NULL,
+  bookmarked_by TEXT DEFAULT NULL
+);
+CREATE TABLE IF NOT EXISTS covers (
+  id ULID PRIMARY KEY NOT NULL,
+  cover BLOB DEFAULT NULL
+);
+CREATE TABLE IF NOT EXISTS reading (
+  id INTEGER PRIMARY KEY NOT NULL,
+  file_id ULID NOT NULL,
+  user_id INTEGER NOT NULL,
+  page INTEGER NOT NULL,
+  UNIQUE(file_id, user_id)
+);
+CREATE TABLE IF NOT EXISTS libraries (
+  id INTEGER PRIMARY KEY NOT NULL,
+  name TEXT DEFAULT NULL UNIQUE,
+  path TEXT DEFAULT NULL UNIQUE,
+  scan_lock BOOLEAN DEFAULT FALSE,
+  file_count INTEGER DEFAULT NULL,
+  last_successfull_scan_date INTEGER NOT NULL DEFAULT 0,
+  last_successfull_extract_date INTEGER NOT NULL DEFAULT 0
+);
@@ -0,0 +1,34 @@
+-- read status and bookmarks move from comma separated users id (`1,2,3`) to join tables
+CREATE TABLE user_file_flags (
+  file_id ULID NOT NULL,
+  user_id INTEGER NOT NULL,
+  flag TEXT NOT NULL,
+  flag_date INTEGER NOT NULL,
+  PRIMARY KEY(file_id, user_id, flag)
+);
+CREATE INDEX user_file_flags_user_id ON user_file_flags(user_id, flag);
+CREATE TABLE user_directory_flags (
+  directory_id ULID NOT NULL,
+  user_id INTEGER NOT NULL,
+  flag TEXT NOT NULL,
+  flag_date INTEGER NOT NULL,
+  PRIMARY KEY(directory_id, user_id, flag)
+);
+
+-- split users id lists, garbage is ignored
+INSERT OR IGNORE INTO user_file_flags(file_id, user_id, flag, flag_date)
+WITH RECURSIVE split(file_id, flag, user_id, remaining) AS (
+  SELECT id, 'read_status', '', read_by || ',' FROM files WHERE ifnull(read_by, '') != ''
+  UNION ALL
+  SELECT id, 'bookmark', '', bookmarked_by || ',' FROM files WHERE ifnull(bookmarked_by, '') != ''
+  UNION ALL
+  SELECT file_id, flag,
+    trim(substr(remaining, 1, instr(remaining, ',') - 1)),
+    substr(remaining, instr(remaining, ',') + 1)
+  FROM split WHERE remaining != ''
+)
+SELECT file_id, CAST(user_id AS INTEGER), flag, CAST(strftime('%s', 'now') AS INTEGER)
+FROM split WHERE user_id != '' AND user_id NOT GLOB '*[^0-9]*';
+
+ALTER TABLE files DROP COLUMN read_by;
+ALTER TABLE files DROP COLUMN bookmarked_by;
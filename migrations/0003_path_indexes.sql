@@ -0,0 +1,4 @@
+-- libraries and directories are browsed by path
+CREATE INDEX IF NOT EXISTS files_parent_path ON files(parent_path);
+CREATE INDEX IF NOT EXISTS files_library_name ON files(library_name);
+CREATE INDEX IF NOT EXISTS directories_parent_path ON directories(parent_path);
@@ -0,0 +1,52 @@
+-- metadata extracted from files, used by search
+ALTER TABLE files ADD COLUMN title TEXT DEFAULT NULL;
+ALTER TABLE files ADD COLUMN author TEXT DEFAULT NULL;
+ALTER TABLE files ADD COLUMN series TEXT DEFAULT NULL;
+ALTER TABLE files ADD COLUMN description TEXT DEFAULT NULL;
+
+-- full text search, rowid is the rowid of files and directories
+-- `remove_diacritics 2` : `élan` matches `elan`
+CREATE VIRTUAL TABLE files_search USING fts5(
+  name, parent_path, title, author, series, description,
+  tokenize = 'unicode61 remove_diacritics 2',
+  prefix = '2 3'
+);
+CREATE VIRTUAL TABLE directories_search USING fts5(
+  name, parent_path,
+  tokenize = 'unicode61 remove_diacritics 2',
+  prefix = '2 3'
+);
+INSERT INTO files_search(rowid, name, parent_path, title, author, series, description)
+SELECT rowid, name, parent_path, title, author, series, description FROM files;
+INSERT INTO directories_search(rowid, name, parent_path)
+SELECT rowid, name, parent_path FROM directories;
+
+-- keep indexes up to date, `INSERT OR REPLACE` changes the rowid without firing delete triggers
+CREATE TRIGGER files_search_before_insert BEFORE INSERT ON files BEGIN
+  DELETE FROM files_search WHERE rowid = (SELECT rowid FROM files WHERE id = new.id);
+END;
+CREATE TRIGGER files_search_after_insert AFTER INSERT ON files BEGIN
+  INSERT INTO files_search(rowid, name, parent_path, title, author, series, description)
+  VALUES (new.rowid, new.name, new.parent_path, new.title, new.author, new.series, new.description);
+END;
+CREATE TRIGGER files_search_after_update AFTER UPDATE OF name, parent_path, title, author, series, description ON files BEGIN
+  DELETE FROM files_search WHERE rowid = old.rowid;
+  INSERT INTO files_search(rowid, name, parent_path, title, author, series, description)
+  VALUES (new.rowid, new.name, new.parent_path, new.title, new.author, new.series, new.description);
+END;
+CREATE TRIGGER files_search_after_delete AFTER DELETE ON files BEGIN
+  DELETE FROM files_search WHERE rowid = old.rowid;
+END;
+CREATE TRIGGER directories_search_before_insert BEFORE INSERT ON directories BEGIN
+  DELETE FROM directories_search WHERE rowid = (SELECT rowid FROM directories WHERE id = new.id);
+END;
+CREATE TRIGGER directories_search_after_insert AFTER INSERT ON directories BEGIN
+  INSERT INTO directories_search(rowid, name, parent_path) VALUES (new.rowid, new.name, new.parent_path);
+END;
+CREATE TRIGGER directories_search_after_update AFTER UPDATE OF name, parent_path ON directories BEGIN
+  DELETE FROM directories_search WHERE rowid = old.rowid;
+  INSERT INTO directories_search(rowid, name, parent_path) VALUES (new.rowid, new.name, new.parent_path);
+END;
+CREATE TRIGGER directories_search_after_delete AFTER DELETE ON directories BEGIN
+  DELETE FROM directories_search WHERE rowid = old.rowid;
+END;
@@ -0,0 +1,8 @@
+-- saved searches, evaluated again on each opening
+CREATE TABLE collections (
+  id INTEGER PRIMARY KEY NOT NULL,
+  user_id INTEGER NOT NULL,
+  name TEXT NOT NULL,
+  query TEXT NOT NULL,
+  UNIQUE(user_id, name)
+);
@@ -0,0 +1,14 @@
+-- ordered lists of files, private or shared with other users
+CREATE TABLE reading_lists (
+  id INTEGER PRIMARY KEY NOT NULL,
+  user_id INTEGER NOT NULL,
+  name TEXT NOT NULL,
+  shared BOOLEAN NOT NULL DEFAULT FALSE
+);
+CREATE TABLE reading_list_items (
+  list_id INTEGER NOT NULL,
+  file_id ULID NOT NULL,
+  position INTEGER NOT NULL,
+  PRIMARY KEY(list_id, file_id)
+);
+CREATE INDEX reading_list_items_file_id ON reading_list_items(file_id);
@@ -0,0 +1,12 @@
+-- series detected from metadata (ComicInfo.xml, epub) or file names
+CREATE TABLE series (
+  id INTEGER PRIMARY KEY NOT NULL,
+  name TEXT NOT NULL UNIQUE COLLATE NOCASE
+);
+ALTER TABLE files ADD COLUMN series_id INTEGER DEFAULT NULL;
+-- volume number in its series, `1.5` for specials
+ALTER TABLE files ADD COLUMN volume REAL DEFAULT NULL;
+CREATE INDEX files_series_id ON files(series_id);
+-- only metadata and series are extracted again for files already scanned
+ALTER TABLE files ADD COLUMN series_me BOOLEAN NOT NULL DEFAULT FALSE;
+UPDATE files SET series_me = TRUE;
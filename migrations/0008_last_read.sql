@@ -0,0 +1,2 @@
+-- date of the last page read, used to sort by last read
+ALTER TABLE reading ADD COLUMN last_read INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,3 @@
+-- token for clients without session, like feed readers
+ALTER TABLE users ADD COLUMN api_token TEXT DEFAULT NULL;
+CREATE UNIQUE INDEX users_api_token ON users(api_token);
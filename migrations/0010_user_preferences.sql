@@ -0,0 +1,7 @@
+-- display preferences, users without a row get the defaults
+CREATE TABLE user_preferences (
+  user_id INTEGER PRIMARY KEY NOT NULL,
+  view TEXT NOT NULL DEFAULT 'grid',
+  readable_only BOOLEAN NOT NULL DEFAULT FALSE,
+  theme TEXT NOT NULL DEFAULT 'light'
+);
@@ -0,0 +1,6 @@
+-- epub reader typography, sizes are percents except margin (percent of width)
+ALTER TABLE user_preferences ADD COLUMN reader_font TEXT NOT NULL DEFAULT 'publisher';
+ALTER TABLE user_preferences ADD COLUMN reader_font_size INTEGER NOT NULL DEFAULT 100;
+ALTER TABLE user_preferences ADD COLUMN reader_line_height INTEGER NOT NULL DEFAULT 150;
+ALTER TABLE user_preferences ADD COLUMN reader_margin INTEGER NOT NULL DEFAULT 5;
+ALTER TABLE user_preferences ADD COLUMN reader_justify BOOLEAN NOT NULL DEFAULT FALSE;
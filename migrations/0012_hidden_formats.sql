@@ -0,0 +1,3 @@
+-- formats hidden from listings, comma separated format keys (`txt,other`)
+ALTER TABLE user_preferences ADD COLUMN hidden_formats TEXT NOT NULL DEFAULT '';
+ALTER TABLE libraries ADD COLUMN hidden_formats TEXT NOT NULL DEFAULT '';
@@ -0,0 +1,3 @@
+-- cb7 and cbt files were stored as unsupported, extract them again
+UPDATE files SET format = 'cb7', scan_me = 1 WHERE name LIKE '%.cb7';
+UPDATE files SET format = 'cbt', scan_me = 1 WHERE name LIKE '%.cbt';
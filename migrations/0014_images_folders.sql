@@ -0,0 +1,2 @@
+-- images folders are now comics : walk all libraries again at next scan
+UPDATE libraries SET last_successfull_scan_date = 0;
@@ -0,0 +1,7 @@
+-- files whose extraction failed (unreadable entries names, no image...), shown to admins
+CREATE TABLE problem_files (
+  file_id ULID NOT NULL,
+  problem TEXT NOT NULL,
+  date INTEGER NOT NULL,
+  UNIQUE(file_id, problem)
+);
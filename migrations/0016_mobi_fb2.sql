@@ -0,0 +1,4 @@
+-- mobi, azw3 and fb2 files were stored as unsupported, extract them again
+UPDATE files SET format = 'mobi', scan_me = 1 WHERE name LIKE '%.mobi' OR name LIKE '%.azw';
+UPDATE files SET format = 'azw3', scan_me = 1 WHERE name LIKE '%.azw3';
+UPDATE files SET format = 'fb2', scan_me = 1 WHERE name LIKE '%.fb2' OR name LIKE '%.fb2.zip';
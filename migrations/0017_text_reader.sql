@@ -0,0 +1,9 @@
+-- server settings set in admin panel, a single row with id 1
+CREATE TABLE settings (
+  id INTEGER PRIMARY KEY NOT NULL,
+  text_page_unit TEXT NOT NULL DEFAULT 'lines',
+  text_page_size INTEGER NOT NULL DEFAULT 60
+);
+-- markdown files were stored as unsupported and text files had no pages, extract them again
+UPDATE files SET format = 'md' WHERE name LIKE '%.md' OR name LIKE '%.markdown';
+UPDATE files SET scan_me = 1 WHERE format IN ('txt', 'md');
@@ -0,0 +1,8 @@
+-- loose images (outside images folders) were stored as unsupported, extract them again
+UPDATE files SET format = 'jpg', scan_me = 1 WHERE format = 'other' AND (
+  name LIKE '%.jpg' OR name LIKE '%.jpeg' OR name LIKE '%.png' OR name LIKE '%.gif'
+  OR name LIKE '%.webp' OR name LIKE '%.bmp' OR name LIKE '%.tif' OR name LIKE '%.tiff'
+  OR name LIKE '%.tga' OR name LIKE '%.ico' OR name LIKE '%.qoi' OR name LIKE '%.pbm'
+  OR name LIKE '%.pgm' OR name LIKE '%.ppm' OR name LIKE '%.pam' OR name LIKE '%.hdr'
+  OR name LIKE '%.exr' OR name LIKE '%.dds' OR name LIKE '%.ff'
+);
@@ -0,0 +1,11 @@
+-- chapters of audiobooks (tracks for directories of tracks), start in seconds
+CREATE TABLE audio_chapters (
+  file_id ULID NOT NULL,
+  position INTEGER NOT NULL,
+  title TEXT NOT NULL,
+  start REAL NOT NULL,
+  UNIQUE(file_id, position)
+);
+-- audio files were stored as unsupported, extract them again
+UPDATE files SET format = 'm4b', scan_me = 1 WHERE name LIKE '%.m4b' OR name LIKE '%.m4a';
+UPDATE files SET format = 'mp3', scan_me = 1 WHERE name LIKE '%.mp3';
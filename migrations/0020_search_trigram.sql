@@ -0,0 +1,44 @@
+-- chinese, japanese and korean titles have no spaces between words, `unicode61` makes a single
+-- token of them : a trigram index finds any part of them (see `cjk_terms`)
+CREATE VIRTUAL TABLE files_trigram USING fts5(
+  name, parent_path, title, author, series,
+  tokenize = 'trigram'
+);
+CREATE VIRTUAL TABLE directories_trigram USING fts5(
+  name, parent_path,
+  tokenize = 'trigram'
+);
+INSERT INTO files_trigram(rowid, name, parent_path, title, author, series)
+SELECT rowid, name, parent_path, title, author, series FROM files;
+INSERT INTO directories_trigram(rowid, name, parent_path)
+SELECT rowid, name, parent_path FROM directories;
+
+-- same triggers as `files_search` and `directories_search`
+CREATE TRIGGER files_trigram_before_insert BEFORE INSERT ON files BEGIN
+  DELETE FROM files_trigram WHERE rowid = (SELECT rowid FROM files WHERE id = new.id);
+END;
+CREATE TRIGGER files_trigram_after_insert AFTER INSERT ON files BEGIN
+  INSERT INTO files_trigram(rowid, name, parent_path, title, author, series)
+  VALUES (new.rowid, new.name, new.parent_path, new.title, new.author, new.series);
+END;
+CREATE TRIGGER files_trigram_after_update AFTER UPDATE OF name, parent_path, title, author, series ON files BEGIN
+  DELETE FROM files_trigram WHERE rowid = old.rowid;
+  INSERT INTO files_trigram(rowid, name, parent_path, title, author, series)
+  VALUES (new.rowid, new.name, new.parent_path, new.title, new.author, new.series);
+END;
+CREATE TRIGGER files_trigram_after_delete AFTER DELETE ON files BEGIN
+  DELETE FROM files_trigram WHERE rowid = old.rowid;
+END;
+CREATE TRIGGER directories_trigram_before_insert BEFORE INSERT ON directories BEGIN
+  DELETE FROM directories_trigram WHERE rowid = (SELECT rowid FROM directories WHERE id = new.id);
+END;
+CREATE TRIGGER directories_trigram_after_insert AFTER INSERT ON directories BEGIN
+  INSERT INTO directories_trigram(rowid, name, parent_path) VALUES (new.rowid, new.name, new.parent_path);
+END;
+CREATE TRIGGER directories_trigram_after_update AFTER UPDATE OF name, parent_path ON directories BEGIN
+  DELETE FROM directories_trigram WHERE rowid = old.rowid;
+  INSERT INTO directories_trigram(rowid, name, parent_path) VALUES (new.rowid, new.name, new.parent_path);
+END;
+CREATE TRIGGER directories_trigram_after_delete AFTER DELETE ON directories BEGIN
+  DELETE FROM directories_trigram WHERE rowid = old.rowid;
+END;
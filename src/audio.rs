@@ -0,0 +1,706 @@
+//! audiobooks : m4b and mp3 files, and directories of tracks, with their duration, chapters and cover
+
+use crate::scanner::{is_image, natural_cmp, FileMetadata};
+
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// chapter of an audiobook, from its start in seconds
+#[derive(Debug, Clone, PartialEq, Serialize, sqlx::FromRow)]
+pub struct Chapter {
+    pub title: String,
+    pub start: f64,
+}
+
+/// what the scan keeps from an audio file
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AudioInfo {
+    // in seconds
+    pub duration: f64,
+    pub chapters: Vec<Chapter>,
+    pub title: Option<String>,
+    pub album: Option<String>,
+    pub author: Option<String>,
+    pub cover: Option<Vec<u8>>,
+}
+impl AudioInfo {
+    /// an audiobook title is its album, or its title for files without album
+    pub fn metadata(&self) -> FileMetadata {
+        FileMetadata {
+            title: self.album.clone().or(self.title.clone()),
+            author: self.author.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+fn extension(name: &str) -> String {
+    name.rsplit_once('.')
+        .map(|(_, extension)| extension.to_lowercase())
+        .unwrap_or_default()
+}
+
+/// audio files of audiobooks, from their extension
+pub fn is_audio(name: &str) -> bool {
+    !name.starts_with('.') && matches!(extension(name).as_str(), "mp3" | "m4a" | "m4b")
+}
+
+pub fn content_type(name: &str) -> &'static str {
+    match extension(name).as_str() {
+        "mp3" => "audio/mpeg",
+        _ => "audio/mp4",
+    }
+}
+
+/// tracks of a directory in listening order, empty unless it holds several tracks and images only
+pub fn folder_audio_list(directory_path: &Path) -> Vec<String> {
+    let mut track_list = Vec::new();
+    let Ok(entries) = fs::read_dir(directory_path) else {
+        return track_list;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        // hidden files are ignored, like in library scans
+        if name.starts_with('.') {
+            continue;
+        }
+        if !entry.path().is_file() {
+            return Vec::with_capacity(0);
+        }
+        if is_audio(&name) {
+            track_list.push(name);
+        } else if !is_image(&name) {
+            return Vec::with_capacity(0);
+        }
+    }
+    if track_list.len() < 2 {
+        return Vec::with_capacity(0);
+    }
+    track_list.sort_by(|a, b| natural_cmp(a, b));
+    track_list
+}
+
+/// duration, chapters, tags and cover of an audio file
+pub fn audio_info(path: &Path) -> Result<AudioInfo, String> {
+    let mut file = File::open(path).map_err(|e| format!("unable to open file : {e}"))?;
+    match extension(&path.to_string_lossy()).as_str() {
+        "mp3" => mp3_info(&mut file),
+        "m4a" | "m4b" => mp4_info(&mut file),
+        _ => Err("not an audio file".to_string()),
+    }
+}
+
+/// a directory of tracks : one chapter by track, the cover of a track or an image of the directory
+pub fn folder_info(directory_path: &Path) -> Result<AudioInfo, String> {
+    let track_list = folder_audio_list(directory_path);
+    let mut folder_info = AudioInfo::default();
+    for (index, track) in track_list.iter().enumerate() {
+        let track_info = audio_info(&directory_path.join(track))
+            .map_err(|e| format!("unable to read track {track} : {e}"))?;
+        let title = track_info.title.clone().unwrap_or_else(|| {
+            track
+                .rsplit_once('.')
+                .map_or(track.as_str(), |(stem, _)| stem)
+                .to_string()
+        });
+        folder_info.chapters.push(Chapter {
+            title,
+            start: folder_info.duration,
+        });
+        folder_info.duration += track_info.duration;
+        if index == 0 {
+            folder_info.album = track_info.album;
+            folder_info.author = track_info.author;
+        }
+        if folder_info.cover.is_none() {
+            folder_info.cover = track_info.cover;
+        }
+    }
+    if folder_info.chapters.is_empty() {
+        return Err("no tracks in directory".to_string());
+    }
+    if folder_info.cover.is_none() {
+        let mut image_list: Vec<String> = fs::read_dir(directory_path)
+            .map_err(|e| format!("unable to read directory : {e}"))?
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| is_image(name))
+            .collect();
+        image_list.sort_by(|a, b| natural_cmp(a, b));
+        folder_info.cover = image_list
+            .first()
+            .and_then(|image| fs::read(directory_path.join(image)).ok());
+    }
+    Ok(folder_info)
+}
+
+/// mp4 box : its type and content bounds in the file
+struct Mp4Box {
+    kind: [u8; 4],
+    start: u64,
+    end: u64,
+}
+
+/// boxes found between two offsets of a mp4 file
+fn mp4_boxes(file: &mut File, start: u64, end: u64) -> Vec<Mp4Box> {
+    let mut boxes = Vec::new();
+    let mut offset = start;
+    while offset.saturating_add(8) <= end {
+        let mut header = [0u8; 16];
+        if file.seek(SeekFrom::Start(offset)).is_err() || file.read_exact(&mut header[..8]).is_err()
+        {
+            break;
+        }
+        let kind = [header[4], header[5], header[6], header[7]];
+        let (size, header_size) =
+            match u32::from_be_bytes([header[0], header[1], header[2], header[3]]) {
+                // box up to the end of its parent
+                0 => (end - offset, 8),
+                // 64 bits size
+                1 => {
+                    if file.read_exact(&mut header[8..16]).is_err() {
+                        break;
+                    }
+                    let mut size = [0u8; 8];
+                    size.copy_from_slice(&header[8..16]);
+                    (u64::from_be_bytes(size), 16)
+                }
+                size => (size as u64, 8),
+            };
+        // a size overflowing its parent is a corrupted box
+        let box_end = match offset.checked_add(size) {
+            Some(box_end) if size >= header_size && box_end <= end => box_end,
+            _ => break,
+        };
+        boxes.push(Mp4Box {
+            kind,
+            start: offset + header_size,
+            end: box_end,
+        });
+        offset = box_end;
+    }
+    boxes
+}
+
+fn find_box<'a>(boxes: &'a [Mp4Box], kind: &[u8; 4]) -> Option<&'a Mp4Box> {
+    boxes.iter().find(|mp4_box| &mp4_box.kind == kind)
+}
+
+fn read_box(file: &mut File, mp4_box: &Mp4Box) -> Option<Vec<u8>> {
+    let mut content = vec![0u8; usize::try_from(mp4_box.end.checked_sub(mp4_box.start)?).ok()?];
+    file.seek(SeekFrom::Start(mp4_box.start)).ok()?;
+    file.read_exact(&mut content).ok()?;
+    Some(content)
+}
+
+fn be_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        bytes.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn be_u64(bytes: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(
+        bytes.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+/// m4b and m4a : duration of the `mvhd` box, Nero chapters (`chpl`) and iTunes tags (`ilst`)
+fn mp4_info(file: &mut File) -> Result<AudioInfo, String> {
+    let length = file
+        .metadata()
+        .map_err(|e| format!("unable to read file : {e}"))?
+        .len();
+    let top_boxes = mp4_boxes(file, 0, length);
+    let moov = find_box(&top_boxes, b"moov").ok_or("no moov box, not a mp4 file")?;
+    let moov_boxes = mp4_boxes(file, moov.start, moov.end);
+    let mut info = AudioInfo::default();
+    // duration
+    let mvhd = find_box(&moov_boxes, b"mvhd")
+        .and_then(|mvhd| read_box(file, mvhd))
+        .ok_or("no mvhd box")?;
+    let (timescale, duration) = match mvhd.first() {
+        Some(1) => (be_u32(&mvhd, 20), be_u64(&mvhd, 24)),
+        _ => (be_u32(&mvhd, 12), be_u32(&mvhd, 16).map(u64::from)),
+    };
+    if let (Some(timescale), Some(duration)) =
+        (timescale.filter(|&timescale| timescale > 0), duration)
+    {
+        info.duration = duration as f64 / timescale as f64;
+    }
+    // tags are in `moov/udta/meta`, sometimes in `moov/meta`
+    let udta_boxes = find_box(&moov_boxes, b"udta")
+        .map(|udta| mp4_boxes(file, udta.start, udta.end))
+        .unwrap_or_default();
+    if let Some(chpl) = find_box(&udta_boxes, b"chpl").and_then(|chpl| read_box(file, chpl)) {
+        info.chapters = nero_chapters(&chpl);
+    }
+    let meta = find_box(&udta_boxes, b"meta")
+        .map(|meta| (meta.start, meta.end))
+        .or(find_box(&moov_boxes, b"meta").map(|meta| (meta.start, meta.end)));
+    if let Some((start, end)) = meta {
+        // version and flags before the children of `meta`
+        let meta_boxes = mp4_boxes(file, start + 4, end);
+        let items = find_box(&meta_boxes, b"ilst")
+            .map(|ilst| mp4_boxes(file, ilst.start, ilst.end))
+            .unwrap_or_default();
+        for item in items {
+            let data = mp4_boxes(file, item.start, item.end)
+                .into_iter()
+                .find(|data| &data.kind == b"data")
+                .and_then(|data| read_box(file, &data));
+            // type and locale before the value
+            let Some(value) = data.as_ref().and_then(|data| data.get(8..)) else {
+                continue;
+            };
+            let text = || {
+                Some(String::from_utf8_lossy(value).trim().to_string())
+                    .filter(|text| !text.is_empty())
+            };
+            match &item.kind {
+                b"\xA9nam" => info.title = text(),
+                b"\xA9alb" => info.album = text(),
+                b"\xA9ART" | b"aART" if info.author.is_none() => info.author = text(),
+                b"covr" => info.cover = Some(value.to_vec()),
+                _ => (),
+            }
+        }
+    }
+    Ok(info)
+}
+
+/// chapters of a Nero `chpl` box, starts in 100 nanoseconds
+fn nero_chapters(chpl: &[u8]) -> Vec<Chapter> {
+    let mut chapters = Vec::new();
+    // version, flags and, from version 1, a reserved field
+    let mut offset = if chpl.first() == Some(&1) { 8 } else { 4 };
+    let count = chpl.get(offset).copied().unwrap_or_default();
+    offset += 1;
+    for _ in 0..count {
+        let (Some(start), Some(&title_length)) = (be_u64(chpl, offset), chpl.get(offset + 8))
+        else {
+            break;
+        };
+        let title_start = offset + 9;
+        let Some(title) = chpl.get(title_start..title_start + title_length as usize) else {
+            break;
+        };
+        chapters.push(Chapter {
+            title: String::from_utf8_lossy(title).trim().to_string(),
+            start: start as f64 / 10_000_000.0,
+        });
+        offset = title_start + title_length as usize;
+    }
+    chapters
+}
+
+/// mp3 : ID3v2 tags and chapters (`CHAP` frames), duration from the first frame
+fn mp3_info(file: &mut File) -> Result<AudioInfo, String> {
+    let length = file
+        .metadata()
+        .map_err(|e| format!("unable to read file : {e}"))?
+        .len();
+    let mut header = [0u8; 10];
+    file.read_exact(&mut header)
+        .map_err(|e| format!("unable to read file : {e}"))?;
+    let mut info = AudioInfo::default();
+    let mut audio_start = 0;
+    if &header[..3] == b"ID3" {
+        let tag_size = syncsafe(&header[6..10]) as u64;
+        // optional footer
+        let footer_size = if header[5] & 0x10 != 0 { 10 } else { 0 };
+        // size comes from the header, checked before allocating it
+        if tag_size > length.saturating_sub(10) {
+            return Err("ID3 tag larger than the file".to_string());
+        }
+        let mut tag = vec![0u8; tag_size as usize];
+        file.read_exact(&mut tag)
+            .map_err(|e| format!("unable to read ID3 tag : {e}"))?;
+        parse_id3(header[3], header[5], &tag, &mut info);
+        audio_start = 10 + tag_size + footer_size;
+    }
+    // first frames, enough to find a header after some padding
+    let mut frames = vec![0u8; 64 * 1024];
+    file.seek(SeekFrom::Start(audio_start))
+        .map_err(|e| format!("unable to read file : {e}"))?;
+    let read = file
+        .read(&mut frames)
+        .map_err(|e| format!("unable to read file : {e}"))?;
+    frames.truncate(read);
+    // ID3v1 tag at the end
+    let mut end_tag = [0u8; 3];
+    let has_id3v1 = length >= 128
+        && file.seek(SeekFrom::Start(length - 128)).is_ok()
+        && file.read_exact(&mut end_tag).is_ok()
+        && &end_tag == b"TAG";
+    let audio_length = length.saturating_sub(audio_start + if has_id3v1 { 128 } else { 0 });
+    info.duration = mp3_duration(&frames, audio_length).ok_or("no mp3 frame found")?;
+    Ok(info)
+}
+
+/// 7 bits per byte integers of ID3v2 sizes
+fn syncsafe(bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .fold(0, |size, byte| (size << 7) | (byte & 0x7F) as u32)
+}
+
+/// text of an ID3v2 frame, after its encoding byte
+fn id3_text(frame: &[u8]) -> Option<String> {
+    let (&encoding, text) = frame.split_first()?;
+    let text = match encoding {
+        // latin1
+        0 => text.iter().map(|&byte| byte as char).collect(),
+        1 | 2 => {
+            let (encoding, text) = match encoding_rs::Encoding::for_bom(text) {
+                Some((encoding, bom_length)) => (encoding, &text[bom_length..]),
+                None if encoding == 2 => (encoding_rs::UTF_16BE, text),
+                None => (encoding_rs::UTF_16LE, text),
+            };
+            encoding.decode_without_bom_handling(text).0.to_string()
+        }
+        _ => String::from_utf8_lossy(text).to_string(),
+    };
+    // ID3v2.4 separates multiple values with nulls, the first one is enough
+    let text = text
+        .split('\0')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+/// frames of an ID3v2.3 or ID3v2.4 tag (or of a `CHAP` frame)
+fn id3_frames(version: u8, data: &[u8]) -> Vec<([u8; 4], &[u8])> {
+    let mut frames = Vec::new();
+    let mut offset = 0;
+    while offset + 10 <= data.len() && data[offset] != 0 {
+        let id = [
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ];
+        let size_bytes = &data[offset + 4..offset + 8];
+        let size = match version {
+            4 => syncsafe(size_bytes),
+            _ => u32::from_be_bytes([size_bytes[0], size_bytes[1], size_bytes[2], size_bytes[3]]),
+        } as usize;
+        let start = offset + 10;
+        let Some(content) = data.get(start..start + size) else {
+            break;
+        };
+        frames.push((id, content));
+        offset = start + size;
+    }
+    frames
+}
+
+fn parse_id3(version: u8, flags: u8, tag: &[u8], info: &mut AudioInfo) {
+    // ID3v2.2 has 3 characters frames ids, audiobooks rarely use it
+    if !matches!(version, 3 | 4) {
+        return;
+    }
+    let mut frames_start = 0;
+    // extended header
+    if flags & 0x40 != 0 {
+        frames_start = match version {
+            4 => syncsafe(tag.get(..4).unwrap_or_default()) as usize,
+            _ => be_u32(tag, 0).unwrap_or_default() as usize + 4,
+        };
+    }
+    let Some(frames) = tag.get(frames_start..) else {
+        return;
+    };
+    for (id, content) in id3_frames(version, frames) {
+        match &id {
+            b"TIT2" => info.title = id3_text(content),
+            b"TALB" => info.album = id3_text(content),
+            b"TPE1" => info.author = id3_text(content),
+            b"TPE2" if info.author.is_none() => info.author = id3_text(content),
+            b"APIC" => {
+                if let Some((picture_type, picture)) = id3_picture(content) {
+                    // front cover first
+                    if info.cover.is_none() || picture_type == 3 {
+                        info.cover = Some(picture.to_vec());
+                    }
+                }
+            }
+            b"CHAP" => {
+                // element id, then start and end times in milliseconds, and offsets
+                let Some(id_end) = content.iter().position(|&byte| byte == 0) else {
+                    continue;
+                };
+                let Some(start) = be_u32(content, id_end + 1) else {
+                    continue;
+                };
+                let title = content
+                    .get(id_end + 17..)
+                    .map(|sub_frames| id3_frames(version, sub_frames))
+                    .unwrap_or_default()
+                    .into_iter()
+                    .find(|(id, _)| id == b"TIT2")
+                    .and_then(|(_, title)| id3_text(title))
+                    .unwrap_or_else(|| format!("Chapter {}", info.chapters.len() + 1));
+                info.chapters.push(Chapter {
+                    title,
+                    start: start as f64 / 1000.0,
+                });
+            }
+            _ => (),
+        }
+    }
+    info.chapters.sort_by(|a, b| a.start.total_cmp(&b.start));
+}
+
+/// picture type and data of an `APIC` frame
+fn id3_picture(frame: &[u8]) -> Option<(u8, &[u8])> {
+    let (&encoding, rest) = frame.split_first()?;
+    let mime_end = rest.iter().position(|&byte| byte == 0)?;
+    let (&picture_type, rest) = rest.get(mime_end + 1..)?.split_first()?;
+    // description, ended by a null character (two for utf-16)
+    let data_start = match encoding {
+        1 | 2 => rest
+            .chunks(2)
+            .position(|pair| pair == [0, 0])
+            .map(|index| index * 2 + 2)?,
+        _ => rest.iter().position(|&byte| byte == 0)? + 1,
+    };
+    Some((picture_type, rest.get(data_start..)?))
+}
+
+/// duration of mp3 audio data : frames count of a Xing or VBRI header, or constant bitrate
+fn mp3_duration(frames: &[u8], audio_length: u64) -> Option<f64> {
+    const MPEG1_BITRATES: [u32; 15] = [
+        0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+    ];
+    const MPEG2_BITRATES: [u32; 15] =
+        [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+    // first layer III frame header
+    let position = frames.windows(4).position(|header| {
+        header[0] == 0xFF
+            && header[1] & 0xE0 == 0xE0
+            // version not reserved, layer III
+            && (header[1] >> 3) & 0x03 != 1
+            && (header[1] >> 1) & 0x03 == 1
+            && (1..15).contains(&(header[2] >> 4))
+            && (header[2] >> 2) & 0x03 != 3
+    })?;
+    let header = &frames[position..position + 4];
+    let version = (header[1] >> 3) & 0x03;
+    let mpeg1 = version == 3;
+    let bitrate = match mpeg1 {
+        true => MPEG1_BITRATES,
+        false => MPEG2_BITRATES,
+    }[(header[2] >> 4) as usize]
+        * 1000;
+    let sample_rate = [44100, 48000, 32000][((header[2] >> 2) & 0x03) as usize]
+        / match version {
+            3 => 1,
+            2 => 2,
+            // MPEG 2.5
+            _ => 4,
+        };
+    let samples_per_frame = if mpeg1 { 1152 } else { 576 };
+    let mono = header[3] >> 6 == 3;
+    let side_information = match (mpeg1, mono) {
+        (true, false) => 32,
+        (true, true) | (false, false) => 17,
+        (false, true) => 9,
+    };
+    let xing = position + 4 + side_information;
+    let frame_count = match frames.get(xing..xing + 4) {
+        Some(b"Xing") | Some(b"Info") if be_u32(frames, xing + 4)? & 1 == 1 => {
+            be_u32(frames, xing + 8)
+        }
+        _ => match frames.get(position + 36..position + 40) {
+            Some(b"VBRI") => be_u32(frames, position + 36 + 14),
+            _ => None,
+        },
+    };
+    match frame_count {
+        Some(frame_count) => {
+            Some(frame_count as f64 * samples_per_frame as f64 / sample_rate as f64)
+        }
+        None => Some(audio_length.saturating_sub(position as u64) as f64 * 8.0 / bitrate as f64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn mp4_box(kind: &[u8; 4], content: &[u8]) -> Vec<u8> {
+        let mut mp4_box = ((content.len() + 8) as u32).to_be_bytes().to_vec();
+        mp4_box.extend_from_slice(kind);
+        mp4_box.extend_from_slice(content);
+        mp4_box
+    }
+
+    fn id3_frame(id: &[u8; 4], content: &[u8]) -> Vec<u8> {
+        let mut frame = id.to_vec();
+        frame.extend_from_slice(&(content.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&[0, 0]);
+        frame.extend_from_slice(content);
+        frame
+    }
+
+    #[test]
+    fn test_mp4_info() {
+        // mvhd version 0 : timescale 1000, duration 125.5 s
+        let mut mvhd = vec![0u8; 12];
+        mvhd.extend_from_slice(&1000u32.to_be_bytes());
+        mvhd.extend_from_slice(&125_500u32.to_be_bytes());
+        let mut chpl = vec![1, 0, 0, 0, 0, 0, 0, 0, 2];
+        for (start, title) in [(0u64, "Intro"), (600_000_000u64, "Chapitre 1")] {
+            chpl.extend_from_slice(&start.to_be_bytes());
+            chpl.push(title.len() as u8);
+            chpl.extend_from_slice(title.as_bytes());
+        }
+        let data = |value: &[u8]| mp4_box(b"data", &[&[0, 0, 0, 1, 0, 0, 0, 0], value].concat());
+        let ilst = [
+            mp4_box(b"\xA9nam", &data("Le Tour du monde".as_bytes())),
+            mp4_box(b"\xA9ART", &data("Jules Verne".as_bytes())),
+            mp4_box(b"covr", &data(b"\x89PNG")),
+        ]
+        .concat();
+        let meta = [
+            vec![0, 0, 0, 0],
+            mp4_box(b"hdlr", &[0; 25]),
+            mp4_box(b"ilst", &ilst),
+        ]
+        .concat();
+        let udta = [mp4_box(b"chpl", &chpl), mp4_box(b"meta", &meta)].concat();
+        let moov = [mp4_box(b"mvhd", &mvhd), mp4_box(b"udta", &udta)].concat();
+        let m4b = [
+            mp4_box(b"ftyp", b"M4B "),
+            mp4_box(b"mdat", &[0; 100]),
+            mp4_box(b"moov", &moov),
+        ]
+        .concat();
+        let path = Path::new("test_audio.m4b");
+        File::create(path).unwrap().write_all(&m4b).unwrap();
+        let info = audio_info(path).unwrap();
+        fs::remove_file(path).unwrap();
+        assert_eq!(info.duration, 125.5);
+        assert_eq!(
+            info.chapters,
+            vec![
+                Chapter {
+                    title: "Intro".to_string(),
+                    start: 0.0
+                },
+                Chapter {
+                    title: "Chapitre 1".to_string(),
+                    start: 60.0
+                }
+            ]
+        );
+        assert_eq!(info.metadata().title.as_deref(), Some("Le Tour du monde"));
+        assert_eq!(info.metadata().author.as_deref(), Some("Jules Verne"));
+        assert_eq!(info.cover, Some(b"\x89PNG".to_vec()));
+    }
+
+    #[test]
+    fn test_mp4_boxes_overflow() {
+        // 64 bits size overflowing the offset
+        let mut m4b = mp4_box(b"ftyp", b"M4B ");
+        m4b.extend_from_slice(&1u32.to_be_bytes());
+        m4b.extend_from_slice(b"moov");
+        m4b.extend_from_slice(&(u64::MAX - 4).to_be_bytes());
+        let path = Path::new("test_audio_overflow.m4b");
+        File::create(path).unwrap().write_all(&m4b).unwrap();
+        let mut file = File::open(path).unwrap();
+        let boxes = mp4_boxes(&mut file, 0, m4b.len() as u64);
+        fs::remove_file(path).unwrap();
+        assert_eq!(boxes.len(), 1);
+        assert_eq!(&boxes[0].kind, b"ftyp");
+    }
+
+    #[test]
+    fn test_mp3_info() {
+        let chapter = |id: &str, start: u32, title: &str| {
+            let mut chap = id.as_bytes().to_vec();
+            chap.push(0);
+            chap.extend_from_slice(&start.to_be_bytes());
+            chap.extend_from_slice(&(start + 1000).to_be_bytes());
+            chap.extend_from_slice(&[0xFF; 8]);
+            chap.extend(id3_frame(b"TIT2", &[&[3], title.as_bytes()].concat()));
+            id3_frame(b"CHAP", &chap)
+        };
+        let frames = [
+            id3_frame(b"TALB", b"\x00Michel Strogoff"),
+            // utf-16 with byte order mark
+            id3_frame(
+                b"TPE1",
+                b"\x01\xFF\xFEJ\x00.\x00 \x00V\x00e\x00r\x00n\x00e\x00",
+            ),
+            id3_frame(b"APIC", b"\x00image/png\x00\x03cover\x00\x89PNG"),
+            chapter("ch2", 90_000, "Deuxième partie"),
+            chapter("ch1", 0, "Première partie"),
+        ]
+        .concat();
+        let mut mp3 = b"ID3\x03\x00\x00".to_vec();
+        let size = frames.len() as u32;
+        mp3.extend([
+            (size >> 21) as u8 & 0x7F,
+            (size >> 14) as u8 & 0x7F,
+            (size >> 7) as u8 & 0x7F,
+            size as u8 & 0x7F,
+        ]);
+        mp3.extend(frames);
+        // MPEG 1 layer III, 128 kbps, 44.1 kHz, stereo, with a Xing header of 1000 frames
+        let mut frame = vec![0xFF, 0xFB, 0x90, 0x00];
+        frame.extend([0; 32]);
+        frame.extend(b"Xing\x00\x00\x00\x01");
+        frame.extend(1000u32.to_be_bytes());
+        frame.resize(417, 0);
+        mp3.extend(&frame);
+        let path = Path::new("test_audio.mp3");
+        File::create(path).unwrap().write_all(&mp3).unwrap();
+        let info = audio_info(path).unwrap();
+        assert_eq!(info.duration, 1000.0 * 1152.0 / 44100.0);
+        assert_eq!(info.metadata().title.as_deref(), Some("Michel Strogoff"));
+        assert_eq!(info.author.as_deref(), Some("J. Verne"));
+        assert_eq!(info.cover, Some(b"\x89PNG".to_vec()));
+        assert_eq!(
+            info.chapters
+                .iter()
+                .map(|chapter| (chapter.title.as_str(), chapter.start))
+                .collect::<Vec<_>>(),
+            vec![("Première partie", 0.0), ("Deuxième partie", 90.0)]
+        );
+        // constant bitrate without Xing header : 10 frames of 417 bytes at 128 kbps
+        let cbr = [vec![0xFF, 0xFB, 0x90, 0x00], vec![0; 4166]].concat();
+        File::create(path).unwrap().write_all(&cbr).unwrap();
+        let info = audio_info(path).unwrap();
+        assert_eq!(info.duration, 4170.0 * 8.0 / 128_000.0);
+        assert_eq!(info.title, None);
+        // ID3 tag of 256 MB in a file of a few bytes
+        let oversized = [b"ID3\x04\x00\x00\x7F\x7F\x7F\x7F".to_vec(), cbr].concat();
+        File::create(path).unwrap().write_all(&oversized).unwrap();
+        let info = audio_info(path);
+        fs::remove_file(path).unwrap();
+        assert!(info.is_err());
+    }
+
+    #[test]
+    fn test_folder_audio_list() {
+        let directory = Path::new("test_audiobook");
+        fs::create_dir_all(directory).unwrap();
+        for name in ["10 - fin.mp3", "2 - suite.mp3", "cover.jpg", ".DS_Store"] {
+            File::create(directory.join(name)).unwrap();
+        }
+        assert_eq!(
+            folder_audio_list(directory),
+            vec!["2 - suite.mp3", "10 - fin.mp3"]
+        );
+        File::create(directory.join("notes.txt")).unwrap();
+        assert!(folder_audio_list(directory).is_empty());
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,328 @@
+//! FictionBook 2 ebooks : one xml file (sometimes zipped as `.fb2.zip`), with base64 images
+
+use crate::html_render::xml_escape;
+use crate::scanner::FileMetadata;
+
+use roxmltree::{Document, Node, ParsingOptions};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+
+/// xml of a FictionBook, decoded from its declared encoding (often windows-1251)
+pub struct Fb2 {
+    xml: String,
+}
+
+impl Fb2 {
+    pub fn open(path: &str) -> Result<Fb2, String> {
+        let data = if path.to_lowercase().ends_with(".zip") {
+            let file = File::open(path).map_err(|e| format!("unable to open file : {e}"))?;
+            let mut archive =
+                zip::ZipArchive::new(file).map_err(|e| format!("unable to read zip : {e}"))?;
+            let name = archive
+                .file_names()
+                .find(|name| name.to_lowercase().ends_with(".fb2"))
+                .map(String::from)
+                .ok_or("no fb2 file in zip")?;
+            let mut data = Vec::new();
+            archive
+                .by_name(&name)
+                .and_then(|mut entry| Ok(entry.read_to_end(&mut data)?))
+                .map_err(|e| format!("unable to extract {name} : {e}"))?;
+            data
+        } else {
+            fs::read(path).map_err(|e| format!("unable to read file : {e}"))?
+        };
+        Ok(Fb2::from_bytes(&data))
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Fb2 {
+        // encoding from the xml declaration : `<?xml version="1.0" encoding="windows-1251"?>`
+        let declaration = String::from_utf8_lossy(&data[..data.len().min(200)]).to_lowercase();
+        let encoding = declaration
+            .split_once("encoding=")
+            .and_then(|(_, rest)| rest.get(1..))
+            .and_then(|rest| rest.split(['"', '\'']).next())
+            .and_then(|label| encoding_rs::Encoding::for_label(label.as_bytes()))
+            .unwrap_or(encoding_rs::UTF_8);
+        Fb2 {
+            xml: encoding.decode(data).0.to_string(),
+        }
+    }
+
+    fn document(&self) -> Result<Document<'_>, String> {
+        let options = ParsingOptions {
+            allow_dtd: true,
+            ..Default::default()
+        };
+        Document::parse_with_options(&self.xml, options)
+            .map_err(|e| format!("invalid fb2 file : {e}"))
+    }
+
+    /// title, authors, sequence and annotation of the `title-info` description
+    pub fn metadata(&self) -> Option<FileMetadata> {
+        let document = self.document().ok()?;
+        let title_info = find_path(document.root_element(), &["description", "title-info"])?;
+        let authors: Vec<String> = children(title_info, "author")
+            .filter_map(|author| {
+                let names: Vec<String> = ["first-name", "middle-name", "last-name"]
+                    .iter()
+                    .filter_map(|name| child(author, name).map(text))
+                    .filter(|name| !name.is_empty())
+                    .collect();
+                if names.is_empty() {
+                    child(author, "nickname").map(text)
+                } else {
+                    Some(names.join(" "))
+                }
+            })
+            .collect();
+        let sequence = child(title_info, "sequence");
+        Some(FileMetadata {
+            title: child(title_info, "book-title").map(text),
+            author: (!authors.is_empty()).then(|| authors.join(", ")),
+            series: sequence
+                .and_then(|sequence| sequence.attribute("name"))
+                .map(|name| name.trim().to_string()),
+            volume: sequence
+                .and_then(|sequence| sequence.attribute("number"))
+                .and_then(|number| number.trim().parse().ok()),
+            description: child(title_info, "annotation").map(|annotation| {
+                annotation
+                    .descendants()
+                    .filter(|node| node.has_tag_name("p"))
+                    .map(text)
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            }),
+        })
+    }
+
+    /// image of the `coverpage`, decoded from its binary
+    pub fn cover(&self) -> Option<Vec<u8>> {
+        use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+        let document = self.document().ok()?;
+        let coverpage = find_path(
+            document.root_element(),
+            &["description", "title-info", "coverpage"],
+        )?;
+        let image_id = child(coverpage, "image").and_then(image_id)?;
+        let binary = binaries(&document).remove(image_id)?;
+        BASE64.decode(without_whitespaces(binary.1)).ok()
+    }
+
+    /// sections of the main body, content before the first section goes in the first chapter
+    fn chapter_nodes<'a, 'input>(document: &'a Document<'input>) -> Vec<Vec<Node<'a, 'input>>> {
+        // other bodies are notes or comments
+        let Some(body) =
+            children(document.root_element(), "body").find(|body| body.attribute("name").is_none())
+        else {
+            return Vec::new();
+        };
+        let mut chapters: Vec<Vec<Node>> = vec![Vec::new()];
+        let mut first_section = true;
+        for node in body.children().filter(|node| node.is_element()) {
+            // the first section follows the body title
+            if node.has_tag_name("section") && !first_section {
+                chapters.push(vec![node]);
+            } else if let Some(chapter) = chapters.last_mut() {
+                first_section &= !node.has_tag_name("section");
+                chapter.push(node);
+            }
+        }
+        chapters.retain(|chapter| !chapter.is_empty());
+        chapters
+    }
+
+    pub fn chapter_count(&self) -> usize {
+        self.document()
+            .map(|document| Fb2::chapter_nodes(&document).len())
+            .unwrap_or_default()
+    }
+
+    /// html of a chapter, images embedded as data urls
+    pub fn chapter(&self, index: usize) -> Option<String> {
+        let document = self.document().ok()?;
+        let binaries = binaries(&document);
+        let chapter = Fb2::chapter_nodes(&document).into_iter().nth(index)?;
+        let mut html = String::new();
+        for node in chapter {
+            to_html(node, &binaries, &mut html);
+        }
+        Some(html)
+    }
+}
+
+fn children<'a, 'input>(
+    node: Node<'a, 'input>,
+    name: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(move |child| child.has_tag_name(name))
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &'static str) -> Option<Node<'a, 'input>> {
+    children(node, name).next()
+}
+
+fn find_path<'a, 'input>(
+    node: Node<'a, 'input>,
+    path: &[&'static str],
+) -> Option<Node<'a, 'input>> {
+    path.iter().try_fold(node, |node, name| child(node, name))
+}
+
+/// all the text of a node, with collapsed whitespaces
+fn text(node: Node) -> String {
+    node.descendants()
+        .filter(|node| node.is_text())
+        .filter_map(|node| node.text())
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+fn without_whitespaces(text: &str) -> String {
+    text.chars()
+        .filter(|character| !character.is_whitespace())
+        .collect()
+}
+
+/// `#cover.jpg` of `<image l:href="#cover.jpg"/>`, whatever the xlink prefix
+fn image_id<'a>(image: Node<'a, '_>) -> Option<&'a str> {
+    image
+        .attributes()
+        .find(|attribute| attribute.name() == "href")
+        .map(|attribute| attribute.value().trim_start_matches('#'))
+}
+
+/// content type and base64 data of binaries, by id
+fn binaries<'a>(document: &'a Document) -> HashMap<&'a str, (&'a str, &'a str)> {
+    children(document.root_element(), "binary")
+        .filter_map(|binary| {
+            Some((
+                binary.attribute("id")?,
+                (
+                    binary.attribute("content-type").unwrap_or("image/jpeg"),
+                    binary.text().unwrap_or_default(),
+                ),
+            ))
+        })
+        .collect()
+}
+
+/// FictionBook elements to html
+fn to_html(node: Node, binaries: &HashMap<&str, (&str, &str)>, html: &mut String) {
+    if let Some(text) = node.text().filter(|_| node.is_text()) {
+        html.push_str(&xml_escape(text));
+        return;
+    }
+    if !node.is_element() {
+        return;
+    }
+    let tag = match node.tag_name().name() {
+        "section" => "section",
+        "title" => "h2",
+        "subtitle" => "h3",
+        "p" | "v" | "text-author" => "p",
+        "emphasis" => "em",
+        "strong" => "strong",
+        "strikethrough" => "s",
+        "sub" => "sub",
+        "sup" => "sup",
+        "code" => "code",
+        "epigraph" | "cite" => "blockquote",
+        "poem" | "stanza" => "div",
+        "table" => "table",
+        "tr" => "tr",
+        "th" => "th",
+        "td" => "td",
+        "empty-line" => {
+            html.push_str("<br />");
+            return;
+        }
+        "image" => {
+            if let Some((content_type, data)) = image_id(node).and_then(|id| binaries.get(id)) {
+                html.push_str(&format!(
+                    "<img src=\"data:{};base64,{}\" />",
+                    xml_escape(content_type),
+                    xml_escape(&without_whitespaces(data))
+                ));
+            }
+            return;
+        }
+        // links to notes are kept as text, notes are not displayed
+        _ => {
+            for child in node.children() {
+                to_html(child, binaries, html);
+            }
+            return;
+        }
+    };
+    html.push_str(&format!("<{tag}>"));
+    for child in node.children() {
+        to_html(child, binaries, html);
+    }
+    html.push_str(&format!("</{tag}>"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FB2: &str = r##"<?xml version="1.0" encoding="windows-1251"?>
+<FictionBook xmlns="http://www.gribuser.ru/xml/fictionbook/2.0" xmlns:l="http://www.w3.org/1999/xlink">
+  <description>
+    <title-info>
+      <author><first-name>Лев</first-name><last-name>Толстой</last-name></author>
+      <book-title>Война и мир</book-title>
+      <annotation><p>Роман-эпопея.</p></annotation>
+      <coverpage><image l:href="#cover.png"/></coverpage>
+      <sequence name="Война и мир" number="2"/>
+    </title-info>
+  </description>
+  <body>
+    <title><p>Том второй</p></title>
+    <section><title><p>Часть первая</p></title><p>В начале <emphasis>1806</emphasis> года</p></section>
+    <section><p>Глава &amp; <a l:href="#n1">1</a></p><empty-line/><image l:href="#cover.png"/></section>
+  </body>
+  <body name="notes"><section id="n1"><p>note</p></section></body>
+  <binary id="cover.png" content-type="image/png">iVBO
+  Rw==</binary>
+</FictionBook>"##;
+
+    #[test]
+    fn test_fb2() {
+        let (windows_1251, _, _) = encoding_rs::WINDOWS_1251.encode(FB2);
+        let fb2 = Fb2::from_bytes(&windows_1251);
+        let metadata = fb2.metadata().unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("Война и мир"));
+        assert_eq!(metadata.author.as_deref(), Some("Лев Толстой"));
+        assert_eq!(metadata.series.as_deref(), Some("Война и мир"));
+        assert_eq!(metadata.volume, Some(2.0));
+        assert_eq!(metadata.description.as_deref(), Some("Роман-эпопея."));
+        assert_eq!(fb2.cover(), Some(b"\x89PNG".to_vec()));
+        assert_eq!(fb2.chapter_count(), 2);
+        assert_eq!(
+            fb2.chapter(0).unwrap(),
+            "<h2><p>Том второй</p></h2><section><h2><p>Часть первая</p></h2><p>В начале <em>1806</em> года</p></section>"
+        );
+        assert_eq!(
+            fb2.chapter(1).unwrap(),
+            "<section><p>Глава &amp; 1</p><br /><img src=\"data:image/png;base64,iVBORw==\" /></section>"
+        );
+        assert_eq!(fb2.chapter(2), None);
+    }
+
+    #[test]
+    fn test_fb2_image_escaped() {
+        let fb2 = Fb2::from_bytes(
+            br##"<FictionBook xmlns:l="http://www.w3.org/1999/xlink"><body><section><image l:href="#i"/></section></body><binary id="i" content-type="image/png">AA&quot; onerror=&quot;alert(1)</binary></FictionBook>"##,
+        );
+        assert_eq!(
+            fb2.chapter(0).unwrap(),
+            "<section><img src=\"data:image/png;base64,AA&quot;onerror=&quot;alert(1)\" /></section>"
+        );
+    }
+}
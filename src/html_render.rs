@@ -1,16 +1,29 @@
-use crate::http_server::{Role, User};
-use crate::scanner::{DirectoryInfo, FileInfo, Format, Library};
+use crate::audio::Chapter;
+use crate::http_server::{
+    Flag, ReaderFont, Role, SortOrder, Theme, Typography, User, View, PAGE_SIZES,
+};
+use crate::scanner::{
+    DirectoryInfo, FileInfo, Format, HiddenFormats, Library, ProblemFile, Series,
+};
+use crate::search::{Collection, SearchFilters};
+use crate::sqlite::{ReadingList, HIGHLIGHT_END, HIGHLIGHT_START};
+use crate::text::{TextPageUnit, TextPagination};
 
 use horrorshow::{helper::doctype, Raw, Template};
-use time::format_description;
+use std::collections::HashMap;
+use time::format_description::{self, well_known::Rfc3339};
 use time::OffsetDateTime;
 
-fn header<'a>(redirect_url: Option<&'a str>) -> Box<dyn horrorshow::RenderBox + 'a> {
+fn header<'a>(redirect_url: Option<&'a str>, theme: Theme) -> Box<dyn horrorshow::RenderBox + 'a> {
     box_html! {
         title : "Eloran";
         meta(charset="UTF-8");
         meta(name="viewport", content="width=device-width");
         link(rel="stylesheet", href="/css/eloran.css");
+        // light colors are the default ones
+        @ if theme != Theme::Light {
+            link(rel="stylesheet", href=format!("/css/theme-{}.css", theme.as_str()));
+        }
         // favicon
         link(rel="icon", type="image/png", href="/favicon-96x96.png", sizes="96x96");
         link(rel="icon", type="image/svgz+xml", href="/favicon.svgz");
@@ -39,25 +52,119 @@ pub fn simple_message(message: &str, origin: Option<&str>) -> String {
             p { : message; }
         }
     };
-    render(body_content, origin)
+    render(body_content, origin, Theme::default())
 }
 
-pub fn prefs(user: &User) -> String {
+pub fn prefs(user: &User, api_token: Option<String>, library_list: Vec<Library>) -> String {
+    let theme = user.preferences.theme;
+    let preferences = user.preferences.clone();
     let menu = menu(Some(user.to_owned()));
     let body_content = box_html! {
         : menu;
         h2 { : "Preferences" }
         div {
-            p { : "(todo) change password"; }
-            p { : "(todo) display all files or just readables"; }
-            p { : "(todo) grid or list view"; }
-            p { : "(todo) theme : dark or light"; }
+            form(accept-charset="utf-8", action="/prefs", method="post") {
+                label(for="view") : "Files listings";
+                select(name="view", id="view") {
+                    @ for view in View::ALL {
+                        option(value=view.as_str(), selected?=preferences.view == view) : view.as_str();
+                    }
+                }
+                label(for="theme") : "Theme";
+                select(name="theme", id="theme") {
+                    @ for theme in Theme::ALL {
+                        option(value=theme.as_str(), selected?=preferences.theme == theme) : theme.as_str();
+                    }
+                }
+                label {
+                    input(type="checkbox", name="readable_only", checked?=preferences.readable_only);
+                    : "Only readable files";
+                }
+                : hidden_formats_checkboxes(&preferences.hidden_formats);
+                input(type="submit", value="Save");
+            }
+        }
+        h2 { : "Reader" }
+        div {
+            form(accept-charset="utf-8", action="/prefs/reader", method="post") {
+                label(for="reader_font") : "Font";
+                select(name="reader_font", id="reader_font") {
+                    @ for font in ReaderFont::ALL {
+                        option(value=font.as_str(), selected?=preferences.typography.reader_font == font) : font.as_str();
+                    }
+                }
+                label(for="reader_font_size") : "Font size (%)";
+                input(type="number", name="reader_font_size", id="reader_font_size", min="50", max="300", step="10", value=preferences.typography.reader_font_size);
+                label(for="reader_line_height") : "Line height (%)";
+                input(type="number", name="reader_line_height", id="reader_line_height", min="100", max="300", step="10", value=preferences.typography.reader_line_height);
+                label(for="reader_margin") : "Margins (%)";
+                input(type="number", name="reader_margin", id="reader_margin", min="0", max="30", value=preferences.typography.reader_margin);
+                label {
+                    input(type="checkbox", name="reader_justify", checked?=preferences.typography.reader_justify);
+                    : "Justify text";
+                }
+                input(type="submit", value="Save");
+            }
+            style { : Raw(typography_css(&preferences.typography, "#reader-preview")); }
+            div(id="reader-preview") {
+                p { : "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua. Ut enim ad minim veniam, quis nostrud exercitation ullamco laboris nisi ut aliquip ex ea commodo consequat." }
+            }
+        }
+        h2 { : "Password" }
+        div {
+            form(accept-charset="utf-8", action="/prefs/password", method="post") {
+                input(type="password", name="current_password", placeholder="current password", required);
+                input(type="password", name="new_password", placeholder="new password", required);
+                input(type="password", name="confirmation", placeholder="new password again", required);
+                input(type="submit", value="Change password");
+            }
+        }
+        h2 { : "API token" }
+        div {
+            @ if let Some(api_token) = &api_token {
+                p { : "Atom feeds of recently added files :"; }
+                ul {
+                    @ for library in &library_list {
+                        li {
+                            a(href=format!("/feed/{}?token={api_token}", library.id)) : &library.name;
+                        }
+                    }
+                }
+            } else {
+                p { : "No token yet, it is needed to follow libraries in a feed reader"; }
+            }
+            form(action="/prefs/token", method="post") {
+                input(type="submit", value=if api_token.is_some() { "Revoke and generate a new token" } else { "Generate a token" });
+            }
         }
     };
-    render(body_content, None)
+    render(body_content, None, theme)
+}
+
+/// one `hide_{format}` checkbox per format, checked if hidden
+fn hidden_formats_checkboxes<'a>(
+    hidden_formats: &'a HiddenFormats,
+) -> Box<dyn horrorshow::RenderBox + 'a> {
+    box_html! {
+        fieldset(class="hidden-formats") {
+            legend : "Hidden formats";
+            @ for format in Format::ALL {
+                label {
+                    input(type="checkbox", name=format!("hide_{}", format.key()), checked?=hidden_formats.contains(&format));
+                    : format.as_str();
+                }
+            }
+        }
+    }
 }
 
-pub fn admin(user: &User, library_list: Vec<Library>, user_list: Vec<User>) -> String {
+pub fn admin(
+    user: &User,
+    library_list: Vec<Library>,
+    user_list: Vec<User>,
+    text_pagination: TextPagination,
+) -> String {
+    let theme = user.preferences.theme;
     let menu = menu(Some(user.to_owned()));
     let body_content = box_html! {
         : menu;
@@ -78,6 +185,10 @@ pub fn admin(user: &User, library_list: Vec<Library>, user_list: Vec<User>) -> S
                                 input(type="submit", name="covers", value="Disable Covers (todo)");
                             }
                         }
+                        form(action=format!("/admin/library/{}/formats", library.id), method="post") {
+                            : hidden_formats_checkboxes(&library.hidden_formats);
+                            input(type="submit", value="Save");
+                        }
                     }
                 }
                 li {
@@ -88,6 +199,10 @@ pub fn admin(user: &User, library_list: Vec<Library>, user_list: Vec<User>) -> S
                 }
             }
         }
+        h3 { : "Problem files" }
+        div {
+            a(href="/admin/problems") { : "Files which could not be read" }
+        }
         h3 { : "Options" }
         div {
             ul {
@@ -98,6 +213,18 @@ pub fn admin(user: &User, library_list: Vec<Library>, user_list: Vec<User>) -> S
                         input(type="submit", value="Update (todo)");
                     }
                 }
+                li {
+                    : "text and markdown pages size";
+                    form(accept-charset="utf-8", action="/admin/text_pagination", method="post") {
+                        input(type="number", name="text_page_size", min="10", max="50000", value=text_pagination.text_page_size, required);
+                        select(name="text_page_unit") {
+                            @ for unit in TextPageUnit::ALL {
+                                option(value=unit.as_str(), selected?=text_pagination.text_page_unit == unit) : unit.as_str();
+                            }
+                        }
+                        input(type="submit", value="Update");
+                    }
+                }
                 li {
                     : "periodic covers extraction sleep time";
                     form(accept-charset="utf-8", action="/extract_sleep_time", method="post") {
@@ -155,7 +282,7 @@ pub fn admin(user: &User, library_list: Vec<Library>, user_list: Vec<User>) -> S
         }
     };
 
-    render(body_content, None)
+    render(body_content, None, theme)
 }
 
 pub fn login_form() -> String {
@@ -183,7 +310,7 @@ pub fn login_form() -> String {
         }
     };
 
-    render(body_content, None)
+    render(body_content, None, Theme::default())
 }
 
 pub fn logout() -> String {
@@ -194,7 +321,55 @@ pub fn logout() -> String {
     };
 
     let redirect_url = "/";
-    render(body_content, Some(redirect_url))
+    render(body_content, Some(redirect_url), Theme::default())
+}
+
+/// human readable file size without lib
+fn pretty_size(size: i64) -> String {
+    if size < 1024 * 1024 {
+        format!("{:.3} kB", size as f32 / 1024.00)
+    } else if size < 1024 * 1024 * 1024 {
+        format!("{} MB", size / 1024 / 1024)
+    } else {
+        format!("{:.1} GB", size as f64 / 1024.00 / 1024.00 / 1024.00)
+    }
+}
+
+/// files whose extraction failed, they can be flagged to be extracted again
+pub fn problem_files(user: &User, problem_files: Vec<ProblemFile>) -> String {
+    let theme = user.preferences.theme;
+    let menu = menu(Some(user.to_owned()));
+    let body_content = box_html! {
+        : menu;
+        h2 { : "Problem files" }
+        @ if problem_files.is_empty() {
+            p { : "No problem found while extracting files" }
+        } else {
+            form(action="/admin/problems", method="post") {
+                input(type="submit", value="Extract again");
+            }
+            table(class="files-list") {
+                tr {
+                    th { : "File" }
+                    th { : "Directory" }
+                    th { : "Problem" }
+                    th { : "Date" }
+                }
+                @ for problem_file in &problem_files {
+                    tr {
+                        td {
+                            a(href=format!("/infos/{}", problem_file.file.id)) { : &problem_file.file.name }
+                        }
+                        td { : &problem_file.file.parent_path }
+                        td { : &problem_file.problem }
+                        td { : timestamp_to_pretty_date(problem_file.date).unwrap_or_default() }
+                    }
+                }
+            }
+        }
+    };
+
+    render(body_content, None, theme)
 }
 
 fn timestamp_to_pretty_date(timestamp: i64) -> Option<String> {
@@ -206,6 +381,15 @@ fn timestamp_to_pretty_date(timestamp: i64) -> Option<String> {
     Some(pretty_added_date)
 }
 
+/// `3` rather than `3.0`, but keep `4.5`
+fn pretty_volume(volume: f64) -> String {
+    if volume.fract() == 0.0 {
+        format!("{volume:.0}")
+    } else {
+        volume.to_string()
+    }
+}
+
 pub fn file_info(
     user: &User,
     file: &FileInfo,
@@ -213,7 +397,9 @@ pub fn file_info(
     bookmark_status: bool,
     read_status: bool,
     up_link: String,
+    series: Option<(i64, String, Option<f64>)>,
 ) -> String {
+    let theme = user.preferences.theme;
     let menu = menu(Some(user.to_owned()));
     // we need to clone file infos, don't remember why...
     let file = file.clone();
@@ -222,14 +408,7 @@ pub fn file_info(
         Some(pretty_added_date) => pretty_added_date,
         None => String::from("not available"),
     };
-    // human readable file size without lib
-    let pretty_file_size = if file.size < 1024 * 1024 {
-        format!("{:.3} kB", file.size as f32 / 1024.00)
-    } else if file.size >= 1024 && file.size < 1024 * 1024 * 1024 {
-        format!("{} MB", file.size / 1024 / 1024)
-    } else {
-        format!("{:.1} GB", file.size as f64 / 1024.00 / 1024.00 / 1024.00)
-    };
+    let pretty_file_size = pretty_size(file.size);
     // construct file library path for breadcrumb
     let mut breadcrumb_link_path = String::new();
     // separe path elements, delete absolute path...
@@ -278,7 +457,7 @@ pub fn file_info(
                 }
                 // TODO put search elsewhere in code ?
                 div(class="search") {
-                    form(accept-charset="utf-8", action="/search", method="post") {
+                    form(accept-charset="utf-8", action="/search", method="get") {
                         input(type="submit", value="");
                         input(type="text", placeholder="Search...", name="query", value="");
                     }
@@ -294,7 +473,7 @@ pub fn file_info(
                     a(href= up_link , class="navigation") : "↖️  up";
                     : " | " ;
                     @ if file.format != Format::Pdf {
-                        a(href=format!("/read/{}/{}", file.id, current_page), class="navigation") : "📖 read file";
+                        a(href=format!("/read/{}/{}", file.id, current_page), class="navigation") : if file.format.is_audio() { "🎧 listen" } else { "📖 read file" };
                         : " | " ;
                     }
                     a(href=format!("/download/{}", file.id), class="navigation") : "⤵ download";
@@ -302,6 +481,8 @@ pub fn file_info(
                     a(href=format!("/toggle/bookmark/{}", file.id)) : if bookmark_status { "⭐ (remove from bookmarks)" } else { "(bookmark)" } ;
                     : " | " ;
                     a(href=format!("/toggle/read_status/{}", file.id)) : if read_status { "✅ (mark as unread)" } else { "(mark as read)" };
+                    : " | " ;
+                    a(href=format!("/lists/file/{}", file.id), class="navigation") : "📚 lists";
                 }
                 br;
                 br;
@@ -322,37 +503,48 @@ pub fn file_info(
                     br;
                     : format!("size : {}", pretty_file_size) ;
                     br;
-                    : format!("pages : {}/{}", current_page, file.total_pages) ;
+                    @ if file.format.is_audio() {
+                        : format!("listened : {}/{}", duration(current_page), duration(file.total_pages)) ;
+                    } else {
+                        : format!("pages : {}/{}", current_page, file.total_pages) ;
+                    }
                     br;
                     : format!("type : {}", file.format) ;
                     br;
                     : format!("added : {}", pretty_added_date) ;
+                    @ if let Some((series_id, series_name, volume)) = &series {
+                        br;
+                        : "series : " ;
+                        a(href=format!("/series/{series_id}")) : series_name ;
+                        @ if let Some(volume) = volume {
+                            : format!(" (volume {})", pretty_volume(*volume)) ;
+                        }
+                    }
                 }
             }
         }
     };
-    render(body_content, None)
+    render(body_content, None, theme)
 }
 
-pub fn flag_toggle(user: &User, flag_status: bool, file_id: &str, flag: &str) -> String {
+pub fn flag_toggle(user: &User, flag_status: bool, flag: &Flag, redirect_url: &str) -> String {
+    let theme = user.preferences.theme;
     let menu = menu(Some(user.to_owned()));
-    // TODO create enum for flag...
     let flag_response = match flag {
-        "bookmark" => {
+        Flag::Bookmark => {
             if flag_status {
                 "Bookmark added"
             } else {
                 "Bookmark deleted"
             }
         }
-        "read_status" => {
+        Flag::ReadStatus => {
             if flag_status {
                 "Marked as read"
             } else {
                 "Marked as unread"
             }
         }
-        _ => "",
     };
     let body_content = box_html! {
         : menu;
@@ -362,11 +554,88 @@ pub fn flag_toggle(user: &User, flag_status: bool, file_id: &str, flag: &str) ->
             }
         }
     };
-    let redirect_url = format!("/infos/{file_id}");
-    render(body_content, Some(&redirect_url))
+    render(body_content, Some(redirect_url), theme)
+}
+
+/// on the last page, links to the following file of each reading list
+fn next_in_lists<'a>(
+    next_files: &[(ReadingList, FileInfo)],
+) -> Box<dyn horrorshow::RenderBox + 'a> {
+    let next_files = next_files.to_owned();
+    box_html! {
+        @ if !next_files.is_empty() {
+            ul(class="next-in-list") {
+                @ for (reading_list, next_file) in &next_files {
+                    li {
+                        a(href=format!("/infos/{}", next_file.id), class="navigation") : format!("Next in {} : {} ⏩", reading_list.name, next_file.name);
+                    }
+                }
+            }
+        }
+    }
 }
 
-pub fn comic_reader(user: &User, file: &FileInfo, page: i32) -> String {
+pub fn comic_reader(
+    user: &User,
+    file: &FileInfo,
+    page: i32,
+    next_files: &[(ReadingList, FileInfo)],
+) -> String {
+    let next_in_lists = next_in_lists(next_files);
+    // a loose image can be browsed with the other images of its directory
+    let directory_images =
+        (file.format == Format::Jpg).then(|| format!("/directory_images/{}", file.id));
+    let footer = box_html! {
+        @ if let Some(directory_images) = directory_images {
+            p(align="center") {
+                a(href=directory_images) : "view all images in this folder";
+            }
+        }
+        : next_in_lists;
+    };
+    pages_reader(
+        user,
+        file,
+        page,
+        file.total_pages,
+        [
+            format!("/read/{}", file.id),
+            format!("/comic_page/{}", file.id),
+        ],
+        footer,
+    )
+}
+
+/// loose images of the directory of a file, in the comic reader
+pub fn directory_images_reader(
+    user: &User,
+    file: &FileInfo,
+    page: i32,
+    image_count: i32,
+) -> String {
+    pages_reader(
+        user,
+        file,
+        page,
+        image_count,
+        [
+            format!("/directory_images/{}", file.id),
+            format!("/directory_image/{}", file.id),
+        ],
+        box_html! {},
+    )
+}
+
+/// images reader, pages links are `{read_url}/{page}` and images `{image_url}/{page}/{size}`
+fn pages_reader(
+    user: &User,
+    file: &FileInfo,
+    page: i32,
+    total_pages: i32,
+    [read_url, image_url]: [String; 2],
+    footer: Box<dyn horrorshow::RenderBox>,
+) -> String {
+    let theme = user.preferences.theme;
     let menu = menu(Some(user.to_owned()));
     let file = file.clone();
     // don't go outside the range of the book
@@ -374,47 +643,57 @@ pub fn comic_reader(user: &User, file: &FileInfo, page: i32) -> String {
         0 => 0,
         _ => page - 1,
     };
-    let next_page = if page < file.total_pages - 1 {
+    let next_page = if page < total_pages - 1 {
         page + 1
     } else {
-        file.total_pages - 1
+        total_pages - 1
     };
     let body_content = box_html! {
         : menu;
         h1(id="navigation", align="center") {
             // TODO go to page number
-            a(href=format!("/read/{}/{}", file.id, previous_page), class="navigation") : "⏪";
+            a(href=format!("{}/{}", read_url, previous_page), class="navigation") : "⏪";
             : " | " ;
-            a(href=format!("/read/{}/{}", file.id, 0), class="navigation") : "⏮ start";
+            a(href=format!("{}/{}", read_url, 0), class="navigation") : "⏮ start";
             : " | " ;
             a(href=format!("/infos/{}", file.id), class="navigation") : "return to file info";
             : " | " ;
-            a(href=format!("/read/{}/{}", file.id, file.total_pages - 1), class="navigation") : "end ⏭";
+            a(href=format!("{}/{}", read_url, total_pages - 1), class="navigation") : "end ⏭";
             : " | " ;
-            a(href=format!("/read/{}/{}", file.id, next_page), class="navigation") : "⏩";
+            a(href=format!("{}/{}", read_url, next_page), class="navigation") : "⏩";
         }
         br;
         br;
         div(class="navigation-map") {
             picture {
-                source(srcset=format!("/comic_page/{}/{}/800px", file.id, page), media="(max-width: 800px)", class="comic-content");
-                source(srcset=format!("/comic_page/{}/{}/1000px", file.id, page), media="(max-width: 1000px)", class="comic-content");
-                source(srcset=format!("/comic_page/{}/{}/orig", file.id, page), class="comic-content");
-                img(src=format!("/comic_page/{}/{}/orig", file.id, page), alt="TODO_PAGE_NUM", class="comic-content", usemap="navigation-map");
+                source(srcset=format!("{}/{}/800px", image_url, page), media="(max-width: 800px)", class="comic-content");
+                source(srcset=format!("{}/{}/1000px", image_url, page), media="(max-width: 1000px)", class="comic-content");
+                source(srcset=format!("{}/{}/orig", image_url, page), class="comic-content");
+                img(src=format!("{}/{}/orig", image_url, page), alt="TODO_PAGE_NUM", class="comic-content", usemap="navigation-map");
                 // not a html map, because we need percentage coords
                 // thx https://stackoverflow.com/a/26231487
                 a(href="", style="top: 0%; left: 30%; width: 40%; height: 3%;") : ""; // zone for menu
-                a(href=format!("/read/{}/{}", file.id, previous_page), style="top: 0%; left: 0%; width: 30%; height: 100%;") : "";
-                a(href=format!("/read/{}/{}", file.id, next_page), style="top: 0%; left: 70%; width: 30%; height: 100%;") : "";
+                a(href=format!("{}/{}", read_url, previous_page), style="top: 0%; left: 0%; width: 30%; height: 100%;") : "";
+                a(href=format!("{}/{}", read_url, next_page), style="top: 0%; left: 70%; width: 30%; height: 100%;") : "";
             }
         }
+        : footer;
     };
-    render(body_content, None)
+    render(body_content, None, theme)
 }
 
-pub fn ebook_reader(user: &User, file: &FileInfo, epub_content: &str, page: i32) -> String {
+pub fn ebook_reader(
+    user: &User,
+    file: &FileInfo,
+    epub_content: &str,
+    page: i32,
+    next_files: &[(ReadingList, FileInfo)],
+) -> String {
+    let theme = user.preferences.theme;
     let menu = menu(Some(user.to_owned()));
+    let next_in_lists = next_in_lists(next_files);
     let epub_content = epub_content.to_string();
+    let typography_css = typography_css(&user.preferences.typography, "#epub-content");
     let file = file.clone();
     // don't go outside the range of the book
     let previous_page = match page {
@@ -441,25 +720,788 @@ pub fn ebook_reader(user: &User, file: &FileInfo, epub_content: &str, page: i32)
             : " | " ;
             a(href=format!("/read/{}/{}", file.id, next_page), class="navigation") : "➡️";
         }
+        style { : Raw(typography_css); }
         div(id="epub-content") {
             p {: Raw(epub_content); }
         }
+        : next_in_lists;
+    };
+    render(body_content, None, theme)
+}
+
+/// player of audiobooks : chapters, position restored and saved while listening
+const AUDIO_PLAYER_JS: &str = r#"
+const player = document.getElementById("player");
+const starts = player.dataset.starts.split(",").map(Number);
+let track = -1;
+let saved = Number(player.dataset.position);
+// go to a position of the audiobook, in the track holding it
+function seek(position, play) {
+    let index = starts.length - 1;
+    while (index > 0 && starts[index] > position) index--;
+    const offset = position - starts[index];
+    const start = () => { player.currentTime = offset; if (play) player.play(); };
+    if (index !== track) {
+        track = index;
+        player.src = starts.length > 1 ? `${player.dataset.url}/${index}` : player.dataset.url;
+        player.addEventListener("loadedmetadata", start, { once: true });
+    } else {
+        start();
+    }
+}
+function save(position) {
+    position = Math.floor(position ?? starts[track] + player.currentTime);
+    if (position !== saved) {
+        saved = position;
+        navigator.sendBeacon(`/listen/${player.dataset.file}/${position}`);
+    }
+}
+player.addEventListener("timeupdate", () => {
+    if (Math.abs(starts[track] + player.currentTime - saved) >= 15) save();
+});
+player.addEventListener("pause", () => save());
+player.addEventListener("ended", () => {
+    if (track + 1 < starts.length) seek(starts[track + 1], true);
+    else save(Number(player.dataset.duration));
+});
+document.addEventListener("visibilitychange", () => {
+    if (document.visibilityState === "hidden") save();
+});
+document.querySelectorAll("[data-start]").forEach((link) => link.addEventListener("click", (event) => {
+    event.preventDefault();
+    seek(Number(link.dataset.start), true);
+}));
+seek(saved, false);
+"#;
+
+/// `1:02:03` from seconds
+fn duration(seconds: i32) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    match hours {
+        0 => format!("{minutes}:{seconds:02}"),
+        _ => format!("{hours}:{minutes:02}:{seconds:02}"),
+    }
+}
+
+/// audiobooks player, from a position in seconds
+pub fn audio_player(
+    user: &User,
+    file: &FileInfo,
+    position: i32,
+    chapters: Vec<Chapter>,
+    next_files: &[(ReadingList, FileInfo)],
+) -> String {
+    let theme = user.preferences.theme;
+    let menu = menu(Some(user.to_owned()));
+    let next_in_lists = next_in_lists(next_files);
+    let file = file.clone();
+    // tracks of audiobooks directories are their chapters
+    let starts = match file.format {
+        Format::Audiobook => chapters
+            .iter()
+            .map(|chapter| chapter.start.to_string())
+            .collect::<Vec<String>>()
+            .join(","),
+        _ => "0".to_string(),
+    };
+    let body_content = box_html! {
+        : menu;
+        h1(id="navigation", align="center") {
+            a(href=format!("/infos/{}", file.id), class="navigation") : "close";
+        }
+        div(id="audio-player", align="center") {
+            img(src=format!("/cover/{}", file.id), alt="cover", class="infos");
+            p : &file.name;
+            audio(id="player", controls, preload="metadata", data-file=&file.id, data-url=format!("/audio/{}", file.id),
+                data-starts=starts, data-position=position, data-duration=file.total_pages) {
+                : "your browser does not play audio";
+            }
+            @ if !chapters.is_empty() {
+                ol(id="chapters") {
+                    @ for chapter in &chapters {
+                        li {
+                            a(href="#", data-start=chapter.start) : &chapter.title;
+                            : format!(" ({})", duration(chapter.start as i32));
+                        }
+                    }
+                }
+            }
+        }
+        : next_in_lists;
+        script { : Raw(AUDIO_PLAYER_JS); }
+    };
+    render(body_content, None, theme)
+}
+
+/// reader typography of a user as css rules for the element matching selector
+fn typography_css(typography: &Typography, selector: &str) -> String {
+    let mut css = format!(
+        "{selector} {{ font-size: {}%; line-height: {}; margin: 0 {}%; }}",
+        typography.reader_font_size,
+        // unitless, so it scales with the font size of each element
+        typography.reader_line_height as f64 / 100.0,
+        typography.reader_margin
+    );
+    // override the book styles on every element
+    css.push_str(&format!(" {selector} * {{ line-height: inherit;"));
+    if let Some(font_family) = typography.reader_font.font_family() {
+        css.push_str(&format!(" font-family: {font_family};"));
+    }
+    if typography.reader_justify {
+        css.push_str(" text-align: justify;");
+    }
+    css.push_str(" }");
+    css
+}
+
+/// files added recently, grouped by day, with library and format filters
+pub fn recently_added(
+    user: &User,
+    files: Vec<FileInfo>,
+    library_names: Vec<String>,
+    library: Option<String>,
+    format: Option<String>,
+) -> String {
+    let theme = user.preferences.theme;
+    let menu = menu(Some(user.to_owned()));
+    // files are already sorted by date
+    let mut days: Vec<(String, Vec<FileInfo>)> = Vec::new();
+    for file in files {
+        let day = timestamp_to_pretty_date(file.added_date).unwrap_or_default();
+        match days.last_mut() {
+            Some((last_day, day_files)) if *last_day == day => day_files.push(file),
+            _ => days.push((day, vec![file])),
+        }
+    }
+    let is_empty = days.is_empty();
+    let days: Vec<Box<dyn horrorshow::RenderBox>> = days
+        .into_iter()
+        .map(|(day, files)| files_row(day, files.into_iter().map(|file| (file, None)).collect()))
+        .collect();
+    let body_content = box_html! {
+        : menu;
+        main {
+            header {
+                a(href="/library") {
+                    img(src="/images/library-icon.svgz") ;
+                    h1 { : "Recently added" }
+                }
+            }
+            form(class="search-filters", accept-charset="utf-8", action="/recent", method="get") {
+                select(name="library") {
+                    option(value="") : "All libraries";
+                    @ for library_name in &library_names {
+                        option(value=library_name, selected?=library.as_ref() == Some(library_name)) : library_name;
+                    }
+                }
+                select(name="format") {
+                    option(value="") : "All formats";
+                    @ for file_format in Format::READABLE {
+                        option(value=file_format.as_str(), selected?=format.as_deref() == Some(file_format.as_str())) : file_format.as_str();
+                    }
+                }
+                input(type="submit", value="Filter");
+            }
+            @ if is_empty {
+                p(class="search-results") : "Nothing added yet";
+            }
+            @ for day in days {
+                : day;
+            }
+        }
+    };
+    render(body_content, None, theme)
+}
+
+/// escape text for xml content and attributes
+pub fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// unix timestamp to atom date format
+fn timestamp_to_rfc3339(timestamp: i64) -> String {
+    OffsetDateTime::from_unix_timestamp(timestamp)
+        .ok()
+        .and_then(|date| date.format(&Rfc3339).ok())
+        .unwrap_or_else(|| String::from("1970-01-01T00:00:00Z"))
+}
+
+/// atom feed of files recently added to a library, `base_url` is used for links
+pub fn atom_feed(library: &Library, files: &[FileInfo], base_url: &str) -> String {
+    // built from request headers
+    let base_url = xml_escape(base_url);
+    let updated = files.first().map_or(0, |file| file.added_date);
+    let mut feed = format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Eloran - {}</title>
+  <id>urn:eloran:library:{}</id>
+  <updated>{}</updated>
+  <link href="{base_url}/recent?library={}"/>
+"#,
+        xml_escape(&library.name),
+        library.id,
+        timestamp_to_rfc3339(updated),
+        xml_escape(&urlencoding::encode(&library.name)),
+    );
+    for file in files {
+        feed.push_str(&format!(
+            r#"  <entry>
+    <title>{}</title>
+    <id>urn:eloran:file:{}</id>
+    <updated>{}</updated>
+    <link href="{base_url}/infos/{}"/>
+    <summary>{}, {} pages</summary>
+  </entry>
+"#,
+            xml_escape(&file.name),
+            file.id,
+            timestamp_to_rfc3339(file.added_date),
+            file.id,
+            file.format,
+            file.total_pages,
+        ));
+    }
+    feed.push_str("</feed>\n");
+    feed
+}
+
+pub struct HomeDisplay {
+    pub user: User,
+    // file and current page
+    pub continue_reading: Vec<(FileInfo, i32)>,
+    pub next_to_read: Vec<FileInfo>,
+    pub recently_added: Vec<FileInfo>,
+    pub random_unread: Vec<FileInfo>,
+}
+
+/// a row of covers with a title, and an optional progress in percent
+fn files_row<'a>(
+    title: String,
+    files: Vec<(FileInfo, Option<i32>)>,
+) -> Box<dyn horrorshow::RenderBox + 'a> {
+    box_html! {
+        @ if !files.is_empty() {
+            section(class="home-row") {
+                h2 : title;
+                div(class="gallery") {
+                    @ for (file, progress) in &files {
+                        article(class="file") {
+                            a(href=format!("/infos/{}", file.id)) {
+                                div(class="cover") {
+                                    img(src=format!("/cover/{}", file.id), alt="blurred background cover", class="blurred-background");
+                                    img(src=format!("/cover/{}", file.id), alt="cover", class="cover");
+                                }
+                            }
+                            div(class="title") {
+                                h2 : &file.name;
+                            }
+                            @ if let Some(progress) = progress {
+                                div(class="progress", title=format!("{progress}%")) {
+                                    span(style=format!("width: {progress}%;")) {}
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub fn home(home_to_display: HomeDisplay) -> String {
+    let theme = home_to_display.user.preferences.theme;
+    let menu = menu(Some(home_to_display.user.to_owned()));
+    // pages start at 0
+    let continue_reading = home_to_display
+        .continue_reading
+        .into_iter()
+        .map(|(file, page)| {
+            let progress = if file.total_pages > 0 {
+                (page + 1) * 100 / file.total_pages
+            } else {
+                0
+            };
+            (file, Some(progress))
+        })
+        .collect();
+    let without_progress =
+        |files: Vec<FileInfo>| files.into_iter().map(|file| (file, None)).collect();
+    // nothing recently added means empty libraries
+    let is_empty = home_to_display.recently_added.is_empty();
+    let continue_reading = files_row(String::from("Continue reading"), continue_reading);
+    let next_to_read = files_row(
+        String::from("Next to read"),
+        without_progress(home_to_display.next_to_read),
+    );
+    let recently_added = files_row(
+        String::from("Recently added"),
+        without_progress(home_to_display.recently_added),
+    );
+    let random_unread = files_row(
+        String::from("Random unread pick"),
+        without_progress(home_to_display.random_unread),
+    );
+    let body_content = box_html! {
+        : menu;
+        main {
+            header {
+                a(href="/library") {
+                    img(src="/images/library-icon.svgz") ;
+                    h1 { : "Home" }
+                }
+            }
+            @ if is_empty {
+                p(class="search-results") {
+                    a(href="/library") : "Nothing here yet, please browse the library";
+                }
+            }
+            : continue_reading;
+            : next_to_read;
+            : recently_added;
+            : random_unread;
+        }
+    };
+    render(body_content, None, theme)
+}
+
+/// all series, with read counts
+pub fn series_list(user: &User, series_list: &[Series]) -> String {
+    let theme = user.preferences.theme;
+    let menu = menu(Some(user.to_owned()));
+    let series_list = series_list.to_owned();
+    let body_content = box_html! {
+        : menu;
+        main {
+            header {
+                a(href="/library") {
+                    img(src="/images/library-icon.svgz") ;
+                    h1 { : "Series" }
+                }
+            }
+            @ if series_list.is_empty() {
+                p(class="search-results") : "No series found yet";
+            }
+            section(class="gallery") {
+                @ for series in &series_list {
+                    article(class="folder") {
+                        a(href=format!("/series/{}", series.id)) {
+                            div(class= if series.read_count > 0 && series.read_count == series.file_count {
+                                "cover read"
+                            } else {
+                                "cover"
+                            }) {
+                                span(class="folder-img") {}
+                                span(class="folder-nb-items") {
+                                    @ if series.read_count > 0 {
+                                        : format!("{}/{}", series.read_count, series.file_count);
+                                    } else {
+                                        : series.file_count;
+                                    }
+                                }
+                            }
+                            div(class="title") {
+                                h2 : &series.name;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+    render(body_content, None, theme)
+}
+
+/// lists of the user, and lists shared by others
+pub fn reading_lists(user: &User, reading_lists: &[ReadingList]) -> String {
+    let theme = user.preferences.theme;
+    let menu = menu(Some(user.to_owned()));
+    let reading_lists = reading_lists.to_owned();
+    let user_id = user.id;
+    let body_content = box_html! {
+        : menu;
+        main {
+            header {
+                a(href="/library") {
+                    img(src="/images/library-icon.svgz") ;
+                    h1 { : "Reading lists" }
+                }
+            }
+            form(class="collection", accept-charset="utf-8", action="/lists/new", method="post") {
+                input(type="text", name="name", placeholder="List name", required);
+                label { input(type="checkbox", name="shared", value="on"); : "shared" }
+                input(type="submit", value="Create list");
+            }
+            @ if reading_lists.is_empty() {
+                p(class="search-results") : "No reading list yet";
+            } else {
+                ul(class="reading-lists") {
+                    @ for reading_list in &reading_lists {
+                        li {
+                            a(href=format!("/lists/{}", reading_list.id)) : &reading_list.name;
+                            @ if reading_list.user_id != user_id {
+                                : format!(" (shared by {})", reading_list.owner_name);
+                            } else if reading_list.shared {
+                                : " (shared)";
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+    render(body_content, None, theme)
+}
+
+/// files of a list in order, with read status, editable by its owner only
+pub fn reading_list(user: &User, reading_list: &ReadingList, files: &[(FileInfo, bool)]) -> String {
+    let theme = user.preferences.theme;
+    let menu = menu(Some(user.to_owned()));
+    let reading_list = reading_list.to_owned();
+    let files = files.to_owned();
+    let editable = reading_list.user_id == user.id;
+    // read in sequence, from the first unread file
+    let next_unread = files
+        .iter()
+        .find(|(_, read)| !read)
+        .map(|(file, _)| file.id.clone());
+    let last_position = files.len();
+    let body_content = box_html! {
+        : menu;
+        main {
+            header {
+                a(href="/lists") {
+                    img(src="/images/library-icon.svgz") ;
+                    h1 { : &reading_list.name }
+                }
+            }
+            @ if editable {
+                form(class="collection", accept-charset="utf-8", action=format!("/lists/{}/edit", reading_list.id), method="post") {
+                    input(type="text", name="name", value=&reading_list.name, required);
+                    label { input(type="checkbox", name="shared", value="on", checked?=reading_list.shared); : "shared" }
+                    input(type="submit", value="Save");
+                }
+                form(class="collection", action=format!("/lists/{}/delete", reading_list.id), method="post") {
+                    input(type="submit", value="Delete list");
+                }
+            } else {
+                p(class="search-results") : format!("shared by {}", reading_list.owner_name);
+            }
+            @ if let Some(file_id) = &next_unread {
+                p(class="search-results") {
+                    a(href=format!("/infos/{file_id}"), class="navigation") : "📖 read in order";
+                }
+            }
+            ol(class="reading-list") {
+                @ for (position, (file, read)) in files.iter().enumerate() {
+                    li(class= if *read { "read" } else { "" }) {
+                        a(href=format!("/infos/{}", file.id)) {
+                            img(src=format!("/cover/{}", file.id), alt="cover");
+                            : &file.name;
+                        }
+                        @ if editable {
+                            @ if position > 0 {
+                                form(action=format!("/lists/{}/move/{}/up", reading_list.id, file.id), method="post") {
+                                    input(type="submit", value="⬆", title="move up");
+                                }
+                            }
+                            @ if position + 1 < last_position {
+                                form(action=format!("/lists/{}/move/{}/down", reading_list.id, file.id), method="post") {
+                                    input(type="submit", value="⬇", title="move down");
+                                }
+                            }
+                            form(action=format!("/lists/{}/remove/{}", reading_list.id, file.id), method="post") {
+                                input(type="submit", value="✖", title="remove from list");
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+    render(body_content, None, theme)
+}
+
+/// add or remove a file from the lists of the user
+pub fn file_reading_lists(
+    user: &User,
+    file: &FileInfo,
+    reading_lists: &[(ReadingList, bool)],
+) -> String {
+    let theme = user.preferences.theme;
+    let menu = menu(Some(user.to_owned()));
+    let file = file.clone();
+    let reading_lists = reading_lists.to_owned();
+    let file_url = format!("/lists/file/{}", file.id);
+    let body_content = box_html! {
+        : menu;
+        main {
+            header {
+                a(href=format!("/infos/{}", file.id)) {
+                    img(src="/images/library-icon.svgz") ;
+                    h1 { : &file.name }
+                }
+            }
+            ul(class="reading-lists") {
+                @ for (reading_list, with_file) in &reading_lists {
+                    li {
+                        @ if *with_file {
+                            form(action=format!("/lists/{}/remove/{}", reading_list.id, file.id), method="post") {
+                                input(type="hidden", name="next", value=&file_url);
+                                input(type="submit", value="✖", title="remove from list");
+                            }
+                        } else {
+                            form(action=format!("/lists/{}/add/{}", reading_list.id, file.id), method="post") {
+                                input(type="submit", value="+", title="add to list");
+                            }
+                        }
+                        a(href=format!("/lists/{}", reading_list.id)) : &reading_list.name;
+                    }
+                }
+            }
+            form(class="collection", accept-charset="utf-8", action="/lists/new", method="post") {
+                input(type="hidden", name="file_id", value=&file.id);
+                input(type="text", name="name", placeholder="New list name", required);
+                label { input(type="checkbox", name="shared", value="on"); : "shared" }
+                input(type="submit", value="Create list with this file");
+            }
+        }
     };
-    render(body_content, None)
+    render(body_content, None, theme)
 }
 
 pub struct LibraryDisplay {
     pub user: User,
-    pub directories_list: Vec<DirectoryInfo>,
+    // directory, bookmark status and number of files read
+    pub directories_list: Vec<(DirectoryInfo, bool, i32)>,
     pub files_list: Vec<(FileInfo, bool, bool)>,
     pub library_id: Option<i64>, // not really need this, see full_rescan button when lib is empty
     pub library_path: String,
     pub current_path: Option<String>,
-    pub search_query: Option<String>,
-    // TODO need search query option string
+    pub search: Option<SearchDisplay>,
+    pub series: Option<Series>,
+    // sort selector, for plain listings only
+    pub sort: Option<SortOrder>,
+    // lists only hold the current page
+    pub pagination: Option<Pagination>,
+    // file id and progress in percent, for list view
+    pub progress: HashMap<String, i32>,
+}
+
+pub struct Pagination {
+    // starting at 1
+    pub page: i64,
+    pub page_count: i64,
+    pub page_size: i64,
+    pub total: i64,
+    // initials with their first page, for the A-Z bar
+    pub letters: Vec<(char, i64)>,
+    // listing url ending with `?` or `&`, page parameters are appended
+    pub base_url: String,
+}
+impl Pagination {
+    pub fn new(total: i64, page: i64, page_size: i64, base_url: String) -> Pagination {
+        let page_count = ((total + page_size - 1) / page_size).max(1);
+        Pagination {
+            page: page.clamp(1, page_count),
+            page_count,
+            page_size,
+            total,
+            letters: Vec::with_capacity(0),
+            base_url,
+        }
+    }
+    /// indexes of the entries of the current page
+    pub fn range(&self) -> std::ops::Range<usize> {
+        let start = ((self.page - 1) * self.page_size) as usize;
+        start..start + self.page_size as usize
+    }
+    fn url(&self, page: i64) -> String {
+        format!("{}page={page}", self.base_url)
+    }
+}
+
+pub struct SearchDisplay {
+    pub query: String,
+    pub total_results: i64,
+    // file or directory id, and its name with matches between `HIGHLIGHT_START` and `HIGHLIGHT_END`
+    pub highlights: HashMap<String, String>,
+    pub filters: SearchFilters,
+    // for the library dropdown
+    pub library_names: Vec<String>,
+    // search saved as a smart collection
+    pub collection: Option<Collection>,
+}
+
+/// split a highlighted name in (text, is a match) parts
+fn highlighted_parts(name: &str, highlighted_name: Option<&String>) -> Vec<(String, bool)> {
+    let Some(highlighted_name) = highlighted_name else {
+        return vec![(name.to_string(), false)];
+    };
+    let mut parts: Vec<(String, bool)> = Vec::new();
+    let mut splitted_name = highlighted_name.split(HIGHLIGHT_START);
+    if let Some(before_first_match) = splitted_name.next() {
+        parts.push((before_first_match.to_string(), false));
+    }
+    for part in splitted_name {
+        match part.split_once(HIGHLIGHT_END) {
+            Some((matched, after)) => {
+                parts.push((matched.to_string(), true));
+                parts.push((after.to_string(), false));
+            }
+            None => parts.push((part.to_string(), true)),
+        }
+    }
+    parts.retain(|(text, _)| !text.is_empty());
+    parts
+}
+
+/// sort orders of a files listing, the current one selected
+fn sort_selector<'a>(sort: SortOrder) -> Box<dyn horrorshow::RenderBox + 'a> {
+    box_html! {
+        form(class="search-filters", method="get") {
+            select(name="sort") {
+                @ for sort_order in SortOrder::ALL {
+                    option(value=sort_order.as_str(), selected?=sort == sort_order) : sort_order.label();
+                }
+            }
+            input(type="submit", value="Sort");
+        }
+    }
+}
+
+/// A-Z bar, initials link to their first page
+fn letters_bar<'a>(pagination: &Pagination) -> Box<dyn horrorshow::RenderBox + 'a> {
+    let letters: Vec<(char, Option<String>)> = ('A'..='Z')
+        .chain(['#'])
+        .map(|letter| {
+            let url = pagination
+                .letters
+                .iter()
+                .find(|(initial, _)| *initial == letter)
+                .map(|(_, page)| pagination.url(*page));
+            (letter, url)
+        })
+        .collect();
+    box_html! {
+        nav(class="letters") {
+            @ for (letter, url) in &letters {
+                @ if let Some(url) = url {
+                    a(href=url) : letter;
+                } else {
+                    span : letter;
+                }
+            }
+        }
+    }
+}
+
+/// previous and next pages, and page sizes
+fn pagination_nav<'a>(pagination: &Pagination) -> Box<dyn horrorshow::RenderBox + 'a> {
+    let page = pagination.page;
+    let page_count = pagination.page_count;
+    let page_size = pagination.page_size;
+    let previous_urls = (pagination.url(1), pagination.url(page - 1));
+    let next_urls = (pagination.url(page + 1), pagination.url(page_count));
+    let size_urls: Vec<(i64, String)> = PAGE_SIZES
+        .iter()
+        .map(|size| (*size, format!("{}page_size={size}", pagination.base_url)))
+        .collect();
+    box_html! {
+        nav(class="pagination") {
+            @ if page > 1 {
+                a(href=&previous_urls.0, title="First page") : "«";
+                a(href=&previous_urls.1) : "Previous";
+            }
+            span : format!("Page {page} / {page_count}");
+            @ if page < page_count {
+                a(href=&next_urls.0) : "Next";
+                a(href=&next_urls.1, title="Last page") : "»";
+            }
+        }
+        nav(class="page-size") {
+            span : "Per page :";
+            @ for (size, url) in &size_urls {
+                @ if *size == page_size {
+                    span(class="selected") : size;
+                } else {
+                    a(href=url) : size;
+                }
+            }
+        }
+    }
+}
+
+/// files as a table, columns sort the listing when it can be sorted
+fn files_table<'a>(
+    files: Vec<(FileInfo, bool, bool)>,
+    progress: HashMap<String, i32>,
+    highlights: HashMap<String, String>,
+    sort: Option<SortOrder>,
+) -> Box<dyn horrorshow::RenderBox + 'a> {
+    let columns = [
+        ("Name", Some(SortOrder::Name)),
+        ("Format", None),
+        ("Size", Some(SortOrder::Size)),
+        ("Pages", None),
+        ("Progress", Some(SortOrder::Progress)),
+        ("Added", Some(SortOrder::Added)),
+    ];
+    box_html! {
+        table(class="files-list") {
+            thead {
+                tr {
+                    @ for (label, column_sort) in columns {
+                        @ if let (Some(sort), Some(column_sort)) = (sort, column_sort) {
+                            th(class=if sort == column_sort { "selected" } else { "" }) {
+                                a(href=format!("?sort={}", column_sort.as_str())) : label;
+                            }
+                        } else {
+                            th : label;
+                        }
+                    }
+                    th;
+                }
+            }
+            tbody {
+                @ for (file, bookmark_status, read_status) in &files {
+                    tr(class=if *read_status { "read" } else { "" }) {
+                        td {
+                            a(href=format!("/infos/{}", file.id)) {
+                                @ for (text, matched) in highlighted_parts(&file.name, highlights.get(&file.id)) {
+                                    @ if matched { mark : text } else { : text }
+                                }
+                            }
+                        }
+                        td : file.format.as_str();
+                        td : pretty_size(file.size);
+                        td : file.total_pages;
+                        td {
+                            @ if *read_status {
+                                : "100%";
+                            } else if let Some(progress) = progress.get(&file.id) {
+                                : format!("{progress}%");
+                            }
+                        }
+                        td : timestamp_to_pretty_date(file.added_date).unwrap_or_default();
+                        td {
+                            a(href=format!("/toggle/bookmark/{}", file.id)) {
+                                button(class=if *bookmark_status { "favorite bookmarked" } else { "favorite" })
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
 }
 
 pub fn library_display(list_to_display: LibraryDisplay) -> String {
+    let theme = list_to_display.user.preferences.theme;
     // we dispose of following variables :
     // - directory.name : Subdir2
     // - directory.parent_path : /home/thasos/mylibrary/Dragonlance
@@ -483,11 +1525,53 @@ pub fn library_display(list_to_display: LibraryDisplay) -> String {
     }
 
     // fill search button value if needed
-    let search_value = list_to_display.search_query.to_owned().unwrap_or_default();
+    let search_value = match &list_to_display.search {
+        Some(search) => search.query.to_owned(),
+        None => String::new(),
+    };
+    let highlights = match &list_to_display.search {
+        Some(search) => search.highlights.to_owned(),
+        None => HashMap::with_capacity(0),
+    };
 
     // String used to build breadcrumb links
     let mut breadcrumb_link_path = String::new();
 
+    // list view replaces file covers with a table
+    let list_view = list_to_display.user.preferences.view == View::List;
+    let files_table = if list_view && !list_to_display.files_list.is_empty() {
+        files_table(
+            list_to_display.files_list.clone(),
+            list_to_display.progress.clone(),
+            highlights.clone(),
+            list_to_display.sort,
+        )
+    } else {
+        box_html! {}
+    };
+
+    // no sort selector without files to sort
+    let sort_selector = match list_to_display.sort {
+        Some(sort) if !list_to_display.files_list.is_empty() => sort_selector(sort),
+        _ => box_html! {},
+    };
+
+    // no navigation for listings smaller than the smallest page
+    let (letters_bar, pagination_nav): (
+        Box<dyn horrorshow::RenderBox>,
+        Box<dyn horrorshow::RenderBox>,
+    ) = match &list_to_display.pagination {
+        Some(pagination) if pagination.total > PAGE_SIZES[0] => {
+            let letters_bar = if pagination.letters.is_empty() || pagination.page_count == 1 {
+                box_html! {}
+            } else {
+                letters_bar(pagination)
+            };
+            (letters_bar, pagination_nav(pagination))
+        }
+        _ => (box_html! {}, box_html! {}),
+    };
+
     // html rendering
     let menu = menu(Some(list_to_display.user.to_owned()));
     let body_content = box_html! {
@@ -525,7 +1609,7 @@ pub fn library_display(list_to_display: LibraryDisplay) -> String {
                 }
                 // TODO put search elsewhere in code ?
                 div(class="search") {
-                    form(accept-charset="utf-8", action="/search", method="post") {
+                    form(accept-charset="utf-8", action="/search", method="get") {
                         input(type="submit", value="");
                         input(type="text", placeholder="Search...", name="query", value=search_value);
                     }
@@ -541,10 +1625,9 @@ pub fn library_display(list_to_display: LibraryDisplay) -> String {
                     br;
                     a(href="/admin") : "Please add a library in Administration panel"
                 }
-            } else if list_to_display.directories_list.is_empty() && list_to_display.files_list.is_empty() {
+            } else if list_to_display.directories_list.is_empty() && list_to_display.files_list.is_empty() && list_to_display.search.is_none() && list_to_display.series.is_none() {
                 p {
                     // TODO need library name or id here (in struct LibraryDisplay)
-                    // TODO do not print in case of search...
                     : format!("Library {} is empty, please be patient", &list_to_display.library_path);
                     // TODO remove this ugly unwrap
                     form(action=format!("/admin/library/{}", &list_to_display.library_id.unwrap_or(0)), method="post") {
@@ -555,35 +1638,118 @@ pub fn library_display(list_to_display: LibraryDisplay) -> String {
                 }
             }
 
+            : sort_selector;
+            : letters_bar;
+
+            @ if let Some(series) = &list_to_display.series {
+                p(class="search-results") {
+                    : format!("{} : {}/{} read", series.name, series.read_count, series.file_count);
+                }
+            }
+
+            @ if let Some(search) = &list_to_display.search {
+                form(class="search-filters", accept-charset="utf-8", action="/search", method="get") {
+                    input(type="hidden", name="query", value=&search.query);
+                    select(name="format") {
+                        option(value="") : "All formats";
+                        @ for format in Format::READABLE {
+                            option(value=format.as_str(), selected?=search.filters.format.as_deref() == Some(format.as_str())) : format.as_str();
+                        }
+                    }
+                    select(name="library") {
+                        option(value="") : "All libraries";
+                        @ for library_name in &search.library_names {
+                            option(value=library_name, selected?=search.filters.library.as_ref().is_some_and(|library| library.eq_ignore_ascii_case(library_name))) : library_name;
+                        }
+                    }
+                    select(name="status") {
+                        option(value="") : "Any status";
+                        @ for status in ["read", "unread", "reading"] {
+                            option(value=status, selected?=search.filters.status.as_ref().map(|filter| filter.as_str()) == Some(status)) : status;
+                        }
+                    }
+                    select(name="bookmarked") {
+                        option(value="") : "Bookmarked or not";
+                        option(value="yes", selected?=search.filters.bookmarked == Some(true)) : "bookmarked";
+                        option(value="no", selected?=search.filters.bookmarked == Some(false)) : "not bookmarked";
+                    }
+                    input(type="submit", value="Filter");
+                }
+                p(class="search-results") {
+                    : format!("{} results for \"{}\"", search.total_results, search.query);
+                }
+                @ if let Some(collection) = &search.collection {
+                    form(class="collection", action=format!("/collections/{}/delete", collection.id), method="post") {
+                        h2 : &collection.name;
+                        input(type="submit", value="Delete collection");
+                    }
+                } else if !search.filters.is_empty() {
+                    form(class="collection", accept-charset="utf-8", action="/collections/new", method="post") {
+                        input(type="hidden", name="query", value=&search.query);
+                        input(type="text", name="name", placeholder="Collection name", required);
+                        input(type="submit", value="Save as collection");
+                    }
+                }
+            }
+
             section(class="gallery") {
-                @ for directory in &list_to_display.directories_list.to_owned() {
+                @ for (directory, bookmark_status, read_count) in &list_to_display.directories_list.to_owned() {
                     article(class="folder") {
                         a(href= {
                             // avoid double '/', I'm not proud of this...
                             if directory.parent_path.is_empty() {
                                 format!("/library/{}", directory.name)
+                            } else if let Some(current_path) = &list_to_display.current_path {
+                                format!("/library{}/{}", current_path, directory.name)
                             } else {
-                                format!("/library{}/{}", list_to_display.current_path.clone().unwrap_or("".to_string()), directory.name)
+                                // search or bookmarks, we don't know the library url
+                                format!("/directory/{}", directory.id)
                             }
                         }) {
-                            div(class="cover") {
+                            div(class= if *read_count > 0 && Some(*read_count) == directory.file_count {
+                                "cover read"
+                            } else {
+                                "cover"
+                            }) {
                                 span(class="folder-img") {}
                                 @ if let Some(file_count) = directory.file_count {
-                                    span(class="folder-nb-items")
-                                        : file_count;
+                                    span(class="folder-nb-items") {
+                                        @ if *read_count > 0 {
+                                            : format!("{read_count}/{file_count}");
+                                        } else {
+                                            : file_count;
+                                        }
+                                    }
+                                }
+                            }
+                            div(class="title") {
+                                h2 {
+                                    @ for (text, matched) in highlighted_parts(&directory.name, highlights.get(&directory.id)) {
+                                        @ if matched { mark : text } else { : text }
+                                    }
+                                }
+                            }
+                        }
+                        // libraries are displayed as directories, but have no flags
+                        @ if !directory.parent_path.is_empty() {
+                            a(href=format!("/toggle/directory/bookmark/{}", directory.id)) {
+                                @ if *bookmark_status {
+                                    button(class="favorite bookmarked")
+                                } else {
+                                    button(class="favorite")
+                                }
+                            }
+                            a(href=format!("/toggle/directory/read_status/{}", directory.id)) {
+                                @ if *read_count > 0 && Some(*read_count) == directory.file_count {
+                                    button(class="read-status read", title="mark as unread")
+                                } else {
+                                    button(class="read-status", title="mark as read")
                                 }
                             }
-                            div(class="title") { h2 { : format_args!("{}", directory.name) } }
                         }
-                        // TODO add toggle bookmark
-                        // @ if file.1 {
-                        //     button(class="favorite bookmarked")
-                        // } else {
-                        //     button(class="favorite")
-                        // }
                     }
                 }
-                @ for file in &list_to_display.files_list.to_owned() {
+                @ for file in list_to_display.files_list.iter().filter(|_| !list_view) {
                     article(class="file") {
                         a(href=format!("/infos/{}", &file.0.id)) {
                             div(class="cover") {
@@ -601,7 +1767,16 @@ pub fn library_display(list_to_display: LibraryDisplay) -> String {
                                 } );
                             }
                         }
-                        div(class="title") { h2 { : format_args!("{}", file.0.name); } }
+                        div(class="title") {
+                            h2 {
+                                @ for (text, matched) in highlighted_parts(&file.0.name, highlights.get(&file.0.id)) {
+                                    @ if matched { mark : text } else { : text }
+                                }
+                            }
+                        }
+                        a(href=format!("/lists/file/{}", file.0.id)) {
+                            button(class="add-to-list", title="add to a reading list")
+                        }
                         // add toggle link
                         @ if file.1 {
                             a(href=format!("/toggle/bookmark/{}", file.0.id)) {
@@ -617,9 +1792,11 @@ pub fn library_display(list_to_display: LibraryDisplay) -> String {
                     }
                 }
             }
+            : files_table;
+            : pagination_nav;
         }
     };
-    render(body_content, None)
+    render(body_content, None, theme)
 }
 
 fn menu<'a>(user: Option<User>) -> Box<dyn horrorshow::RenderBox + 'a> {
@@ -627,7 +1804,7 @@ fn menu<'a>(user: Option<User>) -> Box<dyn horrorshow::RenderBox + 'a> {
     let menu_content = box_html! {
         header {
             div(class="logo") {
-                a(href="/library") { : "Eloran" }
+                a(href="/home") { : "Eloran" }
             }
             nav {
                 input(type="checkbox", id="lasagna-checkbox");
@@ -647,6 +1824,15 @@ fn menu<'a>(user: Option<User>) -> Box<dyn horrorshow::RenderBox + 'a> {
                         label(for="prefs-checkbox") { : "A" ; }
                     }
                     ul(class="prefs-menu") {
+                        // smart collections
+                        @ if let Some(user) = &user {
+                            @ for collection in &user.collections {
+                                li { a(href=format!("/collections/{}", collection.id), class="collection") : &collection.name ; }
+                            }
+                        }
+                        li { a(href="/recent") : "Recently added" ; }
+                        li { a(href="/series") : "Series" ; }
+                        li { a(href="/lists") : "Reading lists" ; }
                         li { a(href="/prefs") : "Preferences" ; }
                         // print admin link if Role is ok
                         @ if let Some(user) = user {
@@ -666,11 +1852,15 @@ fn menu<'a>(user: Option<User>) -> Box<dyn horrorshow::RenderBox + 'a> {
 }
 
 /// take body content html box, and return all the page with headers and full body
-fn render(body_content: Box<dyn horrorshow::RenderBox>, redirect_url: Option<&str>) -> String {
+fn render(
+    body_content: Box<dyn horrorshow::RenderBox>,
+    redirect_url: Option<&str>,
+    theme: Theme,
+) -> String {
     let full_page = html! { : doctype::HTML;
     html {
-        head { : header(redirect_url); }
-            body(class="page-library") {
+        head { : header(redirect_url, theme); }
+            body(class=format!("page-library theme-{}", theme.as_str())) {
                 : body_content
             }
         }
@@ -685,13 +1875,40 @@ fn render(body_content: Box<dyn horrorshow::RenderBox>, redirect_url: Option<&st
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::http_server::Preferences;
 
     #[test]
     fn test_headers() {
         let redirect_url = "tests";
         // TODO WTF ?
-        let rendered_headers = render(header(Some(&redirect_url)), Some(&redirect_url));
+        let rendered_headers = render(
+            header(Some(&redirect_url), Theme::default()),
+            Some(&redirect_url),
+            Theme::default(),
+        );
         insta::assert_yaml_snapshot!(rendered_headers);
+        // other themes load their stylesheet after the default one
+        let rendered_headers = render(header(None, Theme::Sepia), None, Theme::Sepia);
+        assert!(rendered_headers.contains(r#"<link rel="stylesheet" href="/css/theme-sepia.css">"#));
+        assert!(rendered_headers.contains(r#"<body class="page-library theme-sepia">"#));
+    }
+    #[test]
+    fn test_typography_css() {
+        assert_eq!(
+            typography_css(&Typography::default(), "#epub-content"),
+            "#epub-content { font-size: 100%; line-height: 1.5; margin: 0 5%; } #epub-content * { line-height: inherit; }"
+        );
+        let typography = Typography {
+            reader_font: ReaderFont::Exo,
+            reader_font_size: 120,
+            reader_line_height: 200,
+            reader_margin: 10,
+            reader_justify: true,
+        };
+        assert_eq!(
+            typography_css(&typography, "#epub-content"),
+            "#epub-content { font-size: 120%; line-height: 2; margin: 0 10%; } #epub-content * { line-height: inherit; font-family: Exo, sans-serif; text-align: justify; }"
+        );
     }
     #[test]
     fn test_simple_message() {
@@ -700,14 +1917,68 @@ mod tests {
     #[test]
     fn test_prefs() {
         let user = User::default();
-        insta::assert_yaml_snapshot!(prefs(&user));
+        let library = Library {
+            id: 1,
+            name: String::from("Comics"),
+            ..Default::default()
+        };
+        insta::assert_yaml_snapshot!(prefs(&user, None, Vec::with_capacity(0)));
+        insta::assert_yaml_snapshot!(prefs(&user, Some(String::from("0123abcd")), vec![library]));
+    }
+    #[test]
+    fn test_recently_added() {
+        let file = |id: &str, added_date: i64| FileInfo {
+            id: id.to_string(),
+            name: format!("{id} & co.cbz"),
+            added_date,
+            format: Format::Cbz,
+            total_pages: 24,
+            ..Default::default()
+        };
+        let files = vec![
+            file("third", 1735732800),
+            file("second", 1735730000),
+            file("first", 1735603200),
+        ];
+        insta::assert_yaml_snapshot!(recently_added(
+            &User::default(),
+            files.clone(),
+            vec![String::from("Comics")],
+            Some(String::from("Comics")),
+            None
+        ));
+        let library = Library {
+            id: 1,
+            name: String::from("Comics & Manga"),
+            ..Default::default()
+        };
+        insta::assert_snapshot!(atom_feed(&library, &files, "https://eloran.example"));
+        assert!(atom_feed(&library, &files, "https://\"><x/>")
+            .contains("https://&quot;&gt;&lt;x/&gt;/infos/third"));
     }
     #[test]
     fn test_admin() {
         let user = User::default();
         let library_list = Vec::with_capacity(0);
         let user_list = Vec::with_capacity(0);
-        insta::assert_yaml_snapshot!(admin(&user, library_list, user_list));
+        insta::assert_yaml_snapshot!(admin(
+            &user,
+            library_list,
+            user_list,
+            TextPagination::default()
+        ));
+        let library = Library {
+            id: 1,
+            name: String::from("Comics"),
+            hidden_formats: HiddenFormats(vec![Format::Txt, Format::Other]),
+            ..Default::default()
+        };
+        insta::assert_yaml_snapshot!(admin(
+            &user,
+            vec![library],
+            Vec::with_capacity(0),
+            TextPagination::default()
+        ));
     }
     #[test]
     fn test_login_form() {
@@ -725,29 +1996,31 @@ mod tests {
         let bookmark_status = false;
         let read_status = true;
         let up_link = String::from("some/up/link");
+        let series = Some((2, String::from("Asterix"), Some(1.0)));
         insta::assert_yaml_snapshot!(file_info(
             &user,
             &file,
             current_page,
             bookmark_status,
             read_status,
-            up_link
+            up_link,
+            series
         ));
     }
     #[test]
     fn test_flag_toggle() {
         let user = User::default();
         let flag_status = true;
-        let file_id = "blabla";
-        let flag = "read_status";
-        insta::assert_yaml_snapshot!(flag_toggle(&user, flag_status, file_id, flag));
+        let flag = Flag::ReadStatus;
+        let redirect_url = "/infos/blabla";
+        insta::assert_yaml_snapshot!(flag_toggle(&user, flag_status, &flag, redirect_url));
     }
     #[test]
     fn test_comic_reader() {
         let user = User::default();
         let file = FileInfo::default();
         let page: i32 = 10;
-        insta::assert_yaml_snapshot!(comic_reader(&user, &file, page));
+        insta::assert_yaml_snapshot!(comic_reader(&user, &file, page, &[]));
     }
     #[test]
     fn test_ebook_reader() {
@@ -755,7 +2028,59 @@ mod tests {
         let file = FileInfo::default();
         let epub_content = "Lorem ipsum dolor sit amet";
         let page: i32 = 10;
-        insta::assert_yaml_snapshot!(ebook_reader(&user, &file, epub_content, page));
+        let next_files = vec![(
+            ReadingList {
+                id: 1,
+                name: String::from("Dragonlance"),
+                ..Default::default()
+            },
+            FileInfo {
+                id: String::from("next_id"),
+                name: String::from("Dragons of Winter Night.epub"),
+                ..Default::default()
+            },
+        )];
+        insta::assert_yaml_snapshot!(ebook_reader(&user, &file, epub_content, page, &next_files));
+    }
+    #[test]
+    fn test_reading_list() {
+        let user = User {
+            id: 1,
+            ..User::default()
+        };
+        let dragonlance = ReadingList {
+            id: 4,
+            user_id: 1,
+            name: String::from("Dragonlance"),
+            shared: true,
+            owner_name: String::from("admin"),
+        };
+        let files: Vec<(FileInfo, bool)> = ["one", "two", "three"]
+            .iter()
+            .enumerate()
+            .map(|(index, id)| {
+                let file = FileInfo {
+                    id: id.to_string(),
+                    name: format!("Chronicles {}.epub", index + 1),
+                    ..Default::default()
+                };
+                (file, index == 0)
+            })
+            .collect();
+        insta::assert_yaml_snapshot!(reading_list(&user, &dragonlance, &files));
+        insta::assert_yaml_snapshot!(reading_lists(
+            &user,
+            &[
+                dragonlance,
+                ReadingList {
+                    id: 5,
+                    user_id: 2,
+                    name: String::from("Best of"),
+                    shared: true,
+                    owner_name: String::from("bob"),
+                }
+            ]
+        ));
     }
     #[test]
     fn test_library() {
@@ -766,17 +2091,250 @@ mod tests {
             library_id: None,
             library_path: String::from("some/path"),
             current_path: None,
-            search_query: None,
+            search: None,
+            series: None,
+            sort: None,
+            progress: HashMap::with_capacity(0),
+            pagination: None,
         };
         insta::assert_yaml_snapshot!(library_display(list_to_display));
     }
     #[test]
+    fn test_library_directories() {
+        let directory = DirectoryInfo {
+            id: String::from("some_id"),
+            name: String::from("Dragonlance"),
+            parent_path: String::from("/some/path"),
+            file_count: Some(40),
+        };
+        let list_to_display = LibraryDisplay {
+            user: User::default(),
+            directories_list: vec![(directory.clone(), true, 12), (directory, false, 40)],
+            files_list: Vec::with_capacity(0),
+            library_id: None,
+            library_path: String::from("/some/path"),
+            current_path: Some(String::from("/path")),
+            search: None,
+            series: None,
+            sort: None,
+            progress: HashMap::with_capacity(0),
+            pagination: None,
+        };
+        insta::assert_yaml_snapshot!(library_display(list_to_display));
+    }
+    #[test]
+    fn test_library_sort() {
+        let file = FileInfo {
+            id: String::from("file_id"),
+            name: String::from("Issue 2.cbz"),
+            ..Default::default()
+        };
+        let list_to_display = LibraryDisplay {
+            user: User::default(),
+            directories_list: Vec::with_capacity(0),
+            files_list: vec![(file, false, false)],
+            library_id: None,
+            library_path: String::from("/some/path"),
+            current_path: Some(String::from("/path")),
+            search: None,
+            series: None,
+            sort: Some(SortOrder::LastRead),
+            progress: HashMap::with_capacity(0),
+            pagination: Some(Pagination {
+                letters: vec![('I', 1), ('Z', 3)],
+                ..Pagination::new(300, 2, 100, String::from("/library/path?"))
+            }),
+        };
+        insta::assert_yaml_snapshot!(library_display(list_to_display));
+    }
+    #[test]
+    fn test_library_list_view() {
+        let user = User {
+            preferences: Preferences {
+                view: View::List,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let file = |id: &str, name: &str| FileInfo {
+            id: id.to_string(),
+            name: name.to_string(),
+            format: Format::Cbz,
+            size: 25 * 1024 * 1024,
+            total_pages: 40,
+            added_date: 1735732800,
+            ..Default::default()
+        };
+        let list_to_display = LibraryDisplay {
+            user,
+            directories_list: Vec::with_capacity(0),
+            files_list: vec![
+                (file("first", "Issue 1.cbz"), true, true),
+                (file("second", "Issue 2.cbz"), false, false),
+                (file("third", "Issue 3.cbz"), false, false),
+            ],
+            library_id: None,
+            library_path: String::from("/some/path"),
+            current_path: Some(String::from("/path")),
+            search: None,
+            series: None,
+            sort: Some(SortOrder::Size),
+            pagination: None,
+            progress: HashMap::from([(String::from("second"), 25)]),
+        };
+        insta::assert_yaml_snapshot!(library_display(list_to_display));
+    }
+    #[test]
+    fn test_pagination() {
+        let pagination = Pagination::new(250, 3, 100, String::from("/reading?"));
+        assert_eq!(pagination.page_count, 3);
+        assert_eq!(pagination.range(), 200..300);
+        // out of range pages are clamped
+        assert_eq!(Pagination::new(250, 9, 100, String::new()).page, 3);
+        assert_eq!(Pagination::new(0, 0, 100, String::new()).page, 1);
+    }
+    #[test]
+    fn test_library_search() {
+        let directory = DirectoryInfo {
+            id: String::from("directory_id"),
+            name: String::from("Élan vital"),
+            parent_path: String::from("/some/path"),
+            file_count: Some(2),
+        };
+        let file = FileInfo {
+            id: String::from("file_id"),
+            name: String::from("Elan vital 01.cbz"),
+            ..Default::default()
+        };
+        let highlights = HashMap::from([
+            (
+                directory.id.clone(),
+                format!("{HIGHLIGHT_START}Élan{HIGHLIGHT_END} vital"),
+            ),
+            (
+                file.id.clone(),
+                format!("{HIGHLIGHT_START}Elan{HIGHLIGHT_END} vital 01.cbz"),
+            ),
+        ]);
+        let list_to_display = LibraryDisplay {
+            user: User::default(),
+            directories_list: vec![(directory, false, 0)],
+            files_list: vec![(file, false, false)],
+            library_id: None,
+            library_path: String::from("/some/path"),
+            current_path: None,
+            search: Some(SearchDisplay {
+                query: String::from("elan"),
+                total_results: 102,
+                highlights,
+                filters: crate::search::parse_query("elan format:cbz status:unread"),
+                library_names: vec![String::from("Comics"), String::from("Manga")],
+                collection: None,
+            }),
+            series: None,
+            sort: None,
+            progress: HashMap::with_capacity(0),
+            pagination: Some(Pagination::new(
+                102,
+                2,
+                50,
+                String::from("/search?query=elan&"),
+            )),
+        };
+        insta::assert_yaml_snapshot!(library_display(list_to_display));
+    }
+    #[test]
+    fn test_library_collection() {
+        let user = User {
+            collections: vec![Collection {
+                id: 3,
+                user_id: 0,
+                name: String::from("Unread manga"),
+                query: String::from("library:Manga status:unread"),
+            }],
+            ..User::default()
+        };
+        let list_to_display = LibraryDisplay {
+            user: user.clone(),
+            directories_list: Vec::with_capacity(0),
+            files_list: Vec::with_capacity(0),
+            library_id: None,
+            library_path: String::from("/some/path"),
+            current_path: None,
+            search: Some(SearchDisplay {
+                query: String::from("library:Manga status:unread"),
+                total_results: 60,
+                highlights: HashMap::with_capacity(0),
+                filters: crate::search::parse_query("library:Manga status:unread"),
+                library_names: vec![String::from("Manga")],
+                collection: user.collections.first().cloned(),
+            }),
+            series: None,
+            sort: None,
+            progress: HashMap::with_capacity(0),
+            pagination: Some(Pagination::new(60, 1, 50, String::from("/collections/3?"))),
+        };
+        insta::assert_yaml_snapshot!(library_display(list_to_display));
+    }
+    #[test]
+    fn test_series() {
+        let series = Series {
+            id: 2,
+            name: String::from("Asterix"),
+            file_count: 38,
+            read_count: 12,
+        };
+        insta::assert_yaml_snapshot!(series_list(&User::default(), std::slice::from_ref(&series)));
+        let volumes = ["T01 - Asterix le Gaulois.pdf", "T02 - La Serpe d'Or.pdf"]
+            .iter()
+            .map(|name| {
+                let file = FileInfo {
+                    id: name.to_string(),
+                    name: name.to_string(),
+                    ..Default::default()
+                };
+                (file, false, true)
+            })
+            .collect();
+        let list_to_display = LibraryDisplay {
+            user: User::default(),
+            directories_list: Vec::with_capacity(0),
+            files_list: volumes,
+            library_id: None,
+            library_path: String::from("/some/path"),
+            current_path: None,
+            search: None,
+            series: Some(series),
+            sort: None,
+            progress: HashMap::with_capacity(0),
+            pagination: None,
+        };
+        insta::assert_yaml_snapshot!(library_display(list_to_display));
+    }
+    #[test]
+    fn test_home() {
+        let file = |id: &str, name: &str| FileInfo {
+            id: id.to_string(),
+            name: name.to_string(),
+            total_pages: 40,
+            ..Default::default()
+        };
+        let home_to_display = HomeDisplay {
+            user: User::default(),
+            continue_reading: vec![(file("reading", "One Piece v03.cbz"), 9)],
+            next_to_read: vec![file("next", "One Piece v04.cbz")],
+            recently_added: vec![file("new", "Dragonlance 01.epub")],
+            random_unread: Vec::with_capacity(0),
+        };
+        insta::assert_yaml_snapshot!(home(home_to_display));
+    }
+    #[test]
     fn test_menu() {
         let user = User::default();
         let search_query = String::from("searching");
         let redirect_url = "tests";
         let menu = menu(Some(user));
-        let rendered_menu = render(menu, Some(redirect_url));
+        let rendered_menu = render(menu, Some(redirect_url), Theme::default());
         insta::assert_yaml_snapshot!(rendered_menu);
     }
 }
@@ -1,30 +1,45 @@
+use crate::audio;
 use crate::html_render;
 use crate::reader;
-use crate::scanner::{self, DirectoryInfo, FileInfo, Library};
+use crate::scanner::{self, DirectoryInfo, FileInfo, Format, HiddenFormats, Library};
+use crate::search::{self, Collection};
 use crate::sqlite;
+use crate::text::{self, TextPagination};
+use crate::zip_stream;
 
 use argon2::{
-    password_hash::{rand_core::OsRng, PasswordHasher, SaltString},
+    password_hash::{
+        rand_core::{OsRng, RngCore},
+        PasswordHasher, SaltString,
+    },
     Argon2,
 };
-use axum::http::{header, StatusCode};
+use axum::body::Body;
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
 use axum::response::{Html, IntoResponse, Redirect, Response};
-use axum::Form;
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     routing::{get, post},
     Router,
 };
+use axum::{Form, Json};
 use axum_login::{
     login_required,
-    tower_sessions::{Expiry, MemoryStore, SessionManagerLayer},
+    tower_sessions::{Expiry, MemoryStore, Session, SessionManagerLayer},
     AuthManagerLayerBuilder,
 };
 use serde::{Deserialize, Serialize};
 use sqlx::pool::Pool;
 use sqlx::Sqlite;
-use std::{collections::VecDeque, fs, process};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, VecDeque},
+    fs, process,
+    time::{SystemTime, UNIX_EPOCH},
+};
 use time::Duration;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio_util::io::{ReaderStream, SyncIoBridge};
 use tower::ServiceBuilder;
 use urlencoding::decode;
 
@@ -37,6 +52,141 @@ pub struct User {
     pub password_hash: String,
     pub name: String,
     pub role: Role,
+    // printed in menu
+    #[sqlx(skip)]
+    pub collections: Vec<Collection>,
+    #[sqlx(skip)]
+    pub preferences: Preferences,
+}
+
+/// Display preferences of a user
+#[derive(Debug, Clone, Default, PartialEq, sqlx::FromRow)]
+pub struct Preferences {
+    pub view: View,
+    // hide files the reader can't open
+    pub readable_only: bool,
+    pub theme: Theme,
+    #[sqlx(flatten)]
+    pub typography: Typography,
+    #[sqlx(try_from = "String")]
+    pub hidden_formats: HiddenFormats,
+}
+impl Preferences {
+    /// formats hidden by the user, readable only hides those the reader can't open
+    pub fn hidden(&self) -> HiddenFormats {
+        let mut hidden = self.hidden_formats.clone();
+        if self.readable_only {
+            let unreadable: Vec<Format> = Format::ALL
+                .into_iter()
+                .filter(|format| !format.is_readable())
+                .collect();
+            hidden.extend(&unreadable);
+        }
+        hidden
+    }
+}
+
+/// Epub reader typography, sizes in percents
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct Typography {
+    pub reader_font: ReaderFont,
+    pub reader_font_size: i64,
+    pub reader_line_height: i64,
+    // horizontal margins, in percents of the page width
+    pub reader_margin: i64,
+    pub reader_justify: bool,
+}
+impl Default for Typography {
+    fn default() -> Self {
+        Typography {
+            reader_font: ReaderFont::default(),
+            reader_font_size: 100,
+            reader_line_height: 150,
+            reader_margin: 5,
+            reader_justify: false,
+        }
+    }
+}
+
+/// Font of the epub reader, publisher keeps the book's own fonts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum ReaderFont {
+    #[default]
+    Publisher,
+    Exo,
+    Serif,
+    SansSerif,
+    Monospace,
+}
+impl ReaderFont {
+    pub const ALL: [ReaderFont; 5] = [
+        ReaderFont::Publisher,
+        ReaderFont::Exo,
+        ReaderFont::Serif,
+        ReaderFont::SansSerif,
+        ReaderFont::Monospace,
+    ];
+    pub fn as_str(&self) -> &str {
+        match &self {
+            ReaderFont::Publisher => "publisher",
+            ReaderFont::Exo => "exo",
+            ReaderFont::Serif => "serif",
+            ReaderFont::SansSerif => "sans_serif",
+            ReaderFont::Monospace => "monospace",
+        }
+    }
+    /// css font-family, none for the publisher fonts
+    pub fn font_family(&self) -> Option<&str> {
+        match &self {
+            ReaderFont::Publisher => None,
+            ReaderFont::Exo => Some("Exo, sans-serif"),
+            ReaderFont::Serif => Some("Georgia, serif"),
+            ReaderFont::SansSerif => Some("Helvetica, Arial, sans-serif"),
+            ReaderFont::Monospace => Some("monospace"),
+        }
+    }
+}
+
+/// Files listings as covers or as a table
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum View {
+    #[default]
+    Grid,
+    List,
+}
+impl View {
+    pub const ALL: [View; 2] = [View::Grid, View::List];
+    pub fn as_str(&self) -> &str {
+        match &self {
+            View::Grid => "grid",
+            View::List => "list",
+        }
+    }
+}
+
+/// Colors of the interface
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum Theme {
+    #[default]
+    Light,
+    Dark,
+    Sepia,
+}
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::Light, Theme::Dark, Theme::Sepia];
+    pub fn as_str(&self) -> &str {
+        match &self {
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+            Theme::Sepia => "sepia",
+        }
+    }
 }
 
 /// Roles
@@ -47,6 +197,250 @@ pub enum Role {
     Admin,
 }
 
+/// Flags a user can set on files and directories
+#[derive(Debug, Clone, PartialEq, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum Flag {
+    Bookmark,
+    ReadStatus,
+}
+
+/// Sort orders of files listings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Name,
+    Added,
+    Size,
+    LastRead,
+    Progress,
+}
+impl SortOrder {
+    pub const ALL: [SortOrder; 5] = [
+        SortOrder::Name,
+        SortOrder::Added,
+        SortOrder::Size,
+        SortOrder::LastRead,
+        SortOrder::Progress,
+    ];
+    pub fn as_str(&self) -> &str {
+        match &self {
+            SortOrder::Name => "name",
+            SortOrder::Added => "added",
+            SortOrder::Size => "size",
+            SortOrder::LastRead => "last_read",
+            SortOrder::Progress => "progress",
+        }
+    }
+    pub fn label(&self) -> &str {
+        match &self {
+            SortOrder::Name => "Name",
+            SortOrder::Added => "Recently added",
+            SortOrder::Size => "Largest",
+            SortOrder::LastRead => "Last read",
+            SortOrder::Progress => "Progress",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SortQuery {
+    sort: Option<SortOrder>,
+}
+
+/// sort order from the url, or the last one chosen during the session
+async fn sort_order(session: &Session, sort_query: SortQuery) -> SortOrder {
+    match sort_query.sort {
+        Some(sort) => {
+            if let Err(e) = session.insert("sort", sort).await {
+                warn!("unable to keep sort order in session : {e}");
+            }
+            sort
+        }
+        None => session.get("sort").await.ok().flatten().unwrap_or_default(),
+    }
+}
+
+/// natural order of names first, kept for equal dates, sizes...
+async fn sort_files(
+    files_list: &mut [FileInfo],
+    sort: SortOrder,
+    user_id: i64,
+    conn: &Pool<Sqlite>,
+) {
+    files_list.sort();
+    match sort {
+        SortOrder::Name => (),
+        SortOrder::Added => files_list.sort_by_key(|file| Reverse(file.added_date)),
+        SortOrder::Size => files_list.sort_by_key(|file| Reverse(file.size)),
+        SortOrder::LastRead => {
+            let reading_state = sqlite::get_reading_state(user_id, conn).await;
+            files_list.sort_by_key(|file| {
+                Reverse(reading_state.get(&file.id).map_or(0, |state| state.0))
+            });
+        }
+        SortOrder::Progress => {
+            let reading_state = sqlite::get_reading_state(user_id, conn).await;
+            let read_files = sqlite::get_read_file_ids(user_id, conn).await;
+            // read files are complete, pages start at 0
+            let progress = |file: &FileInfo| -> f64 {
+                match reading_state.get(&file.id) {
+                    _ if read_files.contains(&file.id) => 1.0,
+                    Some((_, Some(page))) if file.total_pages > 0 => {
+                        (*page as f64 + 1.0) / file.total_pages as f64
+                    }
+                    _ => 0.0,
+                }
+            };
+            files_list.sort_by(|a, b| progress(b).total_cmp(&progress(a)));
+        }
+    }
+}
+
+/// page sizes of listings, the smallest one is the limit to paginate
+pub const PAGE_SIZES: [i64; 4] = [50, 100, 200, 500];
+const DEFAULT_PAGE_SIZE: i64 = 100;
+
+#[derive(Deserialize)]
+struct PageQuery {
+    // starting at 1
+    page: Option<i64>,
+    page_size: Option<i64>,
+}
+
+/// page size from query, kept in session for next listings
+async fn page_size(session: &Session, page_size: Option<i64>) -> i64 {
+    match page_size.filter(|page_size| PAGE_SIZES.contains(page_size)) {
+        Some(page_size) => {
+            if let Err(e) = session.insert("page_size", page_size).await {
+                warn!("unable to keep page size in session : {e}");
+            }
+            page_size
+        }
+        None => session
+            .get("page_size")
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or(DEFAULT_PAGE_SIZE),
+    }
+}
+
+/// first page of each initial, names in display order
+fn letter_pages<'a>(names: impl Iterator<Item = &'a str>, page_size: i64) -> Vec<(char, i64)> {
+    let mut letters: Vec<(char, i64)> = Vec::new();
+    for (index, name) in names.enumerate() {
+        let initial = scanner::initial(name);
+        if !letters.iter().any(|(letter, _)| *letter == initial) {
+            letters.push((initial, index as i64 / page_size + 1));
+        }
+    }
+    letters
+}
+
+/// pagination of a listing of `total` entries, at the page asked or the last one
+async fn pagination(
+    session: &Session,
+    page_query: PageQuery,
+    total: i64,
+    base_url: String,
+) -> html_render::Pagination {
+    let page_size = page_size(session, page_query.page_size).await;
+    html_render::Pagination::new(total, page_query.page.unwrap_or(1), page_size, base_url)
+}
+
+/// directories and files indexes of a page, directories first
+fn page_ranges(
+    range: std::ops::Range<usize>,
+    directories_count: usize,
+) -> (std::ops::Range<usize>, std::ops::Range<usize>) {
+    let files_range =
+        range.start.saturating_sub(directories_count)..range.end.saturating_sub(directories_count);
+    (
+        range.start.min(directories_count)..range.end.min(directories_count),
+        files_range,
+    )
+}
+
+/// keep the current page of a listing, directories first
+/// with an A-Z bar when sorted by name
+/// flags of files are to be fetched for the page only
+async fn paginate(
+    session: &Session,
+    page_query: PageQuery,
+    directories: Vec<DirectoryInfo>,
+    files: Vec<FileInfo>,
+    sort: SortOrder,
+    base_url: String,
+) -> (Vec<DirectoryInfo>, Vec<FileInfo>, html_render::Pagination) {
+    let total = (directories.len() + files.len()) as i64;
+    let mut pagination = pagination(session, page_query, total, base_url).await;
+    if sort == SortOrder::Name {
+        let names = directories
+            .iter()
+            .map(|directory| directory.name.as_str())
+            .chain(files.iter().map(|file| file.name.as_str()));
+        pagination.letters = letter_pages(names, pagination.page_size);
+    }
+    let (directories_range, files_range) = page_ranges(pagination.range(), directories.len());
+    let directories = directories
+        .into_iter()
+        .skip(directories_range.start)
+        .take(directories_range.len())
+        .collect();
+    let files = files
+        .into_iter()
+        .skip(files_range.start)
+        .take(files_range.len())
+        .collect();
+    (directories, files, pagination)
+}
+
+/// hide files of formats hidden by the user or by their library
+async fn visible_files(
+    user: &User,
+    mut files: Vec<FileInfo>,
+    conn: &Pool<Sqlite>,
+) -> Vec<FileInfo> {
+    let user_hidden = user.preferences.hidden();
+    let library_hidden: HashMap<String, HiddenFormats> = sqlite::get_library(None, None, conn)
+        .await
+        .into_iter()
+        .map(|library| (library.name, library.hidden_formats))
+        .collect();
+    files.retain(|file| {
+        !user_hidden.contains(&file.format)
+            && !library_hidden
+                .get(&file.library_name)
+                .is_some_and(|hidden| hidden.contains(&file.format))
+    });
+    files
+}
+
+/// progress in percent of files being read, only needed by list view
+async fn files_progress(
+    user: &User,
+    files: &[(FileInfo, bool, bool)],
+    conn: &Pool<Sqlite>,
+) -> HashMap<String, i32> {
+    if user.preferences.view != View::List {
+        return HashMap::with_capacity(0);
+    }
+    let reading_state = sqlite::get_reading_state(user.id, conn).await;
+    // pages start at 0
+    files
+        .iter()
+        .filter_map(|(file, _, _)| match reading_state.get(&file.id) {
+            Some((_, Some(page))) if file.total_pages > 0 => {
+                Some((file.id.clone(), (page + 1) * 100 / file.total_pages))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
 fn error_handler() -> Html<String> {
     Html(html_render::simple_message(
         "server error, please see logs",
@@ -54,28 +448,48 @@ fn error_handler() -> Html<String> {
     ))
 }
 
+/// construct the `/library/...` url of a directory from its path on disk
+fn directory_url(directory: &DirectoryInfo, library_list: &[Library]) -> String {
+    let directory_full_path = format!("{}/{}", directory.parent_path, directory.name);
+    for library in library_list {
+        if let Some(path_end) = directory_full_path.strip_prefix(&library.path) {
+            if path_end.is_empty() || path_end.starts_with('/') {
+                return format!("/library/{}{}", library.name, path_end);
+            }
+        }
+    }
+    String::from("/library")
+}
+
 async fn reading_handler(
     auth_session: AuthSession,
+    session: Session,
     State(conn): State<Pool<Sqlite>>,
+    Query(sort_query): Query<SortQuery>,
+    Query(page_query): Query<PageQuery>,
 ) -> impl IntoResponse {
     match auth_session.user {
         Some(user) => {
             info!("get /reading : {}", &user.name);
+            let sort = sort_order(&session, sort_query).await;
             // search files
-            let mut files_results = sqlite::get_reading_files_from_user_id(&user.id, &conn).await;
-            files_results.sort();
+            let files_results = sqlite::get_reading_files_from_user_id(&user.id, &conn).await;
             // add status (read, bookmark)
             let user = sqlite::get_user(Some(&user.name), None, &conn).await;
             let user = user.first().unwrap();
-            let mut files_results_with_status: Vec<(FileInfo, bool, bool)> =
-                Vec::with_capacity(files_results.capacity());
-            for file in files_results {
-                let bookmark_status =
-                    sqlite::get_flag_status("bookmark", user.id, &file.id, &conn).await;
-                let read_status =
-                    sqlite::get_flag_status("read_status", user.id, &file.id, &conn).await;
-                files_results_with_status.push((file, bookmark_status, read_status));
-            }
+            let mut files_results = visible_files(user, files_results, &conn).await;
+            sort_files(&mut files_results, sort, user.id, &conn).await;
+            let (_, files_results, pagination) = paginate(
+                &session,
+                page_query,
+                Vec::with_capacity(0),
+                files_results,
+                sort,
+                String::from("/reading?"),
+            )
+            .await;
+            let files_results_with_status =
+                sqlite::get_files_flags(files_results, user.id, &conn).await;
             // lib path
             let library_path = sqlite::get_library(None, None, &conn).await;
             let empty_library = Library::default();
@@ -88,109 +502,776 @@ async fn reading_handler(
             let list_to_display = html_render::LibraryDisplay {
                 user: user.clone(),
                 directories_list: Vec::with_capacity(0),
+                progress: files_progress(user, &files_results_with_status, &conn).await,
+                files_list: files_results_with_status,
+                library_id: None,
+                library_path: library_path.path,
+                current_path: None,
+                search: None,
+                series: None,
+                sort: Some(sort),
+                pagination: Some(pagination),
+            };
+            Html(html_render::library_display(list_to_display))
+        }
+        None => unauthorized_response(),
+    }
+}
+
+/// number of files in each row of the home page
+const HOME_ROW_SIZE: i64 = 12;
+/// files on recently added page and in atom feeds
+const RECENT_SIZE: i64 = 200;
+const FEED_SIZE: i64 = 50;
+
+/// first unread file after the last read one, in recently read series and directories
+async fn next_to_read(user_id: i64, being_read: &[String], conn: &Pool<Sqlite>) -> Vec<FileInfo> {
+    let mut next_files: Vec<FileInfo> = Vec::new();
+    for (series_id, parent_path) in
+        sqlite::get_recently_read_groups(user_id, HOME_ROW_SIZE * 2, conn).await
+    {
+        let mut files = match (series_id, parent_path.rsplit_once('/')) {
+            // already ordered by volume
+            (Some(series_id), _) => sqlite::get_series_files(series_id, conn).await,
+            (None, Some((parent_path, directory_name))) => {
+                let mut files =
+                    sqlite::get_files_from_directory(parent_path, directory_name, conn).await;
+                files.sort();
+                files
+            }
+            (None, None) => continue,
+        };
+        files.retain(|file| file.format != scanner::Format::Other);
+        let files = sqlite::get_files_flags(files, user_id, conn).await;
+        let Some(last_read) = files.iter().rposition(|(_, _, read)| *read) else {
+            continue;
+        };
+        let next_file = files[last_read + 1..]
+            .iter()
+            .find(|(file, _, read)| !read && !being_read.contains(&file.id));
+        if let Some((next_file, _, _)) = next_file {
+            if !next_files.iter().any(|file| file.id == next_file.id) {
+                next_files.push(next_file.clone());
+            }
+        }
+        if next_files.len() as i64 == HOME_ROW_SIZE {
+            break;
+        }
+    }
+    next_files
+}
+
+/// dashboard : continue reading, next to read, recently added and random unread files
+async fn home_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            info!("get /home : {}", user.name);
+            let user = sqlite::get_user(Some(&user.name), None, &conn).await;
+            let user = user.first().unwrap();
+            let continue_reading =
+                sqlite::get_continue_reading(user.id, HOME_ROW_SIZE, &conn).await;
+            let being_read: Vec<String> = continue_reading
+                .iter()
+                .map(|(file, _)| file.id.clone())
+                .collect();
+            let home_to_display = html_render::HomeDisplay {
+                user: user.clone(),
+                continue_reading,
+                next_to_read: next_to_read(user.id, &being_read, &conn).await,
+                recently_added: sqlite::get_recently_added(
+                    None,
+                    None,
+                    &user.preferences.hidden(),
+                    HOME_ROW_SIZE,
+                    &conn,
+                )
+                .await,
+                random_unread: sqlite::get_random_unread(
+                    user.id,
+                    &user.preferences.hidden(),
+                    HOME_ROW_SIZE / 2,
+                    &conn,
+                )
+                .await,
+            };
+            Html(html_render::home(home_to_display))
+        }
+        None => unauthorized_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct RecentQuery {
+    library: Option<String>,
+    format: Option<String>,
+}
+/// recently added files, grouped by day
+async fn recent_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+    Query(recent_query): Query<RecentQuery>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            info!("get /recent : {}", user.name);
+            let user = sqlite::get_user(Some(&user.name), None, &conn).await;
+            let user = user.first().unwrap();
+            // empty select values mean no filter
+            let library = recent_query.library.filter(|library| !library.is_empty());
+            let format = recent_query.format.filter(|format| !format.is_empty());
+            let files = sqlite::get_recently_added(
+                library.as_deref(),
+                format.as_deref(),
+                &user.preferences.hidden(),
+                RECENT_SIZE,
+                &conn,
+            )
+            .await;
+            let library_names = sqlite::get_library(None, None, &conn)
+                .await
+                .into_iter()
+                .map(|library| library.name)
+                .collect();
+            Html(html_render::recently_added(
+                user,
+                files,
+                library_names,
+                library,
+                format,
+            ))
+        }
+        None => unauthorized_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct FeedQuery {
+    token: String,
+}
+/// atom feed of a library, authenticated by api token since feed readers have no session
+async fn feed_handler(
+    State(conn): State<Pool<Sqlite>>,
+    headers: HeaderMap,
+    Path(library_id): Path<i64>,
+    Query(feed_query): Query<FeedQuery>,
+) -> impl IntoResponse {
+    let Some(user) = sqlite::get_user_from_api_token(&feed_query.token, &conn).await else {
+        warn!("get /feed/{library_id} : invalid token");
+        // not 401, which is redirected to login page
+        return (StatusCode::FORBIDDEN, "invalid token").into_response();
+    };
+    info!("get /feed/{library_id} : {}", user.name);
+    let library = sqlite::get_library(None, Some(&library_id.to_string()), &conn).await;
+    let Some(library) = library.first() else {
+        return (StatusCode::NOT_FOUND, "library not found").into_response();
+    };
+    let files = sqlite::get_recently_added(
+        Some(&library.name),
+        None,
+        &user.preferences.hidden(),
+        FEED_SIZE,
+        &conn,
+    )
+    .await;
+    // links must be absolute in a feed
+    let host = headers
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .unwrap_or("localhost");
+    let scheme = headers
+        .get("x-forwarded-proto")
+        .and_then(|scheme| scheme.to_str().ok())
+        .unwrap_or("http");
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/atom+xml; charset=utf-8")],
+        html_render::atom_feed(library, &files, &format!("{scheme}://{host}")),
+    )
+        .into_response()
+}
+
+async fn bookmarks_handler(
+    auth_session: AuthSession,
+    session: Session,
+    State(conn): State<Pool<Sqlite>>,
+    Query(sort_query): Query<SortQuery>,
+    Query(page_query): Query<PageQuery>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            info!("get /bookmarks : {}", &user.name);
+            let sort = sort_order(&session, sort_query).await;
+            // search files
+            let files_results = sqlite::bookmarks_for_user_id(user.id, &conn).await;
+            let mut directories_results =
+                sqlite::directory_bookmarks_for_user_id(user.id, &conn).await;
+            directories_results.sort();
+            // add status (read, bookmark)
+            let user = sqlite::get_user(Some(&user.name), None, &conn).await;
+            let user = user.first().unwrap();
+            let mut files_results = visible_files(user, files_results, &conn).await;
+            sort_files(&mut files_results, sort, user.id, &conn).await;
+            let (directories_results, files_results, pagination) = paginate(
+                &session,
+                page_query,
+                directories_results,
+                files_results,
+                sort,
+                String::from("/bookmarks?"),
+            )
+            .await;
+            let files_results_with_status =
+                sqlite::get_files_flags(files_results, user.id, &conn).await;
+            // lib path
+            let library_path = sqlite::get_library(None, None, &conn).await;
+            // let library_path = library_path.first().unwrap().to_owned();
+            let library_path = match library_path.first() {
+                Some(library_path) => library_path.to_owned(),
+                None => Library::new(),
+            };
+            // response
+            let list_to_display = html_render::LibraryDisplay {
+                user: user.clone(),
+                directories_list: sqlite::get_directories_flags(
+                    directories_results,
+                    user.id,
+                    &user.preferences.hidden(),
+                    &conn,
+                )
+                .await,
+                progress: files_progress(user, &files_results_with_status, &conn).await,
                 files_list: files_results_with_status,
                 library_id: None,
                 library_path: library_path.path,
                 current_path: None,
-                search_query: None,
+                search: None,
+                series: None,
+                sort: Some(sort),
+                pagination: Some(pagination),
             };
             Html(html_render::library_display(list_to_display))
         }
-        None => unauthorized_response(),
+        None => unauthorized_response(),
+    }
+}
+
+/// search results page, used by search and collections
+async fn search_page(
+    user: &User,
+    query: &str,
+    page: i64,
+    page_size: i64,
+    collection: Option<Collection>,
+    conn: &Pool<Sqlite>,
+) -> Html<String> {
+    let mut filters = search::parse_query(query);
+    filters.hidden_formats = user.preferences.hidden();
+    // ranked files and dirs
+    let (search_results, total_results) =
+        sqlite::search(&filters, user.id, page - 1, page_size, conn).await;
+    let base_url = match &collection {
+        Some(collection) => format!("/collections/{}?", collection.id),
+        None => format!("/search?query={}&", urlencoding::encode(query)),
+    };
+    // add status (read, bookmark)
+    let files_results_with_status =
+        sqlite::get_files_flags(search_results.files, user.id, conn).await;
+    // lib path
+    let library_list = sqlite::get_library(None, None, conn).await;
+    let library_path = match library_list.first() {
+        Some(library_path) => library_path.to_owned(),
+        None => Library::new(),
+    };
+    // response
+    let list_to_display = html_render::LibraryDisplay {
+        user: user.clone(),
+        directories_list: sqlite::get_directories_flags(
+            search_results.directories,
+            user.id,
+            &user.preferences.hidden(),
+            conn,
+        )
+        .await,
+        progress: files_progress(user, &files_results_with_status, conn).await,
+        files_list: files_results_with_status,
+        library_id: None,
+        library_path: library_path.path,
+        current_path: None,
+        search: Some(html_render::SearchDisplay {
+            query: query.to_string(),
+            total_results,
+            highlights: search_results.highlights,
+            filters,
+            library_names: library_list
+                .into_iter()
+                .map(|library| library.name)
+                .collect(),
+            collection,
+        }),
+        series: None,
+        sort: None,
+        pagination: Some(html_render::Pagination::new(
+            total_results,
+            page,
+            page_size,
+            base_url,
+        )),
+    };
+    Html(html_render::library_display(list_to_display))
+}
+
+#[derive(Deserialize)]
+struct SearchQuery {
+    query: String,
+    // dropdowns, override filters of the query, empty means all
+    format: Option<String>,
+    library: Option<String>,
+    status: Option<String>,
+    bookmarked: Option<String>,
+}
+async fn search_handler(
+    auth_session: AuthSession,
+    session: Session,
+    State(conn): State<Pool<Sqlite>>,
+    Query(search_query): Query<SearchQuery>,
+    Query(page_query): Query<PageQuery>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            let query = search::merge_query(
+                search_query.query.trim(),
+                &[
+                    ("format", search_query.format),
+                    ("library", search_query.library),
+                    ("status", search_query.status),
+                    ("bookmarked", search_query.bookmarked),
+                ],
+            );
+            let page = page_query.page.unwrap_or(1).max(1);
+            let page_size = page_size(&session, page_query.page_size).await;
+            info!("get /search : {query} (page {page})");
+            let user = sqlite::get_user(Some(&user.name), None, &conn).await;
+            let user = user.first().unwrap();
+            search_page(user, &query, page, page_size, None, &conn).await
+        }
+        None => unauthorized_response(),
+    }
+}
+
+async fn collection_handler(
+    auth_session: AuthSession,
+    session: Session,
+    State(conn): State<Pool<Sqlite>>,
+    Path(collection_id): Path<i64>,
+    Query(page_query): Query<PageQuery>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            let page = page_query.page.unwrap_or(1).max(1);
+            let page_size = page_size(&session, page_query.page_size).await;
+            info!("get /collections/{collection_id} (page {page})");
+            let user = sqlite::get_user(Some(&user.name), None, &conn).await;
+            let user = user.first().unwrap();
+            match sqlite::get_collection(collection_id, user.id, &conn).await {
+                Some(collection) => {
+                    let query = collection.query.clone();
+                    search_page(user, &query, page, page_size, Some(collection), &conn)
+                        .await
+                        .into_response()
+                }
+                None => (StatusCode::NOT_FOUND, "collection not found").into_response(),
+            }
+        }
+        None => unauthorized_response().into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct FormCollection {
+    name: String,
+    query: String,
+}
+async fn new_collection_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+    Form(collection): Form<FormCollection>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            info!("post /collections/new : {}", collection.name);
+            let name = collection.name.trim();
+            if name.is_empty() {
+                return Redirect::to(&format!(
+                    "/search?query={}",
+                    urlencoding::encode(&collection.query)
+                ))
+                .into_response();
+            }
+            match sqlite::create_collection(user.id, name, &collection.query, &conn).await {
+                Some(collection_id) => {
+                    Redirect::to(&format!("/collections/{collection_id}")).into_response()
+                }
+                None => error_handler().into_response(),
+            }
+        }
+        None => unauthorized_response().into_response(),
+    }
+}
+
+async fn delete_collection_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+    Path(collection_id): Path<i64>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            info!("post /collections/{collection_id}/delete");
+            sqlite::delete_collection(collection_id, user.id, &conn).await;
+            Redirect::to("/library").into_response()
+        }
+        None => unauthorized_response().into_response(),
+    }
+}
+
+/// collections list, in json
+async fn api_collections_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            info!("get /api/collections : {}", user.name);
+            Json(sqlite::get_collections(user.id, &conn).await).into_response()
+        }
+        None => StatusCode::UNAUTHORIZED.into_response(),
+    }
+}
+
+#[derive(Serialize)]
+struct ApiCollection {
+    collection: Collection,
+    page: i64,
+    page_count: i64,
+    total_results: i64,
+    directories: Vec<DirectoryInfo>,
+    files: Vec<FileInfo>,
+}
+/// a page of collection results, in json
+async fn api_collection_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+    Path(collection_id): Path<i64>,
+    Query(page_query): Query<PageQuery>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            let page = page_query.page.unwrap_or(1).max(1);
+            let page_size = page_query
+                .page_size
+                .filter(|page_size| PAGE_SIZES.contains(page_size))
+                .unwrap_or(sqlite::SEARCH_PAGE_SIZE);
+            info!("get /api/collections/{collection_id} (page {page})");
+            match sqlite::get_collection(collection_id, user.id, &conn).await {
+                Some(collection) => {
+                    let mut filters = search::parse_query(&collection.query);
+                    filters.hidden_formats = user.preferences.hidden();
+                    let (search_results, total_results) =
+                        sqlite::search(&filters, user.id, page - 1, page_size, &conn).await;
+                    Json(ApiCollection {
+                        collection,
+                        page,
+                        page_count: (total_results + page_size - 1) / page_size,
+                        total_results,
+                        directories: search_results.directories,
+                        files: search_results.files,
+                    })
+                    .into_response()
+                }
+                None => StatusCode::NOT_FOUND.into_response(),
+            }
+        }
+        None => StatusCode::UNAUTHORIZED.into_response(),
+    }
+}
+
+async fn series_list_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            info!("get /series : {}", user.name);
+            let user = sqlite::get_user(Some(&user.name), None, &conn).await;
+            let user = user.first().unwrap();
+            let series_list = sqlite::get_series_list(user.id, &conn).await;
+            Html(html_render::series_list(user, &series_list)).into_response()
+        }
+        None => unauthorized_response().into_response(),
+    }
+}
+
+/// volumes of a series, in order
+async fn series_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+    Path(series_id): Path<i64>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            info!("get /series/{series_id} : {}", user.name);
+            let user = sqlite::get_user(Some(&user.name), None, &conn).await;
+            let user = user.first().unwrap();
+            match sqlite::get_series(series_id, user.id, &conn).await {
+                Some(series) => {
+                    let files = visible_files(
+                        user,
+                        sqlite::get_series_files(series_id, &conn).await,
+                        &conn,
+                    )
+                    .await;
+                    let files = sqlite::get_files_flags(files, user.id, &conn).await;
+                    let library_path = sqlite::get_library(None, None, &conn).await;
+                    let library_path = match library_path.first() {
+                        Some(library_path) => library_path.to_owned(),
+                        None => Library::new(),
+                    };
+                    let list_to_display = html_render::LibraryDisplay {
+                        user: user.clone(),
+                        directories_list: Vec::with_capacity(0),
+                        progress: files_progress(user, &files, &conn).await,
+                        files_list: files,
+                        library_id: None,
+                        library_path: library_path.path,
+                        current_path: None,
+                        search: None,
+                        series: Some(series),
+                        sort: None,
+                        pagination: None,
+                    };
+                    Html(html_render::library_display(list_to_display)).into_response()
+                }
+                None => (StatusCode::NOT_FOUND, "series not found").into_response(),
+            }
+        }
+        None => unauthorized_response().into_response(),
+    }
+}
+
+async fn reading_lists_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            info!("get /lists : {}", user.name);
+            let user = sqlite::get_user(Some(&user.name), None, &conn).await;
+            let user = user.first().unwrap();
+            let reading_lists = sqlite::get_reading_lists(user.id, &conn).await;
+            Html(html_render::reading_lists(user, &reading_lists)).into_response()
+        }
+        None => unauthorized_response().into_response(),
+    }
+}
+
+async fn reading_list_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+    Path(list_id): Path<i64>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            info!("get /lists/{list_id} : {}", user.name);
+            let user = sqlite::get_user(Some(&user.name), None, &conn).await;
+            let user = user.first().unwrap();
+            match sqlite::get_reading_list(list_id, user.id, &conn).await {
+                Some(reading_list) => {
+                    let files = sqlite::get_reading_list_files(list_id, &conn).await;
+                    let files: Vec<(FileInfo, bool)> =
+                        sqlite::get_files_flags(files, user.id, &conn)
+                            .await
+                            .into_iter()
+                            .map(|(file, _, read)| (file, read))
+                            .collect();
+                    Html(html_render::reading_list(user, &reading_list, &files)).into_response()
+                }
+                None => (StatusCode::NOT_FOUND, "reading list not found").into_response(),
+            }
+        }
+        None => unauthorized_response().into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct FormReadingList {
+    name: String,
+    // checkbox, absent if unchecked
+    shared: Option<String>,
+    // add this file to the new list
+    file_id: Option<String>,
+}
+async fn new_reading_list_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+    Form(reading_list): Form<FormReadingList>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            info!("post /lists/new : {}", reading_list.name);
+            let name = reading_list.name.trim();
+            if name.is_empty() {
+                return Redirect::to("/lists").into_response();
+            }
+            let shared = reading_list.shared.is_some();
+            match sqlite::create_reading_list(user.id, name, shared, &conn).await {
+                Some(list_id) => {
+                    if let Some(file_id) = &reading_list.file_id {
+                        sqlite::add_to_reading_list(list_id, file_id, &conn).await;
+                    }
+                    Redirect::to(&format!("/lists/{list_id}")).into_response()
+                }
+                None => error_handler().into_response(),
+            }
+        }
+        None => unauthorized_response().into_response(),
+    }
+}
+
+async fn edit_reading_list_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+    Path(list_id): Path<i64>,
+    Form(reading_list): Form<FormReadingList>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            info!("post /lists/{list_id}/edit : {}", reading_list.name);
+            let name = reading_list.name.trim();
+            if !name.is_empty() {
+                let shared = reading_list.shared.is_some();
+                sqlite::update_reading_list(list_id, user.id, name, shared, &conn).await;
+            }
+            Redirect::to(&format!("/lists/{list_id}")).into_response()
+        }
+        None => unauthorized_response().into_response(),
+    }
+}
+
+async fn delete_reading_list_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+    Path(list_id): Path<i64>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            info!("post /lists/{list_id}/delete");
+            sqlite::delete_reading_list(list_id, user.id, &conn).await;
+            Redirect::to("/lists").into_response()
+        }
+        None => unauthorized_response().into_response(),
+    }
+}
+
+/// lists of the user, to add or remove a file
+async fn file_reading_lists_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+    Path(file_id): Path<String>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            info!("get /lists/file/{file_id} : {}", user.name);
+            let user = sqlite::get_user(Some(&user.name), None, &conn).await;
+            let user = user.first().unwrap();
+            match sqlite::get_files_from_file_id(&file_id, &conn).await {
+                Some(file) => {
+                    let reading_lists =
+                        sqlite::get_reading_lists_with_file(&file.id, user.id, &conn).await;
+                    Html(html_render::file_reading_lists(user, &file, &reading_lists))
+                        .into_response()
+                }
+                None => (StatusCode::NOT_FOUND, "file not found").into_response(),
+            }
+        }
+        None => unauthorized_response().into_response(),
+    }
+}
+
+/// only the owner can change the content of a list
+async fn owned_reading_list(list_id: i64, user_id: i64, conn: &Pool<Sqlite>) -> bool {
+    sqlite::get_reading_list(list_id, user_id, conn)
+        .await
+        .is_some_and(|reading_list| reading_list.user_id == user_id)
+}
+
+async fn add_to_reading_list_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+    Path((list_id, file_id)): Path<(i64, String)>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            info!("post /lists/{list_id}/add/{file_id}");
+            if !owned_reading_list(list_id, user.id, &conn).await {
+                return (StatusCode::NOT_FOUND, "reading list not found").into_response();
+            }
+            sqlite::add_to_reading_list(list_id, &file_id, &conn).await;
+            Redirect::to(&format!("/lists/file/{file_id}")).into_response()
+        }
+        None => unauthorized_response().into_response(),
     }
 }
 
-async fn bookmarks_handler(
+#[derive(Deserialize)]
+struct FormNext {
+    next: Option<String>,
+}
+async fn remove_from_reading_list_handler(
     auth_session: AuthSession,
     State(conn): State<Pool<Sqlite>>,
+    Path((list_id, file_id)): Path<(i64, String)>,
+    Form(form): Form<FormNext>,
 ) -> impl IntoResponse {
     match auth_session.user {
         Some(user) => {
-            info!("get /bookmarks : {}", &user.name);
-            // search files
-            let mut files_results = sqlite::bookmarks_for_user_id(user.id, &conn).await;
-            files_results.sort();
-            // add status (read, bookmark)
-            let user = sqlite::get_user(Some(&user.name), None, &conn).await;
-            let user = user.first().unwrap();
-            let mut files_results_with_status: Vec<(FileInfo, bool, bool)> =
-                Vec::with_capacity(files_results.capacity());
-            for file in files_results {
-                let bookmark_status =
-                    sqlite::get_flag_status("bookmark", user.id, &file.id, &conn).await;
-                let read_status =
-                    sqlite::get_flag_status("read_status", user.id, &file.id, &conn).await;
-                files_results_with_status.push((file, bookmark_status, read_status));
+            info!("post /lists/{list_id}/remove/{file_id}");
+            if !owned_reading_list(list_id, user.id, &conn).await {
+                return (StatusCode::NOT_FOUND, "reading list not found").into_response();
+            }
+            sqlite::remove_from_reading_list(list_id, &file_id, &conn).await;
+            // local redirections only
+            match form.next {
+                Some(next) if next.starts_with('/') && !next.starts_with("//") => {
+                    Redirect::to(&next).into_response()
+                }
+                _ => Redirect::to(&format!("/lists/{list_id}")).into_response(),
             }
-            // lib path
-            let library_path = sqlite::get_library(None, None, &conn).await;
-            // let library_path = library_path.first().unwrap().to_owned();
-            let library_path = match library_path.first() {
-                Some(library_path) => library_path.to_owned(),
-                None => Library::new(),
-            };
-            // response
-            let list_to_display = html_render::LibraryDisplay {
-                user: user.clone(),
-                directories_list: Vec::with_capacity(0),
-                files_list: files_results_with_status,
-                library_id: None,
-                library_path: library_path.path,
-                current_path: None,
-                search_query: None,
-            };
-            Html(html_render::library_display(list_to_display))
         }
-        None => unauthorized_response(),
+        None => unauthorized_response().into_response(),
     }
 }
 
-// TODO use struct, like new_user_handler()
-async fn search_handler(
+async fn move_in_reading_list_handler(
     auth_session: AuthSession,
     State(conn): State<Pool<Sqlite>>,
-    query: String,
+    Path((list_id, file_id, direction)): Path<(i64, String, String)>,
 ) -> impl IntoResponse {
     match auth_session.user {
         Some(user) => {
-            info!("get /search : {}", &query);
-            // body string is `query=search_string`, we need only the `search_string`
-            let query = query.strip_prefix("query=").unwrap();
-            let query = &query.replace('+', " ");
-            // search files
-            let mut files_results = sqlite::search_file_from_string(query, &conn).await;
-            files_results.sort();
-            // add status (read, bookmark)
-            let user = sqlite::get_user(Some(&user.name), None, &conn).await;
-            let user = user.first().unwrap();
-            let mut files_results_with_status: Vec<(FileInfo, bool, bool)> =
-                Vec::with_capacity(files_results.capacity());
-            for file in files_results {
-                let bookmark_status =
-                    sqlite::get_flag_status("bookmark", user.id, &file.id, &conn).await;
-                let read_status =
-                    sqlite::get_flag_status("read_status", user.id, &file.id, &conn).await;
-                files_results_with_status.push((file, bookmark_status, read_status));
+            info!("post /lists/{list_id}/move/{file_id}/{direction}");
+            if !owned_reading_list(list_id, user.id, &conn).await {
+                return (StatusCode::NOT_FOUND, "reading list not found").into_response();
             }
-            // search dirs
-            let mut directories_results = sqlite::search_directory_from_string(query, &conn).await;
-            directories_results.sort();
-            // lib path
-            let library_path = sqlite::get_library(None, None, &conn).await;
-            let library_path = library_path.first().unwrap().to_owned();
-            // response
-            let list_to_display = html_render::LibraryDisplay {
-                user: user.clone(),
-                directories_list: directories_results,
-                files_list: files_results_with_status,
-                library_id: None,
-                library_path: library_path.path,
-                current_path: None,
-                search_query: Some(query.to_string()),
-            };
-            Html(html_render::library_display(list_to_display))
+            match direction.as_str() {
+                "up" => sqlite::move_in_reading_list(list_id, &file_id, true, &conn).await,
+                "down" => sqlite::move_in_reading_list(list_id, &file_id, false, &conn).await,
+                _ => return (StatusCode::BAD_REQUEST, "unknown direction").into_response(),
+            }
+            Redirect::to(&format!("/lists/{list_id}")).into_response()
         }
-        None => unauthorized_response(),
+        None => unauthorized_response().into_response(),
     }
 }
 
@@ -210,7 +1291,7 @@ async fn login_handler(
     if let Some(ref next) = creds.next {
         Redirect::to(next).into_response()
     } else {
-        Redirect::to("/library").into_response()
+        Redirect::to("/home").into_response()
     }
 }
 
@@ -262,12 +1343,13 @@ async fn infos_handler(
             let user = sqlite::get_user(Some(&user.name), None, &conn).await;
             let user = user.first().unwrap();
             let bookmark_status =
-                sqlite::get_flag_status("bookmark", user.id, &file.id, &conn).await;
+                sqlite::get_flag_status(&Flag::Bookmark, user.id, &file.id, &conn).await;
             let read_status =
-                sqlite::get_flag_status("read_status", user.id, &file.id, &conn).await;
+                sqlite::get_flag_status(&Flag::ReadStatus, user.id, &file.id, &conn).await;
 
             let current_page =
                 sqlite::get_current_page_from_file_id(user.id, &file.id, &conn).await;
+            let series = sqlite::get_file_series(&file.id, &conn).await;
 
             Html(html_render::file_info(
                 user,
@@ -276,6 +1358,7 @@ async fn infos_handler(
                 bookmark_status,
                 read_status,
                 up_link,
+                series,
             ))
         }
         None => unauthorized_response(),
@@ -286,19 +1369,111 @@ async fn infos_handler(
 async fn flag_handler(
     auth_session: AuthSession,
     State(conn): State<Pool<Sqlite>>,
-    Path((flag, file_id)): Path<(String, String)>,
+    Path((flag, file_id)): Path<(Flag, String)>,
 ) -> impl IntoResponse {
     match auth_session.user {
         Some(user) => {
             let user = sqlite::get_user(Some(&user.name), None, &conn).await;
             let user = user.first().unwrap();
             let flag_status = sqlite::set_flag_status(&flag, user.id, &file_id, &conn).await;
-            Html(html_render::flag_toggle(user, flag_status, &file_id, &flag))
+            Html(html_render::flag_toggle(
+                user,
+                flag_status,
+                &flag,
+                &format!("/infos/{file_id}"),
+            ))
+        }
+        None => unauthorized_response(),
+    }
+}
+
+/// add/remove flag (bookmark or read status) of a directory for a user
+/// read status is applied to all files of the directory and its sub directories
+async fn directory_flag_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+    Path((flag, directory_id)): Path<(Flag, String)>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            let user = sqlite::get_user(Some(&user.name), None, &conn).await;
+            let user = user.first().unwrap();
+            match sqlite::get_directory_from_id(&directory_id, &conn).await {
+                Some(directory) => {
+                    let flag_status = match flag {
+                        Flag::Bookmark => {
+                            sqlite::set_directory_bookmark_status(user.id, &directory.id, &conn)
+                                .await
+                        }
+                        Flag::ReadStatus => {
+                            // mark all files as read, unless they are already all read
+                            let hidden_formats = user.preferences.hidden();
+                            let file_count = sqlite::get_directory_file_count(
+                                &directory,
+                                &hidden_formats,
+                                &conn,
+                            )
+                            .await;
+                            let read_count = sqlite::get_directory_read_count(
+                                user.id,
+                                &directory,
+                                &hidden_formats,
+                                &conn,
+                            )
+                            .await;
+                            let read_status = read_count < file_count;
+                            sqlite::set_directory_read_status(
+                                user.id,
+                                &directory,
+                                read_status,
+                                &conn,
+                            )
+                            .await;
+                            read_status
+                        }
+                    };
+                    // go back to the parent directory, where the toggle link was
+                    let library_list = sqlite::get_library(None, None, &conn).await;
+                    let directory_url = directory_url(&directory, &library_list);
+                    let parent_url = match directory_url.rsplit_once('/') {
+                        Some((parent_url, _)) if parent_url != "/library" => parent_url,
+                        _ => "/library",
+                    };
+                    Html(html_render::flag_toggle(
+                        user,
+                        flag_status,
+                        &flag,
+                        parent_url,
+                    ))
+                }
+                None => Html(html_render::simple_message(
+                    "directory not found",
+                    Some("/library"),
+                )),
+            }
         }
         None => unauthorized_response(),
     }
 }
 
+/// redirect to the library url of a directory (used by search and bookmarks)
+async fn directory_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+    Path(directory_id): Path<String>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(_user) => match sqlite::get_directory_from_id(&directory_id, &conn).await {
+            Some(directory) => {
+                let library_list = sqlite::get_library(None, None, &conn).await;
+                Redirect::to(&directory_url(&directory, &library_list)).into_response()
+            }
+            None => Redirect::to("/library").into_response(),
+        },
+        None => unauthorized_response().into_response(),
+    }
+}
+
 async fn cover_handler(
     auth_session: AuthSession,
     State(conn): State<Pool<Sqlite>>,
@@ -335,7 +1510,8 @@ async fn cover_handler(
             };
             // return default cover if problem with database or cover empty or not supported format
             match file.format.as_str() {
-                "epub" | "pdf" | "cbz" | "cbr" | "cb7" => {
+                "epub" | "pdf" | "cbz" | "cbr" | "cb7" | "cbt" | "folder" | "mobi" | "azw3"
+                | "fb2" | "jpg" | "mp3" | "m4b" | "audiobook" => {
                     // get cover from database
                     let u8_cover = sqlite::get_cover_from_id(&file, &conn).await;
                     match u8_cover {
@@ -359,6 +1535,25 @@ async fn cover_handler(
                         None => default_cover,
                     }
                 }
+                // text files have no image, their title is drawn on a placeholder
+                "txt" | "md" => {
+                    let title = match sqlite::get_file_title(&file.id, &conn).await {
+                        Some(title) => title,
+                        None => std::path::Path::new(&file.name)
+                            .file_stem()
+                            .map(|stem| stem.to_string_lossy().to_string())
+                            .unwrap_or_default(),
+                    };
+                    (
+                        StatusCode::OK,
+                        [
+                            (header::CONTENT_TYPE, "image/svg+xml"),
+                            (header::CACHE_CONTROL, "no-cache"),
+                        ],
+                        text::cover_svg(&title, file.format.as_str()),
+                    )
+                        .into_response()
+                }
                 // format not suupported
                 _ => default_cover,
             }
@@ -371,6 +1566,7 @@ async fn download_handler(
     auth_session: AuthSession,
     State(conn): State<Pool<Sqlite>>,
     Path(file_id): Path<String>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
     match auth_session.user {
         Some(user) => {
@@ -380,64 +1576,435 @@ async fn download_handler(
                 None => FileInfo::new(),
             };
             let full_path = format!("{}/{}", file.parent_path, file.name);
-            // possible content-types : https://www.iana.org/assignments/media-types/media-types.xhtml
-            let content_type = match file.format.as_str() {
-                "epub" => "application/epub+zip",
-                "pdf" => "application/pdf",
-                "cbz" => "application/vnd.comicbook+zip",
-                "cbr" => "application/vnd.comicbook-rar",
-                _ => "",
+            // possible content-types : https://www.iana.org/assignments/media-types/media-types.xhtml
+            let content_type = match file.format.as_str() {
+                "epub" => "application/epub+zip",
+                "pdf" => "application/pdf",
+                // images folders are zipped on the fly
+                "cbz" | "folder" => "application/vnd.comicbook+zip",
+                "cbr" => "application/vnd.comicbook-rar",
+                "cb7" => "application/x-cb7",
+                "cbt" => "application/x-cbt",
+                "mobi" => "application/x-mobipocket-ebook",
+                "azw3" => "application/vnd.amazon.mobi8-ebook",
+                "fb2" if file.name.to_lowercase().ends_with(".zip") => {
+                    "application/x-zip-compressed-fb2"
+                }
+                "fb2" => "application/x-fictionbook+xml",
+                "txt" => "text/plain",
+                "md" => "text/markdown",
+                "jpg" => image::ImageFormat::from_path(&full_path)
+                    .map(|format| format.to_mime_type())
+                    .unwrap_or_default(),
+                "mp3" | "m4b" => audio::content_type(&file.name),
+                "audiobook" => "application/zip",
+                _ => "",
+            };
+            let file_name = match file.format {
+                Format::Folder => format!("{}.cbz", file.name),
+                Format::Audiobook => format!("{}.zip", file.name),
+                _ => file.name.clone(),
+            };
+            // directories are zipped while sent, files are streamed
+            let mut response = match file.format {
+                Format::Folder | Format::Audiobook => {
+                    let entries = reader::folder_zip_entries(&file);
+                    if entries.is_empty() {
+                        return (StatusCode::NOT_FOUND, "file not found").into_response();
+                    }
+                    let (zip_writer, zip_reader) = tokio::io::duplex(64 * 1024);
+                    let folder_name = file.name.clone();
+                    tokio::task::spawn_blocking(move || {
+                        let zip_writer = SyncIoBridge::new(zip_writer);
+                        // a closed connection also ends here
+                        if let Err(e) = zip_stream::write_stored_zip(zip_writer, &entries) {
+                            warn!("unable to zip folder '{folder_name}' : {e}");
+                        }
+                    });
+                    (
+                        StatusCode::OK,
+                        [
+                            (header::CONTENT_TYPE, content_type),
+                            (header::CACHE_CONTROL, "no-cache"),
+                        ],
+                        Body::from_stream(ReaderStream::new(zip_reader)),
+                    )
+                        .into_response()
+                }
+                _ => file_response(&full_path, content_type, &headers).await,
+            };
+            if response.status().is_success() {
+                if let Ok(content_disposition) =
+                    HeaderValue::try_from(format!("attachment; filename=\"{file_name}\""))
+                {
+                    response
+                        .headers_mut()
+                        .insert(header::CONTENT_DISPOSITION, content_disposition);
+                }
+            }
+            response
+        }
+        None => unauthorized_response().into_response(),
+    }
+}
+
+// TODO return image, origin or small
+async fn comic_page_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+    Path((file_id, page, size)): Path<(String, i32, String)>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            info!("get /reader/{} (page {}) : {}", &file_id, &page, &user.name);
+            let file = match sqlite::get_files_from_file_id(&file_id, &conn).await {
+                Some(file) => file,
+                None => FileInfo::new(),
+            };
+            match reader::get_comic_page(&file, page, &size).await {
+                Some(comic_board) => (
+                    StatusCode::OK,
+                    [
+                        (header::CONTENT_TYPE, "image/jpeg"),
+                        (header::CACHE_CONTROL, "no-cache"),
+                    ],
+                    comic_board,
+                )
+                    .into_response(),
+                None => Html(html_render::simple_message(
+                    "unable to get image",
+                    Some(&format!("/reader/{}", &file_id)),
+                ))
+                .into_response(),
+            }
+        }
+        None => unauthorized_response().into_response(),
+    }
+}
+
+/// images viewer, from the image clicked in a directory
+async fn directory_images_start_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+    Path(file_id): Path<String>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            info!("get /directory_images/{file_id} : {}", user.name);
+            let file = sqlite::get_files_from_file_id(&file_id, &conn)
+                .await
+                .unwrap_or_else(FileInfo::new);
+            let page = sqlite::get_directory_images(&file.parent_path, &conn)
+                .await
+                .iter()
+                .position(|image| image.id == file.id)
+                .unwrap_or_default();
+            Redirect::to(&format!("/directory_images/{file_id}/{page}")).into_response()
+        }
+        None => unauthorized_response().into_response(),
+    }
+}
+
+/// all the images of a directory in the comic reader
+async fn directory_images_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+    Path((file_id, page)): Path<(String, i32)>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            info!(
+                "get /directory_images/{file_id} (page {page}) : {}",
+                user.name
+            );
+            let file = match sqlite::get_files_from_file_id(&file_id, &conn).await {
+                Some(file) => file,
+                None => {
+                    return Html(html_render::simple_message("file not found", None))
+                        .into_response()
+                }
+            };
+            let image_count = sqlite::get_directory_images(&file.parent_path, &conn)
+                .await
+                .len() as i32;
+            let page = page.clamp(0, (image_count - 1).max(0));
+            Html(html_render::directory_images_reader(
+                &user,
+                &file,
+                page,
+                image_count,
+            ))
+            .into_response()
+        }
+        None => unauthorized_response().into_response(),
+    }
+}
+
+async fn directory_image_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+    Path((file_id, page, size)): Path<(String, usize, String)>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            info!(
+                "get /directory_image/{file_id} (page {page}) : {}",
+                user.name
+            );
+            let file = sqlite::get_files_from_file_id(&file_id, &conn)
+                .await
+                .unwrap_or_else(FileInfo::new);
+            let images = sqlite::get_directory_images(&file.parent_path, &conn).await;
+            let image = match images.get(page) {
+                Some(image) => reader::get_comic_page(image, 0, &size).await,
+                None => None,
+            };
+            match image {
+                Some(image) => (
+                    StatusCode::OK,
+                    [
+                        (header::CONTENT_TYPE, "image/jpeg"),
+                        (header::CACHE_CONTROL, "no-cache"),
+                    ],
+                    image,
+                )
+                    .into_response(),
+                None => Html(html_render::simple_message(
+                    "unable to get image",
+                    Some(&format!("/directory_images/{file_id}/{page}")),
+                ))
+                .into_response(),
+            }
+        }
+        None => unauthorized_response().into_response(),
+    }
+}
+
+/// set current page, a file read up to its last page is read
+async fn set_progress(file: &FileInfo, user: &User, page: i32, conn: &Pool<Sqlite>) {
+    // set page at current_page
+    sqlite::set_current_page_for_file_id(&file.id, &user.id, &page, conn).await;
+    // remove from reading table if last page
+    if page == file.total_pages - 1 {
+        sqlite::remove_file_id_from_reading(&file.id, &user.id, conn).await;
+        // and mark as read if needed
+        if !sqlite::get_flag_status(&Flag::ReadStatus, user.id, &file.id, conn).await {
+            let _ = sqlite::set_flag_status(&Flag::ReadStatus, user.id, &file.id, conn).await;
+        }
+    }
+}
+
+/// first range of a `Range: bytes=...` header, bounds included, none if not satisfiable
+fn parse_range(range: &str, length: u64) -> Option<(u64, u64)> {
+    let (start, end) = range
+        .trim()
+        .strip_prefix("bytes=")?
+        .split(',')
+        .next()?
+        .trim()
+        .split_once('-')?;
+    match (start.is_empty(), end.is_empty()) {
+        // suffix : the last bytes
+        (true, false) => {
+            let suffix: u64 = end.parse().ok()?;
+            (suffix > 0 && length > 0).then(|| (length.saturating_sub(suffix), length - 1))
+        }
+        (false, _) => {
+            let start: u64 = start.parse().ok()?;
+            let end = match end.is_empty() {
+                true => length.checked_sub(1)?,
+                false => end.parse::<u64>().ok()?.min(length.checked_sub(1)?),
+            };
+            (start <= end).then_some((start, end))
+        }
+        (true, true) => None,
+    }
+}
+
+/// entity tags of a `If-None-Match` header, weak ones compared as strong ones
+fn etag_matches(header: &str, etag: &str) -> bool {
+    header.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+    })
+}
+
+/// `If-None-Match`, or `If-Modified-Since` without it, matching the file : the client copy is fresh
+fn is_not_modified(headers: &HeaderMap, etag: &str, modified: Option<SystemTime>) -> bool {
+    match headers.get(header::IF_NONE_MATCH) {
+        Some(if_none_match) => if_none_match
+            .to_str()
+            .is_ok_and(|if_none_match| etag_matches(if_none_match, etag)),
+        None => headers
+            .get(header::IF_MODIFIED_SINCE)
+            .and_then(|since| since.to_str().ok())
+            .and_then(|since| httpdate::parse_http_date(since).ok())
+            .zip(modified)
+            .is_some_and(|(since, modified)| modified <= since),
+    }
+}
+
+/// a `Range` applies unless a `If-Range` validator no longer matches the file
+fn is_range_valid(headers: &HeaderMap, etag: &str, modified: Option<SystemTime>) -> bool {
+    match headers
+        .get(header::IF_RANGE)
+        .map(|if_range| if_range.to_str())
+    {
+        None => true,
+        // strong comparison, weak tags never match
+        Some(Ok(if_range)) if if_range.starts_with('"') => if_range == etag,
+        Some(Ok(if_range)) => httpdate::parse_http_date(if_range)
+            .ok()
+            .zip(modified)
+            .is_some_and(|(date, modified)| modified == date),
+        Some(Err(_)) => false,
+    }
+}
+
+/// a file streamed from disk, whole or the byte range asked by a `Range` header,
+/// with validators for conditional requests (`If-None-Match`, `If-Modified-Since`, `If-Range`)
+async fn file_response(path: &str, content_type: &str, headers: &HeaderMap) -> Response {
+    let (mut file, metadata) = match tokio::fs::File::open(path).await {
+        Ok(file) => match file.metadata().await {
+            Ok(metadata) => (file, metadata),
+            Err(e) => {
+                warn!("unable to read metadata of file {path} : {e}");
+                return (StatusCode::NOT_FOUND, "file not found").into_response();
+            }
+        },
+        Err(e) => {
+            warn!("file {path} not found : {e}");
+            return (StatusCode::NOT_FOUND, "file not found").into_response();
+        }
+    };
+    let length = metadata.len();
+    // http dates have no subsecond
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|modified| UNIX_EPOCH + std::time::Duration::from_secs(modified.as_secs()));
+    let etag = format!(
+        "\"{:x}-{length:x}\"",
+        modified
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|modified| modified.as_secs())
+            .unwrap_or_default()
+    );
+    let mut validators = vec![
+        (header::ETAG, etag.clone()),
+        (header::CACHE_CONTROL, "no-cache".to_string()),
+    ];
+    if let Some(modified) = modified {
+        validators.push((header::LAST_MODIFIED, httpdate::fmt_http_date(modified)));
+    }
+    let range = headers
+        .get(header::RANGE)
+        .and_then(|range| range.to_str().ok())
+        .filter(|_| is_range_valid(headers, &etag, modified))
+        .map(|range| parse_range(range, length));
+    let mut response = match range {
+        _ if is_not_modified(headers, &etag, modified) => StatusCode::NOT_MODIFIED.into_response(),
+        None => (
+            StatusCode::OK,
+            [
+                (header::CONTENT_TYPE, content_type.to_string()),
+                (header::CONTENT_LENGTH, length.to_string()),
+                (header::ACCEPT_RANGES, "bytes".to_string()),
+            ],
+            Body::from_stream(ReaderStream::new(file)),
+        )
+            .into_response(),
+        Some(Some((start, end))) => {
+            if let Err(e) = file.seek(std::io::SeekFrom::Start(start)).await {
+                warn!("unable to seek in file {path} : {e}");
+                return (StatusCode::INTERNAL_SERVER_ERROR, "unable to read file").into_response();
+            }
+            (
+                StatusCode::PARTIAL_CONTENT,
+                [
+                    (header::CONTENT_TYPE, content_type.to_string()),
+                    (header::CONTENT_LENGTH, (end - start + 1).to_string()),
+                    (header::ACCEPT_RANGES, "bytes".to_string()),
+                    (
+                        header::CONTENT_RANGE,
+                        format!("bytes {start}-{end}/{length}"),
+                    ),
+                ],
+                Body::from_stream(ReaderStream::new(file.take(end - start + 1))),
+            )
+                .into_response()
+        }
+        Some(None) => (
+            StatusCode::RANGE_NOT_SATISFIABLE,
+            [(header::CONTENT_RANGE, format!("bytes */{length}"))],
+        )
+            .into_response(),
+    };
+    for (name, value) in validators {
+        if let Ok(value) = value.parse() {
+            response.headers_mut().insert(name, value);
+        }
+    }
+    response
+}
+
+#[derive(Deserialize)]
+struct AudioPath {
+    file_id: String,
+    // tracks of audiobooks directories
+    track: Option<usize>,
+}
+/// audio of an audiobook file, or a track of an audiobook directory
+async fn audio_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+    Path(AudioPath { file_id, track }): Path<AudioPath>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            debug!("get /audio/{file_id} (track {track:?}) : {}", user.name);
+            let file = sqlite::get_files_from_file_id(&file_id, &conn)
+                .await
+                .unwrap_or_else(FileInfo::new);
+            let full_path = format!("{}/{}", file.parent_path, file.name);
+            let track_path = match file.format {
+                Format::Audiobook => {
+                    let track_list = audio::folder_audio_list(std::path::Path::new(&full_path));
+                    track_list
+                        .get(track.unwrap_or_default())
+                        .map(|track| format!("{full_path}/{track}"))
+                }
+                Format::Mp3 | Format::M4b => Some(full_path),
+                _ => None,
             };
-            if let Ok(file_content) = fs::read(full_path) {
-                (
-                    StatusCode::OK,
-                    [
-                        (header::CONTENT_TYPE, content_type),
-                        (header::CACHE_CONTROL, "no-cache"),
-                    ],
-                    [(
-                        header::CONTENT_DISPOSITION,
-                        format!("attachment; filename=\"{}\"", &file.name),
-                    )],
-                    file_content,
-                )
-                    .into_response()
-            } else {
-                (StatusCode::NOT_FOUND, "file not found").into_response()
+            match track_path {
+                Some(track_path) => {
+                    file_response(&track_path, audio::content_type(&track_path), &headers).await
+                }
+                None => (StatusCode::NOT_FOUND, "audio not found").into_response(),
             }
         }
         None => unauthorized_response().into_response(),
     }
 }
 
-// TODO return image, origin or small
-async fn comic_page_handler(
+/// listening position of the player, in seconds
+async fn listen_handler(
     auth_session: AuthSession,
     State(conn): State<Pool<Sqlite>>,
-    Path((file_id, page, size)): Path<(String, i32, String)>,
+    Path((file_id, position)): Path<(String, i32)>,
 ) -> impl IntoResponse {
     match auth_session.user {
         Some(user) => {
-            info!("get /reader/{} (page {}) : {}", &file_id, &page, &user.name);
-            let file = match sqlite::get_files_from_file_id(&file_id, &conn).await {
-                Some(file) => file,
-                None => FileInfo::new(),
-            };
-            match reader::get_comic_page(&file, page, &size).await {
-                Some(comic_board) => (
-                    StatusCode::OK,
-                    [
-                        (header::CONTENT_TYPE, "image/jpeg"),
-                        (header::CACHE_CONTROL, "no-cache"),
-                    ],
-                    comic_board,
-                )
-                    .into_response(),
-                None => Html(html_render::simple_message(
-                    "unable to get image",
-                    Some(&format!("/reader/{}", &file_id)),
-                ))
-                .into_response(),
+            debug!("post /listen/{file_id}/{position} : {}", user.name);
+            match sqlite::get_files_from_file_id(&file_id, &conn).await {
+                Some(file) => {
+                    let position = position.clamp(0, (file.total_pages - 1).max(0));
+                    set_progress(&file, &user, position, &conn).await;
+                    StatusCode::NO_CONTENT.into_response()
+                }
+                None => (StatusCode::NOT_FOUND, "file not found").into_response(),
             }
         }
         None => unauthorized_response().into_response(),
@@ -448,6 +2015,7 @@ async fn reader_handler(
     auth_session: AuthSession,
     State(conn): State<Pool<Sqlite>>,
     Path((file_id, page)): Path<(String, i32)>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
     // TODO set current page to 0 if not provided ?
     // let page: i32 = page.unwrap_or(0);
@@ -468,54 +2036,78 @@ async fn reader_handler(
             } else {
                 page
             };
-            // set page at current_page
-            sqlite::set_current_page_for_file_id(&file.id, &user.id, &page, &conn).await;
-            // remove from reading table if last page
-            if page == file.total_pages - 1 {
-                sqlite::remove_file_id_from_reading(&file.id, &user.id, &conn).await;
-                // and mark as read if needed
-                if !sqlite::get_flag_status("read_status", user.id, &file.id, &conn).await {
-                    let _ = sqlite::set_flag_status("read_status", user.id, &file.id, &conn).await;
-                }
-            }
+            set_progress(&file, &user, page, &conn).await;
+
+            // on the last page, offer the next file of reading lists
+            let next_files = if page == file.total_pages - 1 {
+                sqlite::get_next_in_reading_lists(&file.id, user.id, &conn).await
+            } else {
+                Vec::with_capacity(0)
+            };
 
             let response = match file.format.as_str() {
                 "epub" => {
                     let epub_reader = reader::epub(&file, page).await;
-                    Html(html_render::ebook_reader(&user, &file, &epub_reader, page))
-                        .into_response()
+                    Html(html_render::ebook_reader(
+                        &user,
+                        &file,
+                        &epub_reader,
+                        page,
+                        &next_files,
+                    ))
+                    .into_response()
+                }
+                "mobi" | "azw3" | "fb2" => {
+                    let chapter = match file.format {
+                        Format::Fb2 => reader::fb2(&file, page),
+                        _ => reader::mobi(&file, page),
+                    };
+                    Html(html_render::ebook_reader(
+                        &user,
+                        &file,
+                        &chapter,
+                        page,
+                        &next_files,
+                    ))
+                    .into_response()
                 }
                 "pdf" => {
-                    let pdf_file = fs::read(format!("{}/{}", &file.parent_path, &file.name));
-                    match pdf_file {
-                        Ok(pdf_file) => (
-                            StatusCode::OK,
-                            [
-                                (header::CONTENT_TYPE, "application/pdf"),
-                                (header::CACHE_CONTROL, "no-cache"),
-                            ],
-                            pdf_file,
-                        )
-                            .into_response(),
-                        Err(e) => {
-                            warn!(
-                                "pdf file {}/{} not found : {e}",
-                                &file.parent_path, &file.name
-                            );
-                            // TODO true 404
-                            (StatusCode::NOT_FOUND, "file not found").into_response()
-                        }
-                    }
+                    file_response(
+                        &format!("{}/{}", &file.parent_path, &file.name),
+                        "application/pdf",
+                        &headers,
+                    )
+                    .await
                 }
                 // "cbr" => reader::cbr(&user, file),
-                "cbz" | "cbr" | "cb7" => {
+                "mp3" | "m4b" | "audiobook" => {
+                    let chapters = sqlite::get_chapters(&file.id, &conn).await;
+                    Html(html_render::audio_player(
+                        &user,
+                        &file,
+                        page,
+                        chapters,
+                        &next_files,
+                    ))
+                    .into_response()
+                }
+                "cbz" | "cbr" | "cb7" | "cbt" | "folder" | "jpg" => {
                     // let comic_reader = reader::comics(&file, page).await;
                     // Html(html_render::ebook_reader(&user, &file, &comic_reader, page)).into_response()
-                    Html(html_render::comic_reader(&user, &file, page)).into_response()
+                    Html(html_render::comic_reader(&user, &file, page, &next_files)).into_response()
+                }
+                "txt" | "md" => {
+                    let pagination = sqlite::get_text_pagination(&conn).await;
+                    let text_page = reader::text(&file, page, &pagination);
+                    Html(html_render::ebook_reader(
+                        &user,
+                        &file,
+                        &text_page,
+                        page,
+                        &next_files,
+                    ))
+                    .into_response()
                 }
-                // TODO txt and raw readers
-                // "txt" => reader::txt(&user, file),
-                // "raw" => reader::raw(&user, file),
                 // TODO real rendered page
                 _ => Html(html_render::simple_message("no yet supported", None)).into_response(),
             };
@@ -537,8 +2129,15 @@ async fn admin_handler(
                 let library_list = sqlite::get_library(None, None, &conn).await;
                 // users
                 let user_list = sqlite::get_user(None, None, &conn).await;
+                let text_pagination = sqlite::get_text_pagination(&conn).await;
                 // render
-                Html(html_render::admin(&user, library_list, user_list)).into_response()
+                Html(html_render::admin(
+                    &user,
+                    library_list,
+                    user_list,
+                    text_pagination,
+                ))
+                .into_response()
             } else {
                 // TODO better display, and redirect to `/` after 3s
                 Html("You are not allowed to see this page").into_response()
@@ -549,11 +2148,149 @@ async fn admin_handler(
 }
 
 // TODO
-async fn prefs_handler(auth_session: AuthSession) -> impl IntoResponse {
+async fn prefs_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+) -> impl IntoResponse {
     match auth_session.user {
         Some(user) => {
             info!("get /prefs : {}", &user.name);
-            Html(html_render::prefs(&user)).into_response()
+            let api_token = sqlite::get_api_token(user.id, &conn).await;
+            let library_list = sqlite::get_library(None, None, &conn).await;
+            Html(html_render::prefs(&user, api_token, library_list)).into_response()
+        }
+        None => unauthorized_response().into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct FormPreferences {
+    view: View,
+    theme: Theme,
+    // checkbox, absent when unchecked
+    readable_only: Option<String>,
+    // `hide_{format}` checkboxes
+    #[serde(flatten)]
+    hidden_formats: HashMap<String, String>,
+}
+async fn save_prefs_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+    Form(body): Form<FormPreferences>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            info!("post /prefs : {}", user.name);
+            let preferences = Preferences {
+                view: body.view,
+                readable_only: body.readable_only.is_some(),
+                theme: body.theme,
+                typography: user.preferences.typography,
+                hidden_formats: hidden_formats_from_form(&body.hidden_formats),
+            };
+            sqlite::set_preferences(user.id, &preferences, &conn).await;
+            Redirect::to("/prefs").into_response()
+        }
+        None => unauthorized_response().into_response(),
+    }
+}
+
+/// formats of the checked `hide_{format}` checkboxes
+fn hidden_formats_from_form(form: &HashMap<String, String>) -> HiddenFormats {
+    HiddenFormats(
+        Format::ALL
+            .into_iter()
+            .filter(|format| form.contains_key(&format!("hide_{}", format.key())))
+            .collect(),
+    )
+}
+
+#[derive(Deserialize)]
+struct FormTypography {
+    reader_font: ReaderFont,
+    reader_font_size: i64,
+    reader_line_height: i64,
+    reader_margin: i64,
+    // checkbox, absent when unchecked
+    reader_justify: Option<String>,
+}
+async fn typography_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+    Form(body): Form<FormTypography>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            info!("post /prefs/reader : {}", user.name);
+            // keep values in a readable range
+            let typography = Typography {
+                reader_font: body.reader_font,
+                reader_font_size: body.reader_font_size.clamp(50, 300),
+                reader_line_height: body.reader_line_height.clamp(100, 300),
+                reader_margin: body.reader_margin.clamp(0, 30),
+                reader_justify: body.reader_justify.is_some(),
+            };
+            sqlite::set_typography(user.id, &typography, &conn).await;
+            Redirect::to("/prefs").into_response()
+        }
+        None => unauthorized_response().into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct FormPassword {
+    current_password: String,
+    new_password: String,
+    confirmation: String,
+}
+async fn password_handler(
+    mut auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+    Form(body): Form<FormPassword>,
+) -> impl IntoResponse {
+    match auth_session.user.clone() {
+        Some(mut user) => {
+            info!("post /prefs/password : {}", user.name);
+            let message = if verify_password(&body.current_password, &user.password_hash).is_err() {
+                "wrong current password"
+            } else if body.new_password.is_empty() || body.new_password != body.confirmation {
+                "new passwords are empty or differ"
+            } else {
+                match hash_password(&body.new_password) {
+                    Ok(hashed_password) => {
+                        user.password_hash = hashed_password;
+                        sqlite::update_user(&user, &conn).await;
+                        // the session is bound to the password hash, log in again
+                        match auth_session.login(&user).await {
+                            Ok(_) => "password changed",
+                            Err(e) => {
+                                warn!("unable to renew session of {} : {e}", user.name);
+                                "password changed, please log in again"
+                            }
+                        }
+                    }
+                    Err(_) => "unable to change password, see logs",
+                }
+            };
+            Html(html_render::simple_message(message, Some("/prefs"))).into_response()
+        }
+        None => unauthorized_response().into_response(),
+    }
+}
+
+/// generate a new api token, the previous one is revoked
+async fn api_token_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            info!("post /prefs/token : {}", user.name);
+            let mut bytes = [0u8; 32];
+            OsRng.fill_bytes(&mut bytes);
+            let api_token: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+            sqlite::set_api_token(user.id, &api_token, &conn).await;
+            Redirect::to("/prefs").into_response()
         }
         None => unauthorized_response().into_response(),
     }
@@ -746,6 +2483,79 @@ async fn change_user_handler(
     }
 }
 
+/// files whose extraction failed, admin only
+async fn problem_files_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            info!("get /admin/problems : {}", user.name);
+            if user.role == Role::Admin {
+                let problem_files = sqlite::get_problem_files(&conn).await;
+                Html(html_render::problem_files(&user, problem_files)).into_response()
+            } else {
+                unauthorized_response().into_response()
+            }
+        }
+        None => unauthorized_response().into_response(),
+    }
+}
+
+/// flag files with problems to be extracted again, admin only
+async fn rescan_problem_files_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            info!("post /admin/problems : {}", user.name);
+            if user.role == Role::Admin {
+                sqlite::rescan_problem_files(&conn).await;
+                Html(html_render::simple_message(
+                    "problem files will be extracted again",
+                    Some("/admin/problems"),
+                ))
+                .into_response()
+            } else {
+                unauthorized_response().into_response()
+            }
+        }
+        None => unauthorized_response().into_response(),
+    }
+}
+
+async fn text_pagination_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+    Form(body): Form<TextPagination>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            info!("post /admin/text_pagination : {}", user.name);
+            if user.role == Role::Admin {
+                // keep pages in a readable range
+                let (min, max) = body.text_page_unit.size_range();
+                let pagination = TextPagination {
+                    text_page_size: body.text_page_size.clamp(min, max),
+                    ..body
+                };
+                let previous = sqlite::get_text_pagination(&conn).await;
+                sqlite::set_text_pagination(&pagination, &conn).await;
+                if previous != pagination {
+                    // readers stay on the same text, other files are counted again
+                    scanner::convert_text_reading_pages(&previous, &pagination, &conn).await;
+                    sqlite::rescan_text_files(&conn).await;
+                }
+                Redirect::to("/admin").into_response()
+            } else {
+                unauthorized_response().into_response()
+            }
+        }
+        None => unauthorized_response().into_response(),
+    }
+}
+
 // TODO admin only and call delete_library fn...
 async fn admin_library_handler(
     auth_session: AuthSession,
@@ -811,6 +2621,46 @@ async fn admin_library_handler(
     }
 }
 
+/// formats hidden from every user in a library
+async fn library_formats_handler(
+    auth_session: AuthSession,
+    State(conn): State<Pool<Sqlite>>,
+    Path(library_id): Path<String>,
+    Form(body): Form<HashMap<String, String>>,
+) -> impl IntoResponse {
+    match auth_session.user {
+        Some(user) => {
+            // only admin
+            if user.role == Role::Admin {
+                match sqlite::get_library(None, Some(&library_id), &conn)
+                    .await
+                    .first()
+                {
+                    Some(library) => {
+                        info!(
+                            "user [{}] changed hidden formats of library [{}]",
+                            user.name, library.name
+                        );
+                        let hidden_formats = hidden_formats_from_form(&body);
+                        sqlite::set_library_hidden_formats(library, &hidden_formats, &conn).await;
+                        sqlite::update_library_file_count(library, &conn).await;
+                        sqlite::update_library_directories_file_count(library, &conn).await;
+                        Redirect::to("/admin").into_response()
+                    }
+                    None => Html(html_render::simple_message(
+                        "unable to find library in database",
+                        Some("/admin"),
+                    ))
+                    .into_response(),
+                }
+            } else {
+                unauthorized_response().into_response()
+            }
+        }
+        None => unauthorized_response().into_response(),
+    }
+}
+
 // TODO better display, and redirect to `/` after 3s
 fn unauthorized_response() -> Html<String> {
     Html(String::from("You are not allowed to see this page"))
@@ -823,7 +2673,10 @@ fn authent_error() -> String {
 
 async fn library_handler(
     auth_session: AuthSession,
+    session: Session,
     State(conn): State<Pool<Sqlite>>,
+    Query(sort_query): Query<SortQuery>,
+    Query(page_query): Query<PageQuery>,
     path: Option<Path<String>>,
 ) -> impl IntoResponse {
     match auth_session.user {
@@ -867,12 +2720,20 @@ async fn library_handler(
                 library_as_directories_list.sort();
                 html_render::LibraryDisplay {
                     user: user.clone(),
-                    directories_list: library_as_directories_list,
+                    // libraries have no flags
+                    directories_list: library_as_directories_list
+                        .into_iter()
+                        .map(|library| (library, false, 0))
+                        .collect(),
                     files_list: Vec::new(),
                     library_id: None,
                     library_path: "/".to_string(),
                     current_path: Some(sub_path.clone()),
-                    search_query: None,
+                    search: None,
+                    series: None,
+                    sort: None,
+                    pagination: None,
+                    progress: HashMap::with_capacity(0),
                 }
             // if sub_path is not empty, we are in a specific library (`/library/foo`)
             } else {
@@ -917,38 +2778,8 @@ async fn library_handler(
                 let user = user.first().unwrap();
 
                 // construct lists
-                let mut files_list_with_status: Vec<(FileInfo, bool, bool)> = {
-                    // TODO pagination ? set limit in conf
-                    let files_list: Vec<FileInfo> =
-                        match sqlx::query_as("SELECT * FROM files WHERE parent_path = ?;")
-                            .bind(&query_parent_path)
-                            .fetch_all(&conn)
-                            .await
-                        {
-                            Ok(files_list) => files_list,
-                            Err(e) => {
-                                warn!("empty library : {}", e);
-                                let empty_list: Vec<FileInfo> = Vec::with_capacity(0);
-                                empty_list
-                            }
-                        };
-                    // add bookmark and read status to the list
-                    let mut files_list_with_status: Vec<(FileInfo, bool, bool)> =
-                        Vec::with_capacity(files_list.capacity());
-                    for file in files_list {
-                        let bookmark_status =
-                            sqlite::get_flag_status("bookmark", user.id, &file.id, &conn).await;
-                        let read_status =
-                            sqlite::get_flag_status("read_status", user.id, &file.id, &conn).await;
-                        files_list_with_status.push((file, bookmark_status, read_status));
-                    }
-                    files_list_with_status
-                };
-                files_list_with_status.sort();
-
                 let mut directories_list: Vec<DirectoryInfo> = {
                     info!("get /library{} : {}", &sub_path, &user.name);
-                    // TODO set limit in conf
                     let directories_list: Vec<DirectoryInfo> =
                         match sqlx::query_as("SELECT * FROM directories WHERE parent_path = ?;")
                             .bind(&query_parent_path)
@@ -965,14 +2796,89 @@ async fn library_handler(
                     directories_list
                 };
                 directories_list.sort();
+                let sort = sort_order(&session, sort_query).await;
+                let base_url = format!("/library{sub_path}?");
+                let (directories_list, files_list, pagination) = match sort {
+                    // only the files of the page are fetched
+                    SortOrder::Added | SortOrder::Size => {
+                        let hidden_formats = user.preferences.hidden();
+                        let total_files =
+                            sqlite::count_files_in_path(&query_parent_path, &hidden_formats, &conn)
+                                .await;
+                        let pagination = pagination(
+                            &session,
+                            page_query,
+                            directories_list.len() as i64 + total_files,
+                            base_url,
+                        )
+                        .await;
+                        let (directories_range, files_range) =
+                            page_ranges(pagination.range(), directories_list.len());
+                        let files_list = sqlite::get_files_page_in_path(
+                            &query_parent_path,
+                            &hidden_formats,
+                            sort,
+                            files_range.start,
+                            files_range.len(),
+                            &conn,
+                        )
+                        .await;
+                        let directories_list = directories_list
+                            .into_iter()
+                            .skip(directories_range.start)
+                            .take(directories_range.len())
+                            .collect();
+                        (directories_list, files_list, pagination)
+                    }
+                    // natural order, A-Z bar and reading state need all files
+                    _ => {
+                        let files_list: Vec<FileInfo> =
+                            match sqlx::query_as("SELECT * FROM files WHERE parent_path = ?;")
+                                .bind(&query_parent_path)
+                                .fetch_all(&conn)
+                                .await
+                            {
+                                Ok(files_list) => files_list,
+                                Err(e) => {
+                                    warn!("empty library : {}", e);
+                                    let empty_list: Vec<FileInfo> = Vec::with_capacity(0);
+                                    empty_list
+                                }
+                            };
+                        let mut files_list = visible_files(user, files_list, &conn).await;
+                        sort_files(&mut files_list, sort, user.id, &conn).await;
+                        paginate(
+                            &session,
+                            page_query,
+                            directories_list,
+                            files_list,
+                            sort,
+                            base_url,
+                        )
+                        .await
+                    }
+                };
+                // add bookmark and read status to the page
+                let files_list_with_status =
+                    sqlite::get_files_flags(files_list, user.id, &conn).await;
                 html_render::LibraryDisplay {
                     user: user.clone(),
-                    directories_list,
+                    directories_list: sqlite::get_directories_flags(
+                        directories_list,
+                        user.id,
+                        &user.preferences.hidden(),
+                        &conn,
+                    )
+                    .await,
+                    progress: files_progress(user, &files_list_with_status, &conn).await,
                     files_list: files_list_with_status,
                     library_id: Some(library.id),
                     library_path: query_parent_path.to_string(),
                     current_path: Some(sub_path),
-                    search_query: None,
+                    search: None,
+                    series: None,
+                    sort: Some(sort),
+                    pagination: Some(pagination),
                 }
             };
             Html(html_render::library_display(list_to_display))
@@ -984,8 +2890,8 @@ async fn library_handler(
 async fn get_root(auth_session: AuthSession) -> impl IntoResponse {
     match auth_session.user {
         Some(_) => {
-            debug!("GET /, user found, redirect to /library");
-            axum::response::Redirect::permanent("/library").into_response()
+            debug!("GET /, user found, redirect to /home");
+            axum::response::Redirect::to("/home").into_response()
         }
         None => {
             debug!("GET /, no user found, login form");
@@ -1099,11 +3005,16 @@ async fn get_root_file(Path(path): Path<String>) -> impl IntoResponse {
 /// create css from binary if not found on disk
 // TODO add a clap option to specify css directory
 // TODO use struct ?
-fn create_css() -> String {
-    // original css file
-    let eloran_css_original = include_bytes!("../css/eloran.css");
-    let mut eloran_css = match std::str::from_utf8(eloran_css_original) {
-        Ok(eloran_css) => eloran_css.to_string(),
+fn create_css(name: &str) -> Option<String> {
+    // original css files
+    let original_css: &[u8] = match name {
+        "eloran.css" => include_bytes!("../css/eloran.css"),
+        "theme-dark.css" => include_bytes!("../css/theme-dark.css"),
+        "theme-sepia.css" => include_bytes!("../css/theme-sepia.css"),
+        _ => return None,
+    };
+    let mut css = match std::str::from_utf8(original_css) {
+        Ok(css) => css.to_string(),
         Err(_) => String::from(""),
     };
     // if custom css exists, use them
@@ -1113,27 +3024,25 @@ fn create_css() -> String {
         for file in css_files.flatten() {
             let filename = file.file_name();
             let filename = filename.to_str().unwrap();
-            if filename.contains("eloran.css") {
-                eloran_css = fs::read_to_string(file.path()).unwrap();
-            } else {
+            if filename == name {
+                css = fs::read_to_string(file.path()).unwrap();
+            } else if !["eloran.css", "theme-dark.css", "theme-sepia.css"].contains(&filename) {
                 warn!(
-                    "css file must be named eloran.css, file [{}] will be ignored",
+                    "css file must be named eloran.css, theme-dark.css or theme-sepia.css, file [{}] will be ignored",
                     filename
                 );
             }
         }
     }
-    eloran_css
+    Some(css)
 }
 
 /// serve css (custom file can be loaded)
 async fn get_css(Path(path): Path<String>) -> impl IntoResponse {
     info!("get /css/{}", &path);
-    // store css file in var
-    let css = create_css();
-    // return css if found
-    match path.as_str() {
-        "eloran.css" => (
+    // return embedded (or custom) css if found
+    match create_css(&path) {
+        Some(css) => (
             StatusCode::OK,
             [
                 (header::CONTENT_TYPE, "text/css"),
@@ -1142,8 +3051,8 @@ async fn get_css(Path(path): Path<String>) -> impl IntoResponse {
             css,
         )
             .into_response(),
-        // useless : the html headers uses only eloran.css but perhaps in the future...
-        _ => {
+        // useless : the html headers uses only embedded css but perhaps in the future...
+        None => {
             let css_file_content = fs::read_to_string(format!("css/{path}"));
             match css_file_content {
                 Ok(css) => {
@@ -1283,11 +3192,18 @@ impl AuthnBackend for Backend {
         }))
     }
     async fn get_user(&self, user_id: &UserId<Self>) -> Result<Option<Self::User>, Self::Error> {
-        let user = sqlx::query_as("select * from users where id = ?")
+        let user: Option<Self::User> = sqlx::query_as("select * from users where id = ?")
             .bind(user_id)
             .fetch_optional(&self.db)
             .await?;
-        Ok(user)
+        match user {
+            Some(mut user) => {
+                user.collections = sqlite::get_collections(user.id, &self.db).await;
+                user.preferences = sqlite::get_preferences(user.id, &self.db).await;
+                Ok(Some(user))
+            }
+            None => Ok(None),
+        }
     }
 }
 
@@ -1315,24 +3231,86 @@ async fn create_router() -> Router {
                 // 🔒🔒🔒 ADMIN PROTECTED 🔒🔒🔒
                 .route("/admin", get(admin_handler))
                 .route("/admin/library/{library_id}", post(admin_library_handler))
+                .route(
+                    "/admin/library/{library_id}/formats",
+                    post(library_formats_handler),
+                )
+                .route(
+                    "/admin/problems",
+                    get(problem_files_handler).post(rescan_problem_files_handler),
+                )
+                .route("/admin/text_pagination", post(text_pagination_handler))
                 .route("/admin/library/new", post(new_library_handler))
                 .route("/admin/user/{user_id}", post(change_user_handler))
                 .route("/admin/user/new", post(new_user_handler))
                 // TODO PROTECT HERE : add a layer (Role::Admin) if possible
                 // 🔒🔒🔒 PROTECTED 🔒🔒🔒
-                .route("/prefs", get(prefs_handler))
+                .route("/prefs", get(prefs_handler).post(save_prefs_handler))
+                .route("/prefs/password", post(password_handler))
+                .route("/prefs/reader", post(typography_handler))
+                .route("/prefs/token", post(api_token_handler))
+                .route("/home", get(home_handler))
+                .route("/recent", get(recent_handler))
                 .route("/library", get(library_handler))
                 .route("/library/{*path}", get(library_handler))
                 .route("/toggle/{flag}/{id}", get(flag_handler))
+                .route("/toggle/directory/{flag}/{id}", get(directory_flag_handler))
+                .route("/directory/{directory_id}", get(directory_handler))
                 .route("/bookmarks", get(bookmarks_handler))
                 .route("/reading", get(reading_handler))
-                .route("/search", post(search_handler))
+                .route("/search", get(search_handler))
+                .route("/collections/new", post(new_collection_handler))
+                .route("/collections/{collection_id}", get(collection_handler))
+                .route(
+                    "/collections/{collection_id}/delete",
+                    post(delete_collection_handler),
+                )
+                .route("/series", get(series_list_handler))
+                .route("/series/{series_id}", get(series_handler))
+                .route("/lists", get(reading_lists_handler))
+                .route("/lists/new", post(new_reading_list_handler))
+                .route("/lists/{list_id}", get(reading_list_handler))
+                .route("/lists/{list_id}/edit", post(edit_reading_list_handler))
+                .route("/lists/{list_id}/delete", post(delete_reading_list_handler))
+                .route("/lists/file/{file_id}", get(file_reading_lists_handler))
+                .route(
+                    "/lists/{list_id}/add/{file_id}",
+                    post(add_to_reading_list_handler),
+                )
+                .route(
+                    "/lists/{list_id}/remove/{file_id}",
+                    post(remove_from_reading_list_handler),
+                )
+                .route(
+                    "/lists/{list_id}/move/{file_id}/{direction}",
+                    post(move_in_reading_list_handler),
+                )
+                .route("/api/collections", get(api_collections_handler))
+                .route(
+                    "/api/collections/{collection_id}",
+                    get(api_collection_handler),
+                )
                 .route("/download/{file_id}", get(download_handler))
                 .route("/read/{file_id}/{page}", get(reader_handler))
                 .route(
                     "/comic_page/{file_id}/{page}/{size}",
                     get(comic_page_handler),
                 )
+                .route("/audio/{file_id}", get(audio_handler))
+                .route("/audio/{file_id}/{track}", get(audio_handler))
+                .route("/listen/{file_id}/{position}", post(listen_handler))
+                .route(
+                    "/directory_images/{file_id}",
+                    get(directory_images_start_handler),
+                )
+                .route(
+                    "/directory_images/{file_id}/{page}",
+                    get(directory_images_handler),
+                )
+                .route(
+                    "/directory_image/{file_id}/{page}/{size}",
+                    get(directory_image_handler),
+                )
                 .route("/infos/{file_id}", get(infos_handler))
                 .route("/cover/{file_id}", get(cover_handler))
                 .route_layer(login_required!(Backend, login_url = "/"))
@@ -1342,6 +3320,7 @@ async fn create_router() -> Router {
                 .route("/{path}", get(get_root_file))
                 .route("/css/{*path}", get(get_css))
                 .route("/fonts/{*path}", get(get_fonts))
+                .route("/feed/{library_id}", get(feed_handler))
                 .with_state(pool)
                 .route("/images/{*path}", get(get_images)) // ⚠️  UI images, not covers
                 .route("/login", post(login_handler))
@@ -1401,6 +3380,83 @@ mod tests {
     use axum_test::TestServer;
     use sqlx::{migrate::MigrateDatabase, Sqlite};
 
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("bytes=0-99", 1000), Some((0, 99)));
+        assert_eq!(parse_range("bytes=500-", 1000), Some((500, 999)));
+        assert_eq!(parse_range("bytes=-200", 1000), Some((800, 999)));
+        assert_eq!(parse_range("bytes=900-2000", 1000), Some((900, 999)));
+        assert_eq!(parse_range("bytes=-2000", 1000), Some((0, 999)));
+        assert_eq!(parse_range("bytes=1000-", 1000), None);
+        assert_eq!(parse_range("bytes=10-5", 1000), None);
+        assert_eq!(parse_range("bytes=-", 1000), None);
+        assert_eq!(parse_range("items=0-99", 1000), None);
+        assert_eq!(parse_range("bytes=0-", 0), None);
+    }
+
+    #[test]
+    fn test_conditional_requests() {
+        let etag = "\"67a1b2c3-1f4\"";
+        let modified = Some(UNIX_EPOCH + std::time::Duration::from_secs(784_111_777));
+        let headers = |name: header::HeaderName, value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(name, value.parse().unwrap());
+            headers
+        };
+        assert!(!is_not_modified(&HeaderMap::new(), etag, modified));
+        assert!(is_not_modified(
+            &headers(header::IF_NONE_MATCH, "\"other\", W/\"67a1b2c3-1f4\""),
+            etag,
+            modified
+        ));
+        assert!(is_not_modified(
+            &headers(header::IF_NONE_MATCH, "*"),
+            etag,
+            modified
+        ));
+        assert!(!is_not_modified(
+            &headers(header::IF_NONE_MATCH, "\"other\""),
+            etag,
+            modified
+        ));
+        assert!(is_not_modified(
+            &headers(header::IF_MODIFIED_SINCE, "Sun, 06 Nov 1994 08:49:37 GMT"),
+            etag,
+            modified
+        ));
+        assert!(!is_not_modified(
+            &headers(header::IF_MODIFIED_SINCE, "Sun, 06 Nov 1994 08:49:36 GMT"),
+            etag,
+            modified
+        ));
+        assert!(!is_not_modified(
+            &headers(header::IF_MODIFIED_SINCE, "yesterday"),
+            etag,
+            modified
+        ));
+        assert!(is_range_valid(&HeaderMap::new(), etag, modified));
+        assert!(is_range_valid(
+            &headers(header::IF_RANGE, etag),
+            etag,
+            modified
+        ));
+        assert!(!is_range_valid(
+            &headers(header::IF_RANGE, "W/\"67a1b2c3-1f4\""),
+            etag,
+            modified
+        ));
+        assert!(is_range_valid(
+            &headers(header::IF_RANGE, "Sun, 06 Nov 1994 08:49:37 GMT"),
+            etag,
+            modified
+        ));
+        assert!(!is_range_valid(
+            &headers(header::IF_RANGE, "Sun, 06 Nov 1994 08:49:38 GMT"),
+            etag,
+            None
+        ));
+    }
+
     #[tokio::test]
     async fn test_favicon() {
         let router = create_router().await;
@@ -1455,7 +3511,8 @@ mod tests {
 
         // root with auth
         let res = client.get("/").expect_failure().await;
-        assert_eq!(res.status_code(), StatusCode::PERMANENT_REDIRECT);
+        res.assert_status_see_other();
+        assert_eq!(res.header(header::LOCATION), "/home");
         let res = client.get("/library").await;
         assert_eq!(res.status_code(), StatusCode::OK);
         insta::assert_yaml_snapshot!(res.text());
@@ -1481,6 +3538,10 @@ mod tests {
             .get("/css/eloran.css")
             .await
             .assert_header("content-type", "text/css");
+        client
+            .get("/css/theme-sepia.css")
+            .await
+            .assert_header("content-type", "text/css");
 
         // delete database
         let _ = Sqlite::drop_database(crate::DB_URL).await;
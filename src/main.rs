@@ -1,10 +1,16 @@
 #![forbid(unsafe_code)]
+mod audio;
 mod conf;
+mod fb2;
 mod html_render;
 mod http_server;
+mod mobi;
 mod reader;
 mod scanner;
+mod search;
 mod sqlite;
+mod text;
+mod zip_stream;
 
 #[macro_use]
 extern crate log;
@@ -0,0 +1,552 @@
+//! MOBI and AZW3 (KF8) ebooks : a PalmDB database whose first record holds the MOBI header,
+//! followed by text records (html), then images records
+
+use crate::scanner::FileMetadata;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use std::fs;
+
+/// compression of text records
+const NO_COMPRESSION: u16 = 1;
+const PALMDOC_COMPRESSION: u16 = 2;
+const HUFF_CDIC_COMPRESSION: u16 = 17480;
+
+/// EXTH records types
+const EXTH_AUTHOR: u32 = 100;
+const EXTH_DESCRIPTION: u32 = 103;
+const EXTH_COVER_OFFSET: u32 = 201;
+const EXTH_UPDATED_TITLE: u32 = 503;
+
+/// memory reserved ahead for the text, its length comes from the header
+const TEXT_MAX_CAPACITY: usize = 64 * 1024 * 1024;
+
+/// base32 digits of `kindle:embed:XXXX` images references
+const KINDLE_BASE32: &str = "0123456789ABCDEFGHIJKLMNOPQRSTUV";
+
+/// a parsed MOBI file, text records are only uncompressed when needed
+pub struct Mobi {
+    data: Vec<u8>,
+    /// start and end of each record in data
+    records: Vec<(usize, usize)>,
+    name: String,
+    compression: u16,
+    text_length: usize,
+    text_record_count: usize,
+    encryption: u16,
+    utf8: bool,
+    version: u32,
+    first_image_index: Option<usize>,
+    extra_flags: u16,
+    fdst_index: Option<usize>,
+    full_name: Option<String>,
+    exth: Vec<(u32, Vec<u8>)>,
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+/// record index, `0xFFFFFFFF` means none
+fn read_index(data: &[u8], offset: usize) -> Option<usize> {
+    read_u32(data, offset)
+        .filter(|&index| index != u32::MAX)
+        .map(|index| index as usize)
+}
+
+impl Mobi {
+    pub fn open(path: &str) -> Result<Mobi, String> {
+        let data = fs::read(path).map_err(|e| format!("unable to read file : {e}"))?;
+        Mobi::parse(data)
+    }
+
+    pub fn parse(data: Vec<u8>) -> Result<Mobi, String> {
+        let invalid = |field: &str| format!("invalid mobi file ({field})");
+        // PalmDB header, then records list
+        let name = data.get(0..32).ok_or_else(|| invalid("palmdb header"))?;
+        let name = String::from_utf8_lossy(name.split(|&byte| byte == 0).next().unwrap_or(name))
+            .to_string();
+        let record_count = read_u16(&data, 76).ok_or_else(|| invalid("records count"))? as usize;
+        let offsets = (0..record_count)
+            .map(|index| read_u32(&data, 78 + index * 8).map(|offset| offset as usize))
+            .collect::<Option<Vec<usize>>>()
+            .ok_or_else(|| invalid("records list"))?;
+        let records: Vec<(usize, usize)> = offsets
+            .iter()
+            .enumerate()
+            .map(|(index, &start)| {
+                let end = offsets.get(index + 1).copied().unwrap_or(data.len());
+                (
+                    start.min(data.len()),
+                    end.clamp(start.min(data.len()), data.len()),
+                )
+            })
+            .collect();
+        let &(header_start, header_end) = records.first().ok_or_else(|| invalid("no record"))?;
+        let header = &data[header_start..header_end];
+        if header.get(16..20) != Some(b"MOBI") {
+            return Err(invalid("no MOBI header"));
+        }
+        let header_length = read_u32(header, 20).unwrap_or_default() as usize;
+        let extra_flags = if header_length >= 0xE4 {
+            read_u16(header, 0xF2).unwrap_or_default()
+        } else {
+            0
+        };
+        let version = read_u32(header, 0x24).unwrap_or_default();
+        let full_name = read_u32(header, 0x54)
+            .zip(read_u32(header, 0x58))
+            .and_then(|(offset, length)| {
+                header.get(offset as usize..offset as usize + length as usize)
+            })
+            .map(|full_name| String::from_utf8_lossy(full_name).to_string());
+        // EXTH records, after the MOBI header
+        let mut exth = Vec::new();
+        if read_u32(header, 0x80).is_some_and(|flags| flags & 0x40 != 0) {
+            let exth_start = 16 + header_length;
+            if header.get(exth_start..exth_start + 4) == Some(b"EXTH") {
+                let exth_count = read_u32(header, exth_start + 8).unwrap_or_default();
+                let mut offset = exth_start + 12;
+                for _ in 0..exth_count {
+                    let (Some(kind), Some(length)) =
+                        (read_u32(header, offset), read_u32(header, offset + 4))
+                    else {
+                        break;
+                    };
+                    let length = (length as usize).max(8);
+                    if let Some(value) = header.get(offset + 8..offset + length) {
+                        exth.push((kind, value.to_vec()));
+                    }
+                    offset += length;
+                }
+            }
+        }
+        Ok(Mobi {
+            name,
+            compression: read_u16(header, 0).unwrap_or_default(),
+            text_length: read_u32(header, 4).unwrap_or_default() as usize,
+            text_record_count: read_u16(header, 8).unwrap_or_default() as usize,
+            encryption: read_u16(header, 12).unwrap_or_default(),
+            utf8: read_u32(header, 0x1C) == Some(65001),
+            version,
+            first_image_index: read_index(header, 0x6C),
+            extra_flags,
+            fdst_index: if version >= 8 {
+                read_index(header, 0xC0)
+            } else {
+                None
+            },
+            full_name,
+            exth,
+            records,
+            data,
+        })
+    }
+
+    fn record(&self, index: usize) -> Option<&[u8]> {
+        let &(start, end) = self.records.get(index)?;
+        Some(&self.data[start..end])
+    }
+
+    fn exth_strings(&self, kind: u32) -> Vec<String> {
+        self.exth
+            .iter()
+            .filter(|(exth_kind, _)| *exth_kind == kind)
+            .map(|(_, value)| String::from_utf8_lossy(value).trim().to_string())
+            .filter(|value| !value.is_empty())
+            .collect()
+    }
+
+    fn exth_string(&self, kind: u32) -> Option<String> {
+        self.exth_strings(kind).into_iter().next()
+    }
+
+    /// title, authors and description, series are not stored in mobi files
+    pub fn metadata(&self) -> FileMetadata {
+        let authors = self.exth_strings(EXTH_AUTHOR);
+        FileMetadata {
+            title: self
+                .exth_string(EXTH_UPDATED_TITLE)
+                .or(self.full_name.clone())
+                .or(Some(self.name.replace('_', " "))),
+            author: (!authors.is_empty()).then(|| authors.join(", ")),
+            series: None,
+            volume: None,
+            description: self.exth_string(EXTH_DESCRIPTION),
+        }
+    }
+
+    /// image of the n-th images record
+    fn image(&self, image_index: usize) -> Option<&[u8]> {
+        self.record(self.first_image_index? + image_index)
+            .filter(|image| image::guess_format(image).is_ok())
+    }
+
+    /// cover image, or the first image of the book
+    pub fn cover(&self) -> Option<&[u8]> {
+        self.exth
+            .iter()
+            .find(|(kind, _)| *kind == EXTH_COVER_OFFSET)
+            .and_then(|(_, value)| Some(u32::from_be_bytes(value.get(0..4)?.try_into().ok()?)))
+            .and_then(|offset| self.image(offset as usize))
+            .or_else(|| self.image(0))
+    }
+
+    /// html of the book, from its uncompressed text records
+    pub fn text(&self) -> Result<String, String> {
+        if self.encryption != 0 {
+            return Err("mobi file is drm protected".to_string());
+        }
+        let mut text: Vec<u8> = Vec::with_capacity(self.text_length.min(TEXT_MAX_CAPACITY));
+        for index in 1..=self.text_record_count {
+            let record = self.record(index).ok_or("missing mobi text record")?;
+            let record = &record[..record.len() - trailing_entries_size(record, self.extra_flags)];
+            match self.compression {
+                NO_COMPRESSION => text.extend_from_slice(record),
+                PALMDOC_COMPRESSION => text.extend(palmdoc_decompress(record)),
+                HUFF_CDIC_COMPRESSION => {
+                    return Err("unsupported mobi compression (huff/cdic)".to_string())
+                }
+                compression => return Err(format!("unknown mobi compression {compression}")),
+            }
+        }
+        text.truncate(self.text_length);
+        // kf8 text is made of flows (html, css, svg...), html is the first one
+        if let Some(fdst) = self.fdst_index.and_then(|index| self.record(index)) {
+            if fdst.get(0..4) == Some(b"FDST") {
+                let table = read_u32(fdst, 4).unwrap_or(12) as usize;
+                if let Some(html_end) = read_u32(fdst, table + 4) {
+                    text.truncate(html_end as usize);
+                }
+            }
+        }
+        Ok(if self.utf8 {
+            String::from_utf8_lossy(&text).to_string()
+        } else {
+            encoding_rs::WINDOWS_1252.decode(&text).0.to_string()
+        })
+    }
+
+    /// html of each chapter : split at page breaks, or at each html file of kf8 books
+    pub fn chapters(&self) -> Result<Vec<String>, String> {
+        let text = self.text()?;
+        let separator = if self.version >= 8 {
+            "<html"
+        } else {
+            "<mbp:pagebreak"
+        };
+        let lowercase = text.to_ascii_lowercase();
+        let mut starts: Vec<usize> = lowercase
+            .match_indices(separator)
+            .map(|(start, _)| start)
+            .collect();
+        starts.insert(0, 0);
+        starts.push(text.len());
+        Ok(starts
+            .windows(2)
+            .map(|bounds| body_content(&text[bounds[0]..bounds[1]]))
+            .filter(|chapter| {
+                chapter.contains("<img")
+                    || strip_tags(chapter)
+                        .chars()
+                        .any(|character| !character.is_whitespace())
+            })
+            .collect())
+    }
+
+    /// chapter with its images embedded
+    pub fn chapter(&self, index: usize) -> Option<String> {
+        let chapter = self.chapters().ok()?.into_iter().nth(index)?;
+        Some(self.embed_images(&chapter))
+    }
+
+    /// replace images references (`recindex="00001"` or `kindle:embed:0001`) by data urls
+    fn embed_images(&self, html: &str) -> String {
+        let data_url = |image_index: usize| -> String {
+            match self.image(image_index) {
+                Some(image) => format!(
+                    "data:{};base64,{}",
+                    image::guess_format(image)
+                        .map(|format| format.to_mime_type())
+                        .unwrap_or("image/jpeg"),
+                    BASE64.encode(image)
+                ),
+                None => String::new(),
+            }
+        };
+        let mut embedded = String::with_capacity(html.len());
+        let mut rest = html;
+        loop {
+            let recindex = rest.find("recindex=\"");
+            let kindle_embed = rest.find("kindle:embed:");
+            match (recindex, kindle_embed) {
+                (Some(start), kindle_embed) if kindle_embed.is_none_or(|embed| start < embed) => {
+                    let number_start = start + "recindex=\"".len();
+                    let number_end = rest[number_start..]
+                        .find('"')
+                        .map_or(rest.len(), |end| number_start + end);
+                    let image_index = rest[number_start..number_end].parse::<usize>().ok();
+                    embedded.push_str(&rest[..start]);
+                    embedded.push_str(&format!(
+                        "src=\"{}\"",
+                        image_index
+                            .map_or(String::new(), |index| data_url(index.saturating_sub(1)))
+                    ));
+                    rest = &rest[(number_end + 1).min(rest.len())..];
+                }
+                (_, Some(start)) => {
+                    let number_start = start + "kindle:embed:".len();
+                    let number_end = rest[number_start..]
+                        .find(|character: char| !character.is_ascii_alphanumeric())
+                        .map_or(rest.len(), |end| number_start + end);
+                    let image_index = rest[number_start..number_end]
+                        .chars()
+                        .try_fold(0, |index, digit| {
+                            KINDLE_BASE32.find(digit).map(|digit| index * 32 + digit)
+                        });
+                    // `?mime=image/jpg` suffix
+                    let reference_end = rest[number_end..]
+                        .find(['"', '\''])
+                        .map_or(rest.len(), |end| number_end + end);
+                    embedded.push_str(&rest[..start]);
+                    embedded.push_str(
+                        &image_index
+                            .map_or(String::new(), |index| data_url(index.saturating_sub(1))),
+                    );
+                    rest = &rest[reference_end..];
+                }
+                _ => break,
+            }
+        }
+        embedded.push_str(rest);
+        embedded
+    }
+}
+
+/// size of the trailing entries of a text record, given by the extra flags of the header
+fn trailing_entries_size(record: &[u8], extra_flags: u16) -> usize {
+    let mut size = 0;
+    let mut flags = extra_flags >> 1;
+    while flags != 0 {
+        if flags & 1 == 1 {
+            // backward variable length integer, at the end of the record
+            let mut entry_size = 0;
+            let mut shift = 0;
+            for &byte in record[..record.len() - size].iter().rev().take(4) {
+                entry_size |= ((byte & 0x7F) as usize) << shift;
+                shift += 7;
+                if byte & 0x80 != 0 {
+                    break;
+                }
+            }
+            size = (size + entry_size).min(record.len());
+        }
+        flags >>= 1;
+    }
+    // multibyte characters overlapping the next record
+    if extra_flags & 1 == 1 {
+        if let Some(&byte) = record
+            .len()
+            .checked_sub(size + 1)
+            .and_then(|last| record.get(last))
+        {
+            size = (size + (byte & 0x3) as usize + 1).min(record.len());
+        }
+    }
+    size
+}
+
+/// uncompress a PalmDOC (LZ77) text record
+fn palmdoc_decompress(record: &[u8]) -> Vec<u8> {
+    let mut text: Vec<u8> = Vec::with_capacity(record.len() * 2);
+    let mut bytes = record.iter();
+    while let Some(&byte) = bytes.next() {
+        match byte {
+            // literal bytes
+            1..=8 => text.extend(bytes.by_ref().take(byte as usize)),
+            0 | 9..=0x7F => text.push(byte),
+            // distance and length of a previous sequence
+            0x80..=0xBF => {
+                let Some(&next) = bytes.next() else {
+                    break;
+                };
+                let pair = (((byte as usize) << 8) | next as usize) & 0x3FFF;
+                let distance = pair >> 3;
+                let length = (pair & 7) + 3;
+                if distance == 0 || distance > text.len() {
+                    continue;
+                }
+                for _ in 0..length {
+                    text.push(text[text.len() - distance]);
+                }
+            }
+            // space and character
+            0xC0..=0xFF => {
+                text.push(b' ');
+                text.push(byte ^ 0x80);
+            }
+        }
+    }
+    text
+}
+
+/// content of an html document, without its head and html/body tags, to be shown in a page
+fn body_content(html: &str) -> String {
+    // ascii lowercase keeps bytes offsets
+    let lowercase = html.to_ascii_lowercase();
+    let mut content = String::with_capacity(html.len());
+    let mut position = 0;
+    while let Some(start) = lowercase[position..]
+        .find('<')
+        .map(|start| position + start)
+    {
+        content.push_str(&html[position..start]);
+        let tag = &lowercase[start..];
+        let is_tag = |name: &str| {
+            tag.starts_with(name)
+                && tag[name.len()..].starts_with(|character: char| {
+                    character.is_whitespace() || ">/".contains(character)
+                })
+        };
+        let end = if is_tag("<head") {
+            tag.find("</head>").map(|end| end + "</head>".len())
+        } else if tag.starts_with("<?xml")
+            || tag.starts_with("<!doctype")
+            || ["<html", "</html", "<body", "</body", "<mbp:pagebreak"]
+                .iter()
+                .any(|name| is_tag(name))
+        {
+            tag.find('>').map(|end| end + 1)
+        } else {
+            content.push('<');
+            position = start + 1;
+            continue;
+        };
+        position = end.map_or(html.len(), |end| start + end);
+    }
+    content.push_str(&html[position..]);
+    content.trim().to_string()
+}
+
+/// text of some html, without its tags
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for character in html.chars() {
+        match character {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => text.push(character),
+            _ => (),
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// minimal mobi file : a header with EXTH, uncompressed text records and an image record
+    fn mobi_file(text_records: &[&[u8]], image: &[u8]) -> Vec<u8> {
+        let mut exth = b"EXTH".to_vec();
+        let exth_records: [(u32, &[u8]); 3] = [
+            (EXTH_AUTHOR, b"Jules Verne"),
+            (EXTH_UPDATED_TITLE, b"Vingt mille lieues sous les mers"),
+            (EXTH_COVER_OFFSET, &[0, 0, 0, 0]),
+        ];
+        let exth_length: usize = exth_records.iter().map(|(_, value)| 8 + value.len()).sum();
+        exth.extend((12 + exth_length as u32).to_be_bytes());
+        exth.extend((exth_records.len() as u32).to_be_bytes());
+        for (kind, value) in exth_records {
+            exth.extend(kind.to_be_bytes());
+            exth.extend((8 + value.len() as u32).to_be_bytes());
+            exth.extend(value);
+        }
+        let mut header = vec![0u8; 16 + 0xE8];
+        header[0..2].copy_from_slice(&NO_COMPRESSION.to_be_bytes());
+        let text_length: usize = text_records.iter().map(|record| record.len()).sum();
+        header[4..8].copy_from_slice(&(text_length as u32).to_be_bytes());
+        header[8..10].copy_from_slice(&(text_records.len() as u16).to_be_bytes());
+        header[16..20].copy_from_slice(b"MOBI");
+        header[20..24].copy_from_slice(&0xE8u32.to_be_bytes());
+        header[0x1C..0x20].copy_from_slice(&65001u32.to_be_bytes());
+        header[0x24..0x28].copy_from_slice(&6u32.to_be_bytes());
+        header[0x54..0x58].copy_from_slice(&u32::MAX.to_be_bytes());
+        header[0x6C..0x70].copy_from_slice(&(1 + text_records.len() as u32).to_be_bytes());
+        header[0x80..0x84].copy_from_slice(&0x40u32.to_be_bytes());
+        header.extend(exth);
+        let mut records: Vec<&[u8]> = vec![&header];
+        records.extend(text_records);
+        records.push(image);
+        let mut data = b"Vingt_mille_lieues".to_vec();
+        data.resize(76, 0);
+        data.extend((records.len() as u16).to_be_bytes());
+        let mut offset = 78 + records.len() * 8;
+        for record in &records {
+            data.extend((offset as u32).to_be_bytes());
+            data.extend([0; 4]);
+            offset += record.len();
+        }
+        for record in records {
+            data.extend(record);
+        }
+        data
+    }
+
+    #[test]
+    fn test_mobi() {
+        let image = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+        let mobi = Mobi::parse(mobi_file(
+            &[
+                b"<html><head><title>t</title></head><body><p>Chapitre 1</p><mbp:pagebreak/>",
+                b"<p>Chapitre 2</p><img recindex=\"00001\"/><mbp:pagebreak/></body></html>",
+            ],
+            image,
+        ))
+        .unwrap();
+        let metadata = mobi.metadata();
+        assert_eq!(
+            metadata.title.as_deref(),
+            Some("Vingt mille lieues sous les mers")
+        );
+        assert_eq!(metadata.author.as_deref(), Some("Jules Verne"));
+        assert_eq!(mobi.cover(), Some(&image[..]));
+        assert_eq!(
+            mobi.chapters().unwrap(),
+            vec![
+                "<p>Chapitre 1</p>",
+                "<p>Chapitre 2</p><img recindex=\"00001\"/>"
+            ]
+        );
+        assert_eq!(
+            mobi.chapter(1).unwrap(),
+            format!(
+                "<p>Chapitre 2</p><img src=\"data:image/png;base64,{}\"/>",
+                BASE64.encode(image)
+            )
+        );
+        assert!(Mobi::parse(b"not a mobi file".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_palmdoc_decompress() {
+        // literal, `bla` repeated from 3 bytes back, space + `a`
+        assert_eq!(palmdoc_decompress(b"bla\x80\x1E\xE1"), b"blablablabla a");
+        assert_eq!(palmdoc_decompress(b"\x02\xC3\xA9!"), "é!".as_bytes());
+    }
+
+    #[test]
+    fn test_trailing_entries_size() {
+        // one trailing entry of 3 bytes (size included), then multibyte overlap of 1 byte
+        assert_eq!(trailing_entries_size(b"text\xAA\xBB\x83", 0b10), 3);
+        assert_eq!(trailing_entries_size(b"tex\xC3\x01\x81", 0b11), 3);
+    }
+}
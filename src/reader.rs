@@ -1,71 +1,276 @@
-use crate::scanner::{self, FileInfo};
+use crate::audio;
+use crate::fb2::Fb2;
+use crate::mobi::Mobi;
+use crate::scanner::{self, FileInfo, Format};
+use crate::text::{self, TextPagination};
 
-use compress_tools::*;
 use epub::doc::EpubDoc;
 use image::imageops::FilterType;
-use std::fs::File;
-use std::io::Cursor;
-
-// pub fn raw(_user: User, _file: FileInfo) -> String {
-//     todo!();
-// }
-// pub fn txt(_user: User, _file: FileInfo) -> String {
-//     todo!();
-// }
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufReader, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Instant, SystemTime};
 
 pub async fn get_comic_page(file: &FileInfo, page: i32, size: &str) -> Option<Vec<u8>> {
     info!(
         "reading comic {}/{} (page {page})",
         file.parent_path, file.name
     );
+    let vec_comic_page = match file.format {
+        Format::Folder => get_folder_image(file, page)?,
+        // a loose image has a single page
+        Format::Jpg => fs::read(format!("{}/{}", file.parent_path, file.name)).ok()?,
+        _ => get_archive_image(file, page)?,
+    };
+    // return img in jpg
+    let dyn_image_comic_page = image::load_from_memory(&vec_comic_page).ok()?;
+    // resize smaller if needed
+    let dyn_image_comic_page = match size {
+        // TODO true ratio not needed, but check size (600 px too much ?)
+        // let w = dyn_image_comic_page.width();
+        // let h = dyn_image_comic_page.height();
+        "800px" => dyn_image_comic_page.resize(800, 2000, FilterType::Triangle),
+        "1000px" => dyn_image_comic_page.resize(1000, 2500, FilterType::Triangle),
+        _ => dyn_image_comic_page,
+    };
+    // encode to jpeg
+    // TODO do not encode if already jpeg ?
+    let mut bytes_comic_page: Vec<u8> = Vec::new();
+    let mut writer = Cursor::new(&mut bytes_comic_page);
+    let jpeg_encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut writer, 75);
+    dyn_image_comic_page.write_with_encoder(jpeg_encoder).ok()?;
+    Some(bytes_comic_page)
+}
+
+/// image of a page, uncompressed from a comic archive
+fn get_archive_image(file: &FileInfo, page: i32) -> Option<Vec<u8>> {
+    if let Some(image) = get_cbz_image(file, page) {
+        return Some(image);
+    }
     let archive_path = &format!("{}/{}", file.parent_path, file.name);
-    match File::open(archive_path) {
-        Ok(compressed_comic_file) => {
-            // get images list from archive
-            let comic_file_list = scanner::extract_comic_image_list(&compressed_comic_file);
-            // set path file wanted from page index
-            if !comic_file_list.is_empty() {
-                let image_path_in_achive = comic_file_list.get(page as usize)?;
-                // uncompress corresponding image
-                let mut vec_comic_page: Vec<u8> = Vec::default();
-
-                // RAR need to reopen file... why ? and why rar only ?
-                let compressed_comic_file = File::open(archive_path).expect("file open");
-                match uncompress_archive_file(
-                    &compressed_comic_file,
-                    &mut vec_comic_page,
-                    image_path_in_achive,
-                ) {
-                    Ok(_) => (),
-                    Err(e) => warn!(
-                        "unable to extract path '{}' from file '{}' : {e}",
-                        image_path_in_achive, file.name
-                    ),
+    // get images list from archive
+    let (image_list, encoding) = match scanner::extract_comic_image_list(archive_path) {
+        Ok(list) => list,
+        Err(e) => {
+            warn!("unable to extract file list from archive {archive_path} : {e}");
+            return None;
+        }
+    };
+    // set path file wanted from page index
+    let image_path_in_achive = image_list.get(page as usize)?;
+    // uncompress corresponding image
+    scanner::uncompress_archive_entry(archive_path, image_path_in_achive, encoding)
+}
+
+/// images of a cbz, as indexes in its zip central directory, in reading order
+struct CbzImages {
+    size: u64,
+    modified: Option<SystemTime>,
+    last_used: Instant,
+    indexes: Arc<Vec<usize>>,
+}
+
+/// images lists of recently opened cbz, so reading a page only uncompresses its entry
+static CBZ_IMAGES: LazyLock<Mutex<HashMap<String, CbzImages>>> = LazyLock::new(Default::default);
+const CBZ_IMAGES_CACHE_SIZE: usize = 64;
+
+type CbzArchive = zip::ZipArchive<BufReader<File>>;
+
+/// images of a zip archive, in reading order
+fn list_cbz_images(archive: &mut CbzArchive) -> Vec<usize> {
+    let mut images: Vec<(usize, String)> = Vec::with_capacity(archive.len());
+    for index in 0..archive.len() {
+        let Ok(mut entry) = archive.by_index(index) else {
+            continue;
+        };
+        let name = entry.name().to_string();
+        let is_image = match scanner::is_image_entry(&name) {
+            Some(is_image) => is_image,
+            // no extension, only read its first bytes
+            None => {
+                let mut magic_bytes = Vec::with_capacity(32);
+                let _ = entry.by_ref().take(32).read_to_end(&mut magic_bytes);
+                scanner::is_image_data(&magic_bytes)
+            }
+        };
+        if is_image {
+            images.push((index, name));
+        }
+    }
+    images.sort_by(|(_, a), (_, b)| scanner::natural_cmp(a, b));
+    images.into_iter().map(|(index, _)| index).collect()
+}
+
+/// opened zip archive of a cbz, with its images from cache when the file did not change
+/// none when the file is not a cbz or not a zip (some are renamed rar)
+fn open_cbz(file: &FileInfo) -> Option<(CbzArchive, Arc<Vec<usize>>)> {
+    if file.format != Format::Cbz {
+        return None;
+    }
+    let archive_path = format!("{}/{}", file.parent_path, file.name);
+    let archive_file = File::open(&archive_path).ok()?;
+    let metadata = archive_file.metadata().ok()?;
+    let (size, modified) = (metadata.len(), metadata.modified().ok());
+    let mut archive = match zip::ZipArchive::new(BufReader::new(archive_file)) {
+        Ok(archive) => archive,
+        Err(e) => {
+            debug!("unable to read {archive_path} as a zip archive : {e}");
+            return None;
+        }
+    };
+    let cached_indexes = CBZ_IMAGES.lock().ok().and_then(|mut cache| {
+        let cbz_images = cache.get_mut(&archive_path)?;
+        if cbz_images.size != size || cbz_images.modified != modified {
+            return None;
+        }
+        cbz_images.last_used = Instant::now();
+        Some(cbz_images.indexes.clone())
+    });
+    let indexes = match cached_indexes {
+        Some(indexes) => indexes,
+        None => {
+            let indexes = Arc::new(list_cbz_images(&mut archive));
+            if let Ok(mut cache) = CBZ_IMAGES.lock() {
+                if cache.len() >= CBZ_IMAGES_CACHE_SIZE && !cache.contains_key(&archive_path) {
+                    let oldest = cache
+                        .iter()
+                        .min_by_key(|(_, cbz_images)| cbz_images.last_used)
+                        .map(|(path, _)| path.clone());
+                    if let Some(oldest) = oldest {
+                        cache.remove(&oldest);
+                    }
                 }
-                // return img in jpg
-                let dyn_image_comic_page = image::load_from_memory(&vec_comic_page).ok()?;
-                // resize smaller if needed
-                let dyn_image_comic_page = match size {
-                    // TODO true ratio not needed, but check size (600 px too much ?)
-                    // let w = dyn_image_comic_page.width();
-                    // let h = dyn_image_comic_page.height();
-                    "800px" => dyn_image_comic_page.resize(800, 2000, FilterType::Triangle),
-                    "1000px" => dyn_image_comic_page.resize(1000, 2500, FilterType::Triangle),
-                    _ => dyn_image_comic_page,
-                };
-                // encode to jpeg
-                // TODO do not encode if already jpeg ?
-                let mut bytes_comic_page: Vec<u8> = Vec::new();
-                let mut writer = Cursor::new(&mut bytes_comic_page);
-                let jpeg_encoder =
-                    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut writer, 75);
-                dyn_image_comic_page.write_with_encoder(jpeg_encoder).ok()?;
-                Some(bytes_comic_page)
-            } else {
-                None
+                cache.insert(
+                    archive_path,
+                    CbzImages {
+                        size,
+                        modified,
+                        last_used: Instant::now(),
+                        indexes: indexes.clone(),
+                    },
+                );
             }
+            indexes
+        }
+    };
+    Some((archive, indexes))
+}
+
+/// number of images of a cbz, none when it can't be read as a zip archive
+pub fn cbz_image_count(file: &FileInfo) -> Option<usize> {
+    open_cbz(file).map(|(_, indexes)| indexes.len())
+}
+
+/// memory reserved for a cbz image before reading it, larger ones grow while read
+const CBZ_IMAGE_MAX_CAPACITY: u64 = 64 * 1024 * 1024;
+
+/// image of a page of a cbz, seeking directly to its entry
+pub fn get_cbz_image(file: &FileInfo, page: i32) -> Option<Vec<u8>> {
+    let (mut archive, indexes) = open_cbz(file)?;
+    let index = *indexes.get(usize::try_from(page).ok()?)?;
+    let mut entry = archive.by_index(index).ok()?;
+    // the size is declared by the archive, it may be wrong
+    let capacity = entry.size().min(CBZ_IMAGE_MAX_CAPACITY);
+    let mut image = Vec::with_capacity(capacity as usize);
+    match entry.read_to_end(&mut image) {
+        Ok(_) => Some(image),
+        Err(e) => {
+            warn!(
+                "unable to extract page {page} from file '{}' : {e}",
+                file.name
+            );
+            None
+        }
+    }
+}
+
+/// image of a page, read from an images folder
+fn get_folder_image(file: &FileInfo, page: i32) -> Option<Vec<u8>> {
+    let directory_path = format!("{}/{}", file.parent_path, file.name);
+    let directory_path = Path::new(&directory_path);
+    let image_list = scanner::folder_image_list(directory_path);
+    let image_name = image_list.get(page as usize)?;
+    match fs::read(directory_path.join(image_name)) {
+        Ok(image) => Some(image),
+        Err(e) => {
+            warn!(
+                "unable to read image '{image_name}' from folder '{}' : {e}",
+                file.name
+            );
+            None
+        }
+    }
+}
+
+/// images of a folder (a cbz) or tracks of an audiobook, with their path, to be zipped without
+/// compression (images and audio already are, see `zip_stream`)
+pub fn folder_zip_entries(file: &FileInfo) -> Vec<(String, PathBuf)> {
+    let directory_path = format!("{}/{}", file.parent_path, file.name);
+    let directory_path = Path::new(&directory_path);
+    let file_list = match file.format {
+        Format::Audiobook => audio::folder_audio_list(directory_path),
+        _ => scanner::folder_image_list(directory_path),
+    };
+    file_list
+        .into_iter()
+        .map(|file_name| {
+            let path = directory_path.join(&file_name);
+            (file_name, path)
+        })
+        .collect()
+}
+
+/// chapter of a mobi or azw3 file, as html
+pub fn mobi(file: &FileInfo, page: i32) -> String {
+    let full_path = format!("{}/{}", file.parent_path, file.name);
+    let chapter = Mobi::open(&full_path)
+        .ok()
+        .and_then(|mobi| mobi.chapter(usize::try_from(page).ok()?));
+    match chapter {
+        Some(chapter) => chapter,
+        None => {
+            warn!("unable to read chapter {page} of file {full_path}");
+            "<p>unable to read this chapter</p>".to_string()
+        }
+    }
+}
+
+/// section of a fb2 file, as html
+pub fn fb2(file: &FileInfo, page: i32) -> String {
+    let full_path = format!("{}/{}", file.parent_path, file.name);
+    let chapter = Fb2::open(&full_path)
+        .ok()
+        .and_then(|fb2| fb2.chapter(usize::try_from(page).ok()?));
+    match chapter {
+        Some(chapter) => chapter,
+        None => {
+            warn!("unable to read section {page} of file {full_path}");
+            "<p>unable to read this section</p>".to_string()
+        }
+    }
+}
+
+/// page of a text or markdown file, as html
+pub fn text(file: &FileInfo, page: i32, pagination: &TextPagination) -> String {
+    let full_path = format!("{}/{}", file.parent_path, file.name);
+    let content = match text::read_text(&full_path) {
+        Ok(content) => content,
+        Err(e) => {
+            warn!("unable to read file {full_path} : {e}");
+            return "<p>unable to read this page</p>".to_string();
+        }
+    };
+    let markdown = file.format == Format::Md;
+    let pages = text::paginate(&content, pagination, markdown);
+    match usize::try_from(page).ok().and_then(|page| pages.get(page)) {
+        Some(page) if markdown => text::markdown_to_html(page),
+        Some(page) => text::text_to_html(page),
+        None => {
+            warn!("unable to read page {page} of file {full_path}");
+            "<p>unable to read this page</p>".to_string()
         }
-        Err(_) => None,
     }
 }
 
@@ -126,20 +331,75 @@ pub async fn epub(file: &FileInfo, page: i32) -> String {
 }
 
 // // TODO create a non empty file for testing
-// #[cfg(test)]
-// mod tests {
-//     use super::*;
-//     #[tokio::test]
-//     async fn test_get_comic_page() {
-//         let file = FileInfo::default();
-//         let page: i32 = 10;
-//         let size = "123456";
-//         insta::assert_yaml_snapshot!(get_comic_page(&file, page, size).await)
-//     }
-//     #[tokio::test]
-//     async fn test_epub() {
-//         let file = FileInfo::default();
-//         let page: i32 = 10;
-//         insta::assert_yaml_snapshot!(epub(&file, page).await)
-//     }
-// }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// small png, its width tells which page it is
+    fn png(width: u32) -> Vec<u8> {
+        let mut png = Vec::new();
+        image::DynamicImage::new_rgb8(width, 1)
+            .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        png
+    }
+
+    fn write_cbz(path: &Path, entries: &[(&str, Vec<u8>)]) {
+        let mut cbz = zip::ZipWriter::new(File::create(path).unwrap());
+        for (name, data) in entries {
+            cbz.start_file(*name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            cbz.write_all(data).unwrap();
+        }
+        cbz.finish().unwrap();
+    }
+
+    #[test]
+    fn test_get_cbz_image() {
+        let library_path = Path::new("library_cbz");
+        fs::create_dir_all(library_path).unwrap();
+        write_cbz(
+            &library_path.join("comic.cbz"),
+            &[
+                ("p10.png", png(10)),
+                ("__MACOSX/._p1.png", b"junk".to_vec()),
+                ("p2", png(2)),
+                ("ComicInfo.xml", b"<ComicInfo/>".to_vec()),
+                ("p1.png", png(1)),
+            ],
+        );
+        let mut file = FileInfo::new();
+        file.name = "comic.cbz".to_string();
+        file.parent_path = library_path.to_string_lossy().to_string();
+        file.format = Format::Cbz;
+        assert_eq!(cbz_image_count(&file), Some(3));
+        let width = |page| {
+            image::load_from_memory(&get_cbz_image(&file, page).unwrap())
+                .unwrap()
+                .width()
+        };
+        assert_eq!((width(0), width(1), width(2)), (1, 2, 10));
+        assert_eq!(get_cbz_image(&file, 3), None);
+        // the cached images list is dropped when the file changes
+        write_cbz(&library_path.join("comic.cbz"), &[("p5.png", png(5))]);
+        assert_eq!(cbz_image_count(&file), Some(1));
+        assert_eq!(width(0), 5);
+        // not a zip archive
+        fs::write(library_path.join("comic.cbz"), b"Rar!\x1a\x07\x00").unwrap();
+        assert_eq!(cbz_image_count(&file), None);
+        fs::remove_dir_all(library_path).unwrap();
+    }
+    //     #[tokio::test]
+    //     async fn test_get_comic_page() {
+    //         let file = FileInfo::default();
+    //         let page: i32 = 10;
+    //         let size = "123456";
+    //         insta::assert_yaml_snapshot!(get_comic_page(&file, page, size).await)
+    //     }
+    //     #[tokio::test]
+    //     async fn test_epub() {
+    //         let file = FileInfo::default();
+    //         let page: i32 = 10;
+    //         insta::assert_yaml_snapshot!(epub(&file, page).await)
+    //     }
+}
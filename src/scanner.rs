@@ -1,10 +1,15 @@
-use crate::sqlite;
+use crate::fb2::Fb2;
+use crate::mobi::Mobi;
+use crate::{audio, reader, sqlite, text};
 
 use cairo::Context;
-use compress_tools::*;
+use compress_tools::{
+    list_archive_files, list_archive_files_with_encoding, uncompress_archive_file,
+    uncompress_archive_file_with_encoding, DecodeCallback,
+};
 use epub::doc::EpubDoc;
 use image::imageops::FilterType;
-use image::DynamicImage;
+use image::{DynamicImage, ImageFormat};
 use jwalk::WalkDirGeneric;
 use poppler::Document;
 use serde::Serialize;
@@ -13,11 +18,12 @@ use sqlx::Sqlite;
 use std::cmp::Ordering;
 use std::fmt;
 use std::fs::{self, File};
-use std::io::Cursor;
+use std::io::{BufReader, Cursor};
 use std::os::linux::fs::MetadataExt;
 use std::path::Path;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::runtime::Runtime;
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
 
 /// Library Struct
 #[derive(Debug, Default, Clone, sqlx::FromRow, PartialEq, Eq)]
@@ -28,6 +34,8 @@ pub struct Library {
     pub last_successfull_scan_date: i64,
     pub last_successfull_extract_date: i64,
     pub file_count: i32,
+    #[sqlx(try_from = "String")]
+    pub hidden_formats: HiddenFormats,
 }
 impl Library {
     pub fn new() -> Library {
@@ -38,6 +46,7 @@ impl Library {
             last_successfull_scan_date: 0,
             last_successfull_extract_date: 0,
             file_count: 0,
+            hidden_formats: HiddenFormats::default(),
         }
     }
 }
@@ -62,9 +71,6 @@ pub struct FileInfo {
     // i64 because no u64 with sqlite...
     pub size: i64,
     pub total_pages: i32,
-    // list of users id separated by comma : `id1,id2,...`
-    pub read_by: String,
-    pub bookmarked_by: String,
 }
 impl FileInfo {
     pub fn new() -> FileInfo {
@@ -80,11 +86,10 @@ impl FileInfo {
             // format: Format::Other,
             size: 0,
             total_pages: 0,
-            read_by: "".to_string(),
-            bookmarked_by: "".to_string(),
         }
     }
 }
+
 impl PartialOrd for FileInfo {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -92,8 +97,76 @@ impl PartialOrd for FileInfo {
 }
 impl Ord for FileInfo {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.name.cmp(&other.name)
+        natural_cmp(&self.name, &other.name)
+    }
+}
+
+/// a file whose extraction failed, for the admin problem files page
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ProblemFile {
+    #[sqlx(flatten)]
+    pub file: FileInfo,
+    pub problem: String,
+    pub date: i64,
+}
+
+/// lowercase and without accents : `Élan` becomes `elan`
+fn fold_case_and_accents(text: &str) -> Vec<char> {
+    text.nfkd()
+        .filter(|character| !is_combining_mark(*character))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// uppercase initial of a name, without accent, `#` when it is not a letter
+pub fn initial(name: &str) -> char {
+    match fold_case_and_accents(name).first() {
+        Some(character) if character.is_ascii_alphabetic() => character.to_ascii_uppercase(),
+        _ => '#',
+    }
+}
+
+/// natural order, case and accents insensitive : `issue 2` < `Issue 10` < `élan` < `Zorro`
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (a_folded, b_folded) = (fold_case_and_accents(a), fold_case_and_accents(b));
+    let (mut a_index, mut b_index) = (0, 0);
+    while a_index < a_folded.len() && b_index < b_folded.len() {
+        let (a_char, b_char) = (a_folded[a_index], b_folded[b_index]);
+        if a_char.is_ascii_digit() && b_char.is_ascii_digit() {
+            // compare numbers, whatever their leading zeros
+            let a_end = a_folded[a_index..]
+                .iter()
+                .position(|character| !character.is_ascii_digit())
+                .map_or(a_folded.len(), |position| a_index + position);
+            let b_end = b_folded[b_index..]
+                .iter()
+                .position(|character| !character.is_ascii_digit())
+                .map_or(b_folded.len(), |position| b_index + position);
+            let a_number: String = a_folded[a_index..a_end].iter().collect();
+            let b_number: String = b_folded[b_index..b_end].iter().collect();
+            let (a_number, b_number) = (
+                a_number.trim_start_matches('0'),
+                b_number.trim_start_matches('0'),
+            );
+            let ordering = a_number
+                .len()
+                .cmp(&b_number.len())
+                .then_with(|| a_number.cmp(b_number));
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+            (a_index, b_index) = (a_end, b_end);
+        } else {
+            if a_char != b_char {
+                return a_char.cmp(&b_char);
+            }
+            (a_index, b_index) = (a_index + 1, b_index + 1);
+        }
     }
+    // shorter first, then stable order for equivalent names
+    (a_folded.len() - a_index)
+        .cmp(&(b_folded.len() - b_index))
+        .then_with(|| a.cmp(b))
 }
 
 // sqlx::FromRow not compatible with enums, need an alternative
@@ -105,41 +178,169 @@ pub enum Format {
     Pdf,
     Cbr,
     Cbz,
+    Cb7,
+    Cbt,
+    // directory of images
+    Folder,
+    Mobi,
+    Azw3,
+    Fb2,
     Txt,
+    // markdown
+    Md,
     Jpg,
+    Mp3,
+    M4b,
+    // directory of audio tracks
+    Audiobook,
     #[default]
     Other,
 }
 impl Format {
-    pub fn as_str(&self) -> &str {
+    pub const ALL: [Format; 17] = [
+        Format::Epub,
+        Format::Pdf,
+        Format::Cbr,
+        Format::Cbz,
+        Format::Cb7,
+        Format::Cbt,
+        Format::Folder,
+        Format::Mobi,
+        Format::Azw3,
+        Format::Fb2,
+        Format::Txt,
+        Format::Md,
+        Format::Jpg,
+        Format::Mp3,
+        Format::M4b,
+        Format::Audiobook,
+        Format::Other,
+    ];
+    /// formats the reader can open
+    pub const READABLE: [Format; 16] = [
+        Format::Epub,
+        Format::Pdf,
+        Format::Cbr,
+        Format::Cbz,
+        Format::Cb7,
+        Format::Cbt,
+        Format::Folder,
+        Format::Mobi,
+        Format::Azw3,
+        Format::Fb2,
+        Format::Txt,
+        Format::Md,
+        Format::Jpg,
+        Format::Mp3,
+        Format::M4b,
+        Format::Audiobook,
+    ];
+    pub fn is_readable(&self) -> bool {
+        Format::READABLE.contains(self)
+    }
+    /// audiobooks, their pages are seconds
+    pub fn is_audio(&self) -> bool {
+        matches!(self, Format::Mp3 | Format::M4b | Format::Audiobook)
+    }
+    /// name stored in database, other names derive from it
+    pub fn key(&self) -> &str {
         match &self {
             Format::Epub => "epub",
             Format::Pdf => "pdf",
             Format::Cbr => "cbr",
             Format::Cbz => "cbz",
+            Format::Cb7 => "cb7",
+            Format::Cbt => "cbt",
+            Format::Folder => "folder",
+            Format::Mobi => "mobi",
+            Format::Azw3 => "azw3",
+            Format::Fb2 => "fb2",
             Format::Txt => "txt",
+            Format::Md => "md",
             Format::Jpg => "jpg",
+            Format::Mp3 => "mp3",
+            Format::M4b => "m4b",
+            Format::Audiobook => "audiobook",
+            Format::Other => "other",
+        }
+    }
+    /// name printed in listings, unsupported formats are told so
+    pub fn as_str(&self) -> &str {
+        match self {
             Format::Other => "Not supported",
+            _ => self.key(),
         }
     }
 }
+/// Formats hidden from listings, stored as a comma separated list of keys
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct HiddenFormats(pub Vec<Format>);
+impl HiddenFormats {
+    pub fn contains(&self, format: &Format) -> bool {
+        self.0.contains(format)
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    /// add formats, without duplicates
+    pub fn extend(&mut self, formats: &[Format]) {
+        for format in formats {
+            if !self.contains(format) {
+                self.0.push(format.clone());
+            }
+        }
+    }
+}
+impl From<String> for HiddenFormats {
+    fn from(keys: String) -> Self {
+        // unknown keys are ignored
+        HiddenFormats(
+            Format::ALL
+                .into_iter()
+                .filter(|format| keys.split(',').any(|key| key == format.key()))
+                .collect(),
+        )
+    }
+}
+impl fmt::Display for HiddenFormats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let keys: Vec<&str> = self.0.iter().map(|format| format.key()).collect();
+        write!(f, "{}", keys.join(","))
+    }
+}
+
 impl fmt::Display for Format {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Format::Epub => write!(f, "epub"),
-            Format::Cbr => write!(f, "cbr"),
-            Format::Cbz => write!(f, "cbz"),
-            Format::Pdf => write!(f, "pdf"),
-            Format::Txt => write!(f, "txt"),
-            Format::Jpg => write!(f, "jpg"),
             Format::Other => write!(f, "unknow"),
+            _ => write!(f, "{}", self.key()),
         }
     }
 }
 
+/// metadata found inside a file, indexed for search
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FileMetadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub series: Option<String>,
+    // position in the series
+    pub volume: Option<f64>,
+    pub description: Option<String>,
+}
+
+/// Series struct, with files and read files counts of a user
+#[derive(Debug, Default, Clone, sqlx::FromRow, PartialEq, Eq, Serialize)]
+pub struct Series {
+    pub id: i64,
+    pub name: String,
+    pub file_count: i32,
+    pub read_count: i32,
+}
+
 /// Directory struct, match database fields
 /// id|name|parent_path
-#[derive(Debug, Default, Clone, sqlx::FromRow, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, sqlx::FromRow, PartialEq, Eq, Serialize)]
 pub struct DirectoryInfo {
     // TODO need library id for easy deleting
     pub id: String,
@@ -154,7 +355,7 @@ impl PartialOrd for DirectoryInfo {
 }
 impl Ord for DirectoryInfo {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.name.cmp(&other.name)
+        natural_cmp(&self.name, &other.name)
     }
 }
 
@@ -191,8 +392,20 @@ fn extract_file_infos(library_name: &str, entry: &Path) -> FileInfo {
         "epub" => Format::Epub,
         "cbr" => Format::Cbr,
         "cbz" => Format::Cbz,
+        "cb7" => Format::Cb7,
+        "cbt" => Format::Cbt,
+        "mobi" | "azw" => Format::Mobi,
+        "azw3" => Format::Azw3,
+        "fb2" => Format::Fb2,
+        // zipped fictionbook
+        "zip" if filename.to_lowercase().ends_with(".fb2.zip") => Format::Fb2,
         "pdf" => Format::Pdf,
         "txt" => Format::Txt,
+        "md" | "markdown" => Format::Md,
+        "mp3" => Format::Mp3,
+        "m4b" | "m4a" => Format::M4b,
+        // loose images, outside images folders
+        _ if is_image(&filename) => Format::Jpg,
         _ => Format::Other,
     };
 
@@ -208,8 +421,121 @@ fn extract_file_infos(library_name: &str, entry: &Path) -> FileInfo {
         format,
         size: size.unwrap_or(0) as i64,
         total_pages: 0,
-        read_by: "".to_string(),
-        bookmarked_by: "".to_string(),
+    }
+}
+
+/// image formats the `image` crate can decode
+fn is_decodable(format: ImageFormat) -> bool {
+    // avif decoding needs the `avif-native` feature (dav1d), only its encoder is built
+    format.reading_enabled() && format != ImageFormat::Avif
+}
+
+/// hidden files and metadata of archives or file systems (`__MACOSX/._001.jpg`, `Thumbs.db`...)
+fn is_hidden_entry(path: &str) -> bool {
+    path.split(['/', '\\']).any(|component| {
+        // tar entries may begin with `./`
+        (component.starts_with('.') && component != ".")
+            || component == "__MACOSX"
+            || component.eq_ignore_ascii_case("thumbs.db")
+    })
+}
+
+/// is an archive entry an image, from its extension
+/// none when its name has no extension, then only its magic bytes can tell
+pub fn is_image_entry(path: &str) -> Option<bool> {
+    if path.ends_with('/') || is_hidden_entry(path) {
+        return Some(false);
+    }
+    let file_name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    file_name
+        .rsplit_once('.')
+        .map(|(_, extension)| ImageFormat::from_extension(extension).is_some_and(is_decodable))
+}
+
+/// is some data an image, from its magic bytes
+pub fn is_image_data(data: &[u8]) -> bool {
+    image::guess_format(data).is_ok_and(is_decodable)
+}
+
+/// avif images, pages that can not be decoded (see `is_decodable`)
+fn is_avif_entry(path: &str) -> bool {
+    !path.ends_with('/')
+        && !is_hidden_entry(path)
+        && ImageFormat::from_path(path).is_ok_and(|format| format == ImageFormat::Avif)
+}
+
+/// number of avif images of a comic archive, skipped from its pages
+fn count_avif_entries(file: &FileInfo) -> usize {
+    let archive_path = format!("{}/{}", file.parent_path, file.name);
+    // cbz are read without libarchive
+    let zip_names = File::open(&archive_path)
+        .ok()
+        .and_then(|archive| zip::ZipArchive::new(BufReader::new(archive)).ok())
+        .map(|archive| {
+            archive
+                .file_names()
+                .filter(|name| is_avif_entry(name))
+                .count()
+        });
+    match zip_names {
+        Some(avif_count) => avif_count,
+        None => list_archive_entries(&archive_path)
+            .map(|(entries, _)| entries.iter().filter(|name| is_avif_entry(name)).count())
+            .unwrap_or_default(),
+    }
+}
+
+/// images a comic reader can display, from the file extension
+pub fn is_image(name: &str) -> bool {
+    is_image_entry(name).unwrap_or(false)
+}
+
+/// images of a directory in reading order, empty if it contains anything else
+pub fn folder_image_list(directory_path: &Path) -> Vec<String> {
+    let mut image_list = Vec::new();
+    let Ok(entries) = fs::read_dir(directory_path) else {
+        return image_list;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        // hidden files are ignored, like in library scans
+        if name.starts_with('.') {
+            continue;
+        }
+        if !entry.path().is_file() || !is_image(&name) {
+            return Vec::with_capacity(0);
+        }
+        image_list.push(name);
+    }
+    image_list.sort_by(|a, b| natural_cmp(a, b));
+    image_list
+}
+
+/// an images folder as a file, its size is the sum of its images
+fn extract_folder_infos(library_name: &str, directory_path: &Path) -> FileInfo {
+    let size = folder_image_list(directory_path)
+        .iter()
+        .filter_map(|image| fs::metadata(directory_path.join(image)).ok())
+        .map(|metadata| metadata.len() as i64)
+        .sum();
+    FileInfo {
+        format: Format::Folder,
+        size,
+        ..extract_file_infos(library_name, directory_path)
+    }
+}
+
+/// a directory of audio tracks as a file, its size is the sum of its tracks
+fn extract_audiobook_infos(library_name: &str, directory_path: &Path) -> FileInfo {
+    let size = audio::folder_audio_list(directory_path)
+        .iter()
+        .filter_map(|track| fs::metadata(directory_path.join(track)).ok())
+        .map(|metadata| metadata.len() as i64)
+        .sum();
+    FileInfo {
+        format: Format::Audiobook,
+        size,
+        ..extract_file_infos(library_name, directory_path)
     }
 }
 
@@ -268,6 +594,28 @@ async fn walk_recent_dir(
                 conn,
             )
             .await;
+            let folder_found: Vec<FileInfo> = sqlite::check_if_file_exists(
+                &current_directory.parent_path,
+                &current_directory.name,
+                conn,
+            )
+            .await
+            .into_iter()
+            .filter(|file| matches!(file.format, Format::Folder | Format::Audiobook))
+            .collect();
+            if !folder_image_list(&entry.path()).is_empty()
+                || !audio::folder_audio_list(&entry.path()).is_empty()
+            {
+                // images folder or audiobook, registered as a file : forget the directory and its files
+                for directory in directory_found {
+                    sqlite::delete_directory(&directory, conn).await;
+                }
+                continue;
+            }
+            // not (or no longer) an images folder or an audiobook
+            for folder in folder_found {
+                sqlite::delete_file(&folder, conn).await;
+            }
             // new directory
             if directory_found.is_empty() && !current_directory.parent_path.is_empty() {
                 sqlite::insert_new_dir(&current_directory, None, conn).await;
@@ -284,7 +632,11 @@ async fn walk_recent_dir(
             for file in registered_files {
                 let full_path = format!("{}/{}", file.parent_path, file.name);
                 let file_path = Path::new(&full_path);
-                if !file_path.is_file() {
+                let file_exists = match file.format {
+                    Format::Folder | Format::Audiobook => file_path.is_dir(),
+                    _ => file_path.is_file(),
+                };
+                if !file_exists {
                     sqlite::delete_file(&file, conn).await;
                 }
             }
@@ -292,6 +644,53 @@ async fn walk_recent_dir(
     }
 }
 
+/// insert a new file, or update it if already in database
+/// a new tokio runtime is created, to be used in the process_read_dir fn of jwalk crate
+fn insert_or_update_file(mut file_infos: FileInfo) {
+    let filename = file_infos.name.clone();
+    let parent_path = file_infos.parent_path.clone();
+    // create a new tokio runtime for inserts
+    // we need it to use async fns create_sqlite_pool and insert_new_file
+    // TODO create one conn for each insert ? not sure if it's realy optimal...
+    match Runtime::new() {
+        Ok(rt) => {
+            rt.block_on(async move {
+                if let Ok(conn) = sqlite::create_sqlite_pool().await {
+                    // check if file alrdeady exists in database
+                    let file_found = sqlite::check_if_file_exists(
+                        parent_path.as_str(),
+                        filename.as_str(),
+                        &conn,
+                    )
+                    .await;
+                    if file_found.is_empty() {
+                        // new file
+                        info!("new file found : {}/{}", parent_path, filename);
+                        sqlite::insert_new_file(&mut file_infos, None, &conn).await;
+                    } else if file_found.len() == 1 {
+                        // 1 file found, ok update it
+                        info!("file modified : {}/{}", parent_path, filename);
+                        // keep the id, we dont want to loose flags
+                        let ulid_found = &file_found[0].id;
+                        // insert with up to date values
+                        sqlite::insert_new_file(&mut file_infos, Some(ulid_found), &conn).await;
+                    } else {
+                        // multiple id for a file ? should not happen !
+                        // TODO propose repair or full rescan
+                        error!(
+                            "base possibly corrupted, multiple id found for file \"{}/{}\"",
+                            parent_path, filename
+                        );
+                    }
+                }
+            });
+        }
+        Err(e) => {
+            error!("unable to create runtime for new file insertion : {e}")
+        }
+    }
+}
+
 /// walk library dir and return list of files modified after the last successfull scan
 /// insert them in the process_read_dir fn of jwalk crate
 fn walk_recent_files_and_insert(library: Library, last_successfull_scan_date: Duration) {
@@ -300,79 +699,54 @@ fn walk_recent_files_and_insert(library: Library, last_successfull_scan_date: Du
     let recent_file_list = WalkDirGeneric::<(usize, bool)>::new(library_path)
         // TODO conf param
         .skip_hidden(true)
-        .process_read_dir(move |_depth, _path, _read_dir_state, children| {
+        .process_read_dir(move |depth, path, _read_dir_state, children| {
+            let is_recent = |file: &jwalk::DirEntry<(usize, bool)>| {
+                // retrieve metadatas for ctime
+                let meta = fs::metadata(file.path()).unwrap();
+                let file_modified_date = Duration::from_secs(meta.st_ctime() as u64);
+                file_modified_date > last_successfull_scan_date
+            };
+            // a directory of images only is a comic, its images are not inserted
+            // (the library root is never a comic)
+            let image_folder = depth.is_some_and(|depth| depth > 0)
+                && !children.is_empty()
+                && children.iter().all(|child| {
+                    child.as_ref().is_ok_and(|child| {
+                        child.file_type().is_file()
+                            && is_image(&child.file_name().to_string_lossy())
+                    })
+                });
+            if image_folder {
+                if children.iter().flatten().any(is_recent) {
+                    insert_or_update_file(extract_folder_infos(&library.name, path));
+                }
+                return;
+            }
+            // a directory of audio tracks (and their covers) is an audiobook
+            if depth.is_some_and(|depth| depth > 0) && !audio::folder_audio_list(path).is_empty() {
+                if children.iter().flatten().any(is_recent) {
+                    insert_or_update_file(extract_audiobook_infos(&library.name, path));
+                }
+                return;
+            }
             children.iter_mut().for_each(|files_found| {
                 if let Ok(file) = files_found {
-                    // retrieve metadatas for ctime
-                    let meta = fs::metadata(file.path()).unwrap();
-                    let file_modified_date = Duration::from_secs(meta.st_ctime() as u64);
                     // check ctime for files only, because directories will be not crossed
                     // without this check
-                    if file.file_type().is_file() && file_modified_date > last_successfull_scan_date
-                    {
+                    if file.file_type().is_file() && is_recent(file) {
                         debug!(
-                            "modified time {}, greater than last successfull scan {} for file [{}]",
-                            file_modified_date.as_secs(),
+                            "modified time greater than last successfull scan {} for file [{}]",
                             last_successfull_scan_date.as_secs(),
                             file.file_name().to_string_lossy()
                         );
                         // insert here for the jwalk parallelism benefit
                         // file_infos need to be mutable for ulid genereation at the insert step
-                        let mut file_infos =
-                            extract_file_infos(&library.name, file.path().as_path());
-                        let filename = file_infos.name.clone();
-                        let parent_path = file_infos.parent_path.clone();
-                        // create a new tokio runtime for inserts
-                        // we need it to use async fns create_sqlite_pool and insert_new_file
-                        // TODO create one conn for each insert ? not sure if it's realy optimal...
-                        match Runtime::new() {
-                            Ok(rt) => {
-                                rt.block_on(async move {
-                                    if let Ok(conn) = sqlite::create_sqlite_pool().await {
-                                        // check if file alrdeady exists in database
-                                        let file_found = sqlite::check_if_file_exists(
-                                            parent_path.as_str(),
-                                            filename.as_str(),
-                                            &conn,
-                                        )
-                                        .await;
-                                        if file_found.is_empty() {
-                                            // new file
-                                            info!("new file found : {}/{}", parent_path, filename);
-                                            sqlite::insert_new_file(&mut file_infos, None, &conn)
-                                                .await;
-                                        } else if file_found.len() == 1 {
-                                            // 1 file found, ok update it
-                                            info!("file modified : {}/{}", parent_path, filename);
-                                            let ulid_found = &file_found[0].id;
-                                            // we dont want to loose flags
-                                            file_infos.bookmarked_by =
-                                                file_found[0].bookmarked_by.clone();
-                                            file_infos.read_by = file_found[0].read_by.clone();
-                                            // insert with up to date values
-                                            sqlite::insert_new_file(
-                                                &mut file_infos,
-                                                Some(ulid_found),
-                                                &conn,
-                                            )
-                                            .await;
-                                        } else {
-                                            // multiple id for a file ? should not happen !
-                                            // TODO propose repair or full rescan
-                                            error!(
-                                        "base possibly corrupted, multiple id found for file \"{}/{}\"",
-                                        parent_path, filename
-                                    );
-                                        }
-                                    }
-                                });
-                                // flag file for insert
-                                file.client_state = true;
-                            }
-                            Err(e) => {
-                                error!("unable to create runtime for new file insertion : {e}")
-                            }
-                        }
+                        insert_or_update_file(extract_file_infos(
+                            &library.name,
+                            file.path().as_path(),
+                        ));
+                        // flag file for insert
+                        file.client_state = true;
                     }
                 }
             });
@@ -414,43 +788,7 @@ pub async fn extraction_routine(speed: i32, sleep_time: Duration) {
                         }
                     };
                 for directory in directories_to_scan_list {
-                    let directory_full_path =
-                        &format!("{}/{}", directory.parent_path, directory.name);
-                    // TODO comments why `(i32,)` ??
-                    // see https://github.com/launchbadge/sqlx/issues/1066 for example
-                    let directory_file_count: (i32,) = match sqlx::query_as(
-                        "SELECT count(*) FROM files WHERE instr(parent_path, ?) > 0;",
-                    )
-                    .bind(directory_full_path)
-                    .fetch_one(&conn)
-                    .await
-                    {
-                        Ok(file_count) => file_count,
-                        Err(e) => {
-                            error!(
-                                "unable to retrieve file number for directory [{}] : {e}",
-                                directory_full_path
-                            );
-                            (0,)
-                        }
-                    };
-                    // TODO Move to `sqlite` mod
-                    // insert number
-                    match sqlx::query("UPDATE directories SET file_count = ? WHERE id = ?;")
-                        .bind(directory_file_count.0)
-                        .bind(directory.id)
-                        .execute(&conn)
-                        .await
-                    {
-                        Ok(_) => debug!(
-                            "insert file count {} for directory [{}]",
-                            directory_file_count.0, directory_full_path
-                        ),
-                        Err(e) => error!(
-                            "unable to set file count for directory [{}] : {e}",
-                            directory_full_path
-                        ),
-                    }
+                    sqlite::update_directory_file_count(&directory, &conn).await;
                 }
 
                 // files extract
@@ -475,6 +813,24 @@ pub async fn extraction_routine(speed: i32, sleep_time: Duration) {
                 for file_to_scan in files_to_scan_list {
                     extract_all(&file_to_scan, &conn).await;
                 }
+
+                // files scanned before series detection (see migration 7)
+                let series_to_scan_list: Vec<FileInfo> = match sqlx::query_as(
+                    "SELECT * FROM files WHERE series_me = TRUE AND scan_me = '0' LIMIT ?;",
+                )
+                .bind(speed)
+                .fetch_all(&conn)
+                .await
+                {
+                    Ok(file_found) => file_found,
+                    Err(e) => {
+                        error!("unable to retrieve file list to scan for series : {e}");
+                        Vec::with_capacity(0)
+                    }
+                };
+                for file_to_scan in series_to_scan_list {
+                    extract_metadata_and_series(&file_to_scan, &conn).await;
+                }
                 // TODO true schedule, last extract status in db...
                 info!(
                     "stop extraction, sleeping for {} seconds",
@@ -506,7 +862,7 @@ async fn purge_removed_directories(conn: &Pool<Sqlite>) {
 }
 
 /// scan function for routine or on demand
-pub async fn launch_scan(library: &Library, conn: &Pool<Sqlite>) -> Result<()> {
+pub async fn launch_scan(library: &Library, conn: &Pool<Sqlite>) -> compress_tools::Result<()> {
     let library_path = Path::new(&library.path);
 
     if !library_path.is_dir() {
@@ -625,34 +981,111 @@ pub fn dynamic_image_to_vec_u8(image: DynamicImage) -> Option<Vec<u8>> {
 }
 
 pub async fn extract_all(file: &FileInfo, conn: &Pool<Sqlite>) {
+    // problems are found again at each extraction
+    sqlite::delete_problems(file, conn).await;
+    // audio tags are read once, for cover, duration and metadata
+    let audio =
+        matches!(file.format.as_str(), "mp3" | "m4b" | "audiobook").then(|| audio_info(file));
     // cover
     let dynamic_image_cover = match file.format.as_str() {
         "epub" => extract_epub_cover(file),
+        "mobi" | "azw3" => extract_mobi_cover(file),
+        "fb2" => extract_fb2_cover(file),
         "pdf" => extract_pdf_cover(file),
-        "cbz" | "cbr" | "cb7" => extract_comic_cover(file),
+        "cbz" | "cbr" | "cb7" | "cbt" => extract_comic_cover(file),
+        "folder" => extract_folder_cover(file),
+        "jpg" => extract_image_cover(file),
+        "mp3" | "m4b" | "audiobook" => audio
+            .as_ref()
+            .and_then(|info| extract_audio_cover(file, info.as_ref().ok()?)),
         _ => None,
     };
 
-    if let Some(cover) = dynamic_image_cover {
-        match dynamic_image_to_vec_u8(cover) {
+    match dynamic_image_cover {
+        Some(cover) => match dynamic_image_to_vec_u8(cover) {
             Some(buffered_u8_cover) => sqlite::insert_cover(file, &buffered_u8_cover, conn).await,
             None => warn!(
                 "unable to insert cover for file {},{}",
                 file.parent_path, file.name
             ),
+        },
+        None => {
+            if matches!(
+                file.format.as_str(),
+                "cbz" | "cbr" | "cb7" | "cbt" | "folder" | "jpg"
+            ) {
+                sqlite::insert_problem(file, "unable to extract cover", conn).await;
+            }
         }
     }
     // total_pages
     match file.format.as_str() {
         "epub" => extract_epub_page_number(file, conn).await,
+        "mobi" | "azw3" => extract_mobi_page_number(file, conn).await,
+        "fb2" => extract_fb2_page_number(file, conn).await,
+        "txt" | "md" => extract_text_page_number(file, conn).await,
         "pdf" => extract_pdf_page_number(file, conn).await,
-        "cbz" | "cbr" | "cb7" => extract_comic_page_number(file, conn).await,
+        "cbz" | "cbr" | "cb7" | "cbt" => extract_comic_page_number(file, conn).await,
+        "folder" => {
+            let path = format!("{}/{}", file.parent_path, file.name);
+            let total_pages = folder_image_list(Path::new(&path)).len();
+            sqlite::insert_total_pages(file, total_pages as i32, conn).await;
+        }
+        "jpg" => sqlite::insert_total_pages(file, 1, conn).await,
+        "mp3" | "m4b" | "audiobook" => {
+            if let Some(info) = &audio {
+                extract_audio_duration(file, info, conn).await;
+            }
+        }
         _ => (),
     }
+    match &audio {
+        Some(info) => {
+            let metadata = info.as_ref().ok().map(|info| info.metadata());
+            insert_metadata_and_series(file, metadata, conn).await;
+        }
+        None => extract_metadata_and_series(file, conn).await,
+    }
     // scan_flag
     sqlite::set_scan_flag(file, 0, conn).await;
 }
 
+/// metadata and series of a file, alone for files scanned before series detection
+async fn extract_metadata_and_series(file: &FileInfo, conn: &Pool<Sqlite>) {
+    let metadata = match file.format.as_str() {
+        "epub" => extract_epub_metadata(file),
+        "mobi" | "azw3" => extract_mobi_metadata(file),
+        "fb2" => extract_fb2_metadata(file),
+        "md" => extract_markdown_metadata(file),
+        "mp3" | "m4b" | "audiobook" => extract_audio_metadata(file),
+        "pdf" => extract_pdf_metadata(file),
+        "cbz" | "cbr" | "cb7" | "cbt" => extract_comic_metadata(file),
+        _ => None,
+    };
+    insert_metadata_and_series(file, metadata, conn).await;
+}
+
+/// metadata found in a file, and its series from metadata or file name
+async fn insert_metadata_and_series(
+    file: &FileInfo,
+    metadata: Option<FileMetadata>,
+    conn: &Pool<Sqlite>,
+) {
+    if let Some(metadata) = &metadata {
+        sqlite::insert_metadata(file, metadata, conn).await;
+    }
+    // series, from metadata first, then from file name
+    let filename_series = series_from_filename(&file.name, &file.parent_path);
+    let series = match metadata.and_then(|metadata| metadata.series.zip(Some(metadata.volume))) {
+        Some((series, volume)) => {
+            Some((series, volume.or(filename_series.map(|(_, volume)| volume))))
+        }
+        None => filename_series.map(|(series, volume)| (series, Some(volume))),
+    };
+    sqlite::insert_series(file, series, conn).await;
+    sqlite::set_series_flag(file, false, conn).await;
+}
+
 pub async fn extract_pdf_page_number(file: &FileInfo, conn: &Pool<Sqlite>) {
     let full_path = format!("file://{}/{}", file.parent_path, file.name);
     let mut total_pages = 0;
@@ -664,6 +1097,18 @@ pub async fn extract_pdf_page_number(file: &FileInfo, conn: &Pool<Sqlite>) {
     sqlite::insert_total_pages(file, total_pages, conn).await;
 }
 
+pub fn extract_pdf_metadata(file: &FileInfo) -> Option<FileMetadata> {
+    let full_path = format!("file://{}/{}", file.parent_path, file.name);
+    let pdf_document = Document::from_file(&full_path, None).ok()?;
+    Some(FileMetadata {
+        title: pdf_document.title().map(|title| title.to_string()),
+        author: pdf_document.author().map(|author| author.to_string()),
+        series: None,
+        volume: None,
+        description: pdf_document.subject().map(|subject| subject.to_string()),
+    })
+}
+
 // TODO error/warn message for each `None` in arms
 // ⚠️  "the image crate is known to be quite slow when compiled in debug mode"
 // from https://www.reddit.com/r/rust/comments/k1wjix/why_opening_of_images_is_so_slow/
@@ -703,6 +1148,27 @@ pub async fn extract_epub_page_number(file: &FileInfo, conn: &Pool<Sqlite>) {
     };
 }
 
+pub fn extract_epub_metadata(file: &FileInfo) -> Option<FileMetadata> {
+    let full_path = format!("{}/{}", file.parent_path, file.name);
+    let doc = EpubDoc::new(&full_path).ok()?;
+    let mdata = |property: &str| doc.mdata(property).map(|item| item.value.clone());
+    // epub3 collection position is a refinement of the collection
+    let group_position = doc
+        .mdata("belongs-to-collection")
+        .and_then(|item| item.refinement("group-position"))
+        .map(|refinement| refinement.value.clone());
+    Some(FileMetadata {
+        title: mdata("title"),
+        author: mdata("creator"),
+        // calibre (epub2) or epub3 collection
+        series: mdata("calibre:series").or_else(|| mdata("belongs-to-collection")),
+        volume: mdata("calibre:series_index")
+            .or(group_position)
+            .and_then(|volume| volume.trim().parse().ok()),
+        description: mdata("description"),
+    })
+}
+
 // TODO error/warn message for each `None` in arms
 pub fn extract_epub_cover(file: &FileInfo) -> Option<image::DynamicImage> {
     let full_path = format!("{}/{}", file.parent_path, file.name);
@@ -734,161 +1200,558 @@ pub fn extract_epub_cover(file: &FileInfo) -> Option<image::DynamicImage> {
     }
 }
 
-// filter a list of files in archives to keep only images with thier indexes
-// fn extract_comic_image_list(archive: &str) -> Vec<(usize, String)> {
-pub fn extract_comic_image_list(archive: &File) -> Vec<String> {
-    // thread '<unnamed>' panicked at 'list_archive_files: Utf(Utf8Error { valid_up_to: 20, error_len: Some(1) })', src/scanner.rs:716:55
-
-    // // encoding prb
-    // // T03 - La Marque Des Demons.cbz
-    // // unable to extract file list form archive : invalid utf-8 sequence of 1 bytes from index 20
-    // // thread 'tokio-runtime-worker' panicked at 'get file path from file list at', src/reader.rs:26:18
-    // // following code does not work, must find the correct encoding
-    // // --------------
-    // use encoding_rs::{GBK, SHIFT_JIS};
-    // let decode_gbk = |bytes: &[u8]| {
-    //     GBK.decode_without_bom_handling_and_without_replacement(bytes)
-    //         .map(String::from)
-    //         .ok_or(Error::Encoding(std::borrow::Cow::Borrowed("GBK failure")))
-    // };
-    // let decode_sjis = |bytes: &[u8]| {
-    //     SHIFT_JIS
-    //         .decode_without_bom_handling_and_without_replacement(bytes)
-    //         .map(String::from)
-    //         .ok_or(Error::Encoding(std::borrow::Cow::Borrowed(
-    //             "SHIFT_JIS failure",
-    //         )))
-    // };
-    // // let decode_utf8 = |bytes: &[u8]| Ok(std::str::from_utf8(bytes)?.to_owned());
-    // let file_list = list_archive_files_with_encoding(archive, decode_sjis).expect("MYTEST");
-    // use std::ffi::OsStr;
-    // use std::os::unix::ffi::OsStrExt;
-    // let mut comic_file_list: Vec<String> = Vec::new();
-    // for file in file_list {
-    //     let vecu8 = file.into_bytes();
-    //     let os_str = OsStr::from_bytes(&vecu8[..]);
-    //     let pathh = os_str.to_string_lossy().into_owned();
-    //     comic_file_list.push(pathh);
-    //     // error!("pathh : {pathh}");
-    // }
+/// cover image of an ebook, resized
+fn decode_cover(image: &[u8], full_path: &str) -> Option<image::DynamicImage> {
+    match image::load_from_memory(image) {
+        Ok(img) => Some(resize_cover(img)),
+        Err(e) => {
+            warn!("I can't decode cover image for file {full_path} : {e}");
+            None
+        }
+    }
+}
 
-    let comic_file_list = match list_archive_files(archive) {
-        Ok(list) => list,
+/// mobi and azw3 pages are their chapters
+pub async fn extract_mobi_page_number(file: &FileInfo, conn: &Pool<Sqlite>) {
+    let full_path = format!("{}/{}", file.parent_path, file.name);
+    let total_pages = match Mobi::open(&full_path).and_then(|mobi| mobi.chapters()) {
+        Ok(chapters) => chapters.len(),
         Err(e) => {
-            // 🔥🔥🔥 TODO 🔥🔥🔥 probably an encoding prb, error to warn (or info), and try with
-            // list_archive_files_with_encoding ?
-            // or ArchiveIterator...
-            // ---------------------------------
-            // let mut name = String::default();
-            // let mut size = 0;
-            // error!("start decode_utf8");
-            // let decode_utf8 = |bytes: &[u8]| Ok(std::str::from_utf8(bytes)?.to_owned());
-            // error!("start ArchiveIterator");
-            // let mut iter = ArchiveIterator::from_read_with_encoding(archive, decode_utf8).unwrap();
-            // error!("start loop");
-            // for content in &mut iter {
-            //     match content {
-            //         ArchiveContents::StartOfEntry(s, _) => {
-            //             error!("{s}");
-            //             name = s
-            //         }
-            //         ArchiveContents::DataChunk(v) => size += v.len(),
-            //         ArchiveContents::EndOfEntry => {
-            //             println!("Entry {} was {} bytes", name, size);
-            //             size = 0;
-            //         }
-            //         ArchiveContents::Err(e) => {
-            //             error!("ArchiveContents Err : {e}");
-            //         }
-            //     }
-            // }
-            // error!("close");
-            // iter.close().unwrap();
-
-            warn!("unable to extract file list form archive : {e}");
-            Vec::with_capacity(0)
+            warn!("unable to read text of file {full_path} : {e}");
+            sqlite::insert_problem(file, &e, conn).await;
+            0
         }
     };
-    // TODO use drain_filter when it will be stable
-    // see https://github.com/rust-lang/rust/issues/43244
-    let mut image_list = Vec::with_capacity(comic_file_list.capacity());
-    for file_path in comic_file_list.into_iter() {
-        if file_path.to_lowercase().contains(".jpg")
-            || file_path.to_lowercase().contains(".jpeg")
-            || file_path.to_lowercase().contains(".png")
-        {
-            image_list.push(file_path);
+    sqlite::insert_total_pages(file, total_pages as i32, conn).await;
+}
+
+pub fn extract_mobi_metadata(file: &FileInfo) -> Option<FileMetadata> {
+    let full_path = format!("{}/{}", file.parent_path, file.name);
+    Mobi::open(&full_path).ok().map(|mobi| mobi.metadata())
+}
+
+pub fn extract_mobi_cover(file: &FileInfo) -> Option<image::DynamicImage> {
+    let full_path = format!("{}/{}", file.parent_path, file.name);
+    let mobi = Mobi::open(&full_path).ok()?;
+    decode_cover(mobi.cover()?, &full_path)
+}
+
+/// fb2 pages are the sections of their main body
+pub async fn extract_fb2_page_number(file: &FileInfo, conn: &Pool<Sqlite>) {
+    let full_path = format!("{}/{}", file.parent_path, file.name);
+    let total_pages = match Fb2::open(&full_path) {
+        Ok(fb2) => fb2.chapter_count(),
+        Err(e) => {
+            warn!("unable to read file {full_path} : {e}");
+            sqlite::insert_problem(file, &e, conn).await;
+            0
         }
+    };
+    sqlite::insert_total_pages(file, total_pages as i32, conn).await;
+}
+
+pub fn extract_fb2_metadata(file: &FileInfo) -> Option<FileMetadata> {
+    let full_path = format!("{}/{}", file.parent_path, file.name);
+    Fb2::open(&full_path).ok()?.metadata()
+}
+
+pub fn extract_fb2_cover(file: &FileInfo) -> Option<image::DynamicImage> {
+    let full_path = format!("{}/{}", file.parent_path, file.name);
+    let fb2 = Fb2::open(&full_path).ok()?;
+    decode_cover(&fb2.cover()?, &full_path)
+}
+
+/// text and markdown pages follow the pagination set in admin panel
+pub async fn extract_text_page_number(file: &FileInfo, conn: &Pool<Sqlite>) {
+    let full_path = format!("{}/{}", file.parent_path, file.name);
+    let pagination = sqlite::get_text_pagination(conn).await;
+    let total_pages = match text::read_text(&full_path) {
+        Ok(content) => text::paginate(&content, &pagination, file.format == Format::Md).len(),
+        Err(e) => {
+            warn!("unable to read file {full_path} : {e}");
+            sqlite::insert_problem(file, &e, conn).await;
+            0
+        }
+    };
+    sqlite::insert_total_pages(file, total_pages as i32, conn).await;
+}
+
+/// pages being read of text and markdown files follow a pagination change
+pub async fn convert_text_reading_pages(
+    old: &text::TextPagination,
+    new: &text::TextPagination,
+    conn: &Pool<Sqlite>,
+) {
+    for file in sqlite::get_read_text_files(conn).await {
+        let full_path = format!("{}/{}", file.parent_path, file.name);
+        let content = match text::read_text(&full_path) {
+            Ok(content) => content,
+            Err(e) => {
+                warn!("unable to read file {full_path} : {e}");
+                continue;
+            }
+        };
+        let markdown = file.format == Format::Md;
+        for (user_id, page) in sqlite::get_reading_pages(&file.id, conn).await {
+            let page = usize::try_from(page).unwrap_or(0);
+            let converted = text::convert_page(&content, old, new, markdown, page);
+            sqlite::update_reading_page(&file.id, user_id, converted as i32, conn).await;
+        }
+        let total_pages = text::paginate(&content, new, markdown).len();
+        sqlite::insert_total_pages(&file, total_pages as i32, conn).await;
     }
-    // sometime the archive does not begin by image 01...
-    image_list.sort();
-    image_list
 }
 
-pub async fn extract_comic_page_number(file: &FileInfo, conn: &Pool<Sqlite>) {
-    if let Ok(compressed_comic_file) = File::open(format!("{}/{}", file.parent_path, file.name)) {
-        let file_list = extract_comic_image_list(&compressed_comic_file);
-        let total_pages = file_list.len();
-        sqlite::insert_total_pages(file, total_pages as i32, conn).await;
+/// title of a markdown file is its first heading
+pub fn extract_markdown_metadata(file: &FileInfo) -> Option<FileMetadata> {
+    let full_path = format!("{}/{}", file.parent_path, file.name);
+    let content = text::read_text(&full_path).ok()?;
+    Some(FileMetadata {
+        title: Some(text::markdown_title(&content)?),
+        ..Default::default()
+    })
+}
+
+/// duration, chapters, tags and cover of an audiobook, file or directory of tracks
+fn audio_info(file: &FileInfo) -> Result<audio::AudioInfo, String> {
+    let full_path = format!("{}/{}", file.parent_path, file.name);
+    match file.format {
+        Format::Audiobook => audio::folder_info(Path::new(&full_path)),
+        _ => audio::audio_info(Path::new(&full_path)),
     }
 }
 
-pub fn extract_comic_cover(file: &FileInfo) -> Option<image::DynamicImage> {
-    let archive_path = &format!("{}/{}", file.parent_path, file.name);
-    if let Ok(compressed_comic_file) = File::open(archive_path) {
-        // get images list from archive
-        let comic_file_list = extract_comic_image_list(&compressed_comic_file);
-        // set path file wanted from page index
-        let image_path_in_achive = match comic_file_list.first() {
-            Some(path) => path,
-            None => {
-                warn!("could not retrive cover in archive");
-                ""
+/// audiobooks pages are seconds, so listening progress works like reading progress
+pub async fn extract_audio_duration(
+    file: &FileInfo,
+    info: &Result<audio::AudioInfo, String>,
+    conn: &Pool<Sqlite>,
+) {
+    let total_pages = match info {
+        Ok(info) => {
+            sqlite::insert_chapters(file, &info.chapters, conn).await;
+            info.duration.ceil() as i32
+        }
+        Err(e) => {
+            warn!(
+                "unable to read audio file {}/{} : {e}",
+                file.parent_path, file.name
+            );
+            sqlite::insert_problem(file, e, conn).await;
+            0
+        }
+    };
+    sqlite::insert_total_pages(file, total_pages, conn).await;
+}
+
+pub fn extract_audio_metadata(file: &FileInfo) -> Option<FileMetadata> {
+    audio_info(file).ok().map(|info| info.metadata())
+}
+
+pub fn extract_audio_cover(
+    file: &FileInfo,
+    info: &audio::AudioInfo,
+) -> Option<image::DynamicImage> {
+    let full_path = format!("{}/{}", file.parent_path, file.name);
+    decode_cover(info.cover.as_ref()?, &full_path)
+}
+
+/// encoding of the entries names in an archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameEncoding {
+    Utf8,
+    ShiftJis,
+    Gbk,
+    Cp1252,
+    Cp437,
+}
+impl NameEncoding {
+    /// decode a raw entry name, `None` if the bytes are invalid for this encoding
+    pub fn decode(&self, bytes: &[u8]) -> Option<String> {
+        let encoding = match self {
+            NameEncoding::Utf8 => return std::str::from_utf8(bytes).ok().map(String::from),
+            NameEncoding::Cp437 => {
+                return Some(
+                    bytes
+                        .iter()
+                        .map(|&byte| match byte {
+                            0..=0x7F => char::from(byte),
+                            _ => CP437_HIGH[byte as usize - 0x80],
+                        })
+                        .collect(),
+                )
             }
+            NameEncoding::ShiftJis => encoding_rs::SHIFT_JIS,
+            NameEncoding::Gbk => encoding_rs::GBK,
+            NameEncoding::Cp1252 => encoding_rs::WINDOWS_1252,
         };
-        // uncompress corresponding image
-        let mut vec_cover: Vec<u8> = Vec::new();
-        // RAR need to reopen file... why ? and why rar only ?
-        match File::open(archive_path) {
-            Ok(compressed_comic_file) => {
-                // ⚠ unsafe code here from compress-tools
-                // TODO change lib ?
-                match uncompress_archive_file(
-                    &compressed_comic_file,
-                    &mut vec_cover,
-                    image_path_in_achive,
-                ) {
-                    Ok(_) => (),
-                    Err(e) => warn!(
-                        "unable to extract path [{}] from file [{}] : {e}",
-                        image_path_in_achive, file.name
-                    ),
+        encoding
+            .decode_without_bom_handling_and_without_replacement(bytes)
+            .map(String::from)
+    }
+
+    /// callback given to libarchive to decode entries names
+    fn decoder(&self) -> DecodeCallback {
+        match self {
+            NameEncoding::Utf8 => |bytes| NameEncoding::Utf8.decode_callback(bytes),
+            NameEncoding::ShiftJis => |bytes| NameEncoding::ShiftJis.decode_callback(bytes),
+            NameEncoding::Gbk => |bytes| NameEncoding::Gbk.decode_callback(bytes),
+            NameEncoding::Cp1252 => |bytes| NameEncoding::Cp1252.decode_callback(bytes),
+            NameEncoding::Cp437 => |bytes| NameEncoding::Cp437.decode_callback(bytes),
+        }
+    }
+
+    fn decode_callback(&self, bytes: &[u8]) -> compress_tools::Result<String> {
+        self.decode(bytes)
+            .ok_or(compress_tools::Error::Encoding(std::borrow::Cow::Owned(
+                format!("invalid {self:?} entry name"),
+            )))
+    }
+}
+
+/// characters 0x80 to 0xFF of the IBM PC code page, used by old zip tools
+const CP437_HIGH: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å', //
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ', //
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»', //
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐', //
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧', //
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀', //
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩', //
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{A0}',
+];
+
+/// guess the encoding of entries names which are not utf-8
+pub fn detect_name_encoding(names: &[Vec<u8>]) -> NameEncoding {
+    let decode_all = |encoding: NameEncoding| -> Option<String> {
+        names
+            .iter()
+            .map(|name| encoding.decode(name))
+            .collect::<Option<Vec<String>>>()
+            .map(|names| names.concat())
+    };
+    // japanese : kana are a strong sign, halfwidth katakana are mostly misread latin or chinese
+    if let Some(names) = decode_all(NameEncoding::ShiftJis) {
+        if names
+            .chars()
+            .any(|c| ('\u{3041}'..='\u{30FF}').contains(&c))
+            && !names
+                .chars()
+                .any(|c| ('\u{FF61}'..='\u{FF9F}').contains(&c))
+        {
+            return NameEncoding::ShiftJis;
+        }
+    }
+    // chinese : all double bytes characters in the GB2312 range
+    let is_gb2312 = |name: &Vec<u8>| {
+        let mut bytes = name.iter();
+        while let Some(&byte) = bytes.next() {
+            if byte >= 0x80 {
+                let gb2312_range = 0xA1..0xFF;
+                if !gb2312_range.contains(&byte)
+                    || !bytes
+                        .next()
+                        .is_some_and(|second| gb2312_range.contains(second))
+                {
+                    return false;
                 }
             }
-            Err(e) => {
-                warn!(
-                    "unable to open path [{}] from file [{}] : {e}",
-                    image_path_in_achive, file.name
-                );
-            }
+        }
+        true
+    };
+    if names.iter().all(is_gb2312) && decode_all(NameEncoding::Gbk).is_some() {
+        return NameEncoding::Gbk;
+    }
+    // western : the code page giving the most letters
+    let letters = |encoding: NameEncoding| {
+        decode_all(encoding).map_or(0, |names| {
+            names
+                .chars()
+                .filter(|c| !c.is_ascii() && c.is_alphabetic())
+                .count()
+        })
+    };
+    if letters(NameEncoding::Cp437) > letters(NameEncoding::Cp1252) {
+        NameEncoding::Cp437
+    } else {
+        NameEncoding::Cp1252
+    }
+}
+
+/// entries of an archive, with the encoding of their names
+pub fn list_archive_entries(archive_path: &str) -> Result<(Vec<String>, NameEncoding), String> {
+    let open = || File::open(archive_path).map_err(|e| format!("unable to open archive : {e}"));
+    match list_archive_files(open()?) {
+        Ok(list) => Ok((list, NameEncoding::Utf8)),
+        Err(utf8_error) => {
+            // names are not utf-8 : list them as latin-1 to get back their raw bytes
+            let latin1 = |bytes: &[u8]| Ok(bytes.iter().map(|&byte| char::from(byte)).collect());
+            let raw_names: Vec<Vec<u8>> = list_archive_files_with_encoding(open()?, latin1)
+                .map_err(|_| format!("unable to list archive entries : {utf8_error}"))?
+                .iter()
+                .map(|name| name.chars().map(|c| c as u8).collect())
+                .collect();
+            let encoding = detect_name_encoding(&raw_names);
+            info!("entries names of archive {archive_path} decoded as {encoding:?}");
+            let list = raw_names
+                .iter()
+                .map(|name| encoding.decode(name))
+                .collect::<Option<Vec<String>>>()
+                .ok_or(format!("unable to decode entries names as {encoding:?}"))?;
+            Ok((list, encoding))
+        }
+    }
+}
+
+/// uncompress an entry of an archive, named in the given encoding
+pub fn uncompress_archive_entry(
+    archive_path: &str,
+    entry: &str,
+    encoding: NameEncoding,
+) -> Option<Vec<u8>> {
+    let mut data: Vec<u8> = Vec::new();
+    // RAR need to reopen file... why ? and why rar only ?
+    let archive = match File::open(archive_path) {
+        Ok(archive) => archive,
+        Err(e) => {
+            warn!("unable to open archive {archive_path} : {e}");
+            return None;
+        }
+    };
+    // ⚠ unsafe code here from compress-tools
+    let result = match encoding {
+        NameEncoding::Utf8 => uncompress_archive_file(archive, &mut data, entry),
+        _ => uncompress_archive_file_with_encoding(archive, &mut data, entry, encoding.decoder()),
+    };
+    match result {
+        Ok(_) => Some(data),
+        Err(e) => {
+            warn!("unable to extract path [{entry}] from file [{archive_path}] : {e}");
+            None
+        }
+    }
+}
+
+/// images of a comic archive, sorted in reading order
+pub fn extract_comic_image_list(archive_path: &str) -> Result<(Vec<String>, NameEncoding), String> {
+    let (entries, encoding) = list_archive_entries(archive_path)?;
+    let mut image_list = Vec::with_capacity(entries.len());
+    for entry in entries.into_iter() {
+        let is_image = match is_image_entry(&entry) {
+            Some(is_image) => is_image,
+            // no extension, uncompress it to check its first bytes
+            None => uncompress_archive_entry(archive_path, &entry, encoding)
+                .is_some_and(|data| is_image_data(&data)),
         };
+        if is_image {
+            image_list.push(entry);
+        }
+    }
+    // sometime the archive does not begin by image 01...
+    image_list.sort_by(|a, b| natural_cmp(a, b));
+    Ok((image_list, encoding))
+}
 
-        match image::load_from_memory(&vec_cover) {
-            // match image::load_from_memory(&cover.0) {
-            Ok(img) => {
-                let cover = resize_cover(img);
-                Some(cover)
+pub async fn extract_comic_page_number(file: &FileInfo, conn: &Pool<Sqlite>) {
+    let archive_path = format!("{}/{}", file.parent_path, file.name);
+    // cbz are read without libarchive, count their pages the same way
+    let image_count = match reader::cbz_image_count(file) {
+        Some(image_count) => Ok(image_count),
+        None => extract_comic_image_list(&archive_path).map(|(image_list, _)| image_list.len()),
+    };
+    let total_pages = match image_count {
+        Ok(image_count) => {
+            if image_count == 0 {
+                sqlite::insert_problem(file, "no image found in archive", conn).await;
             }
-            Err(_) => {
-                warn!("I can't decode cover image for file {archive_path}");
-                None
+            let avif_count = count_avif_entries(file);
+            if avif_count > 0 {
+                let problem =
+                    format!("{avif_count} avif pages skipped, avif images are not decoded");
+                sqlite::insert_problem(file, &problem, conn).await;
             }
+            image_count
         }
-    } else {
+        Err(e) => {
+            warn!("unable to extract file list from archive {archive_path} : {e}");
+            sqlite::insert_problem(file, &e, conn).await;
+            0
+        }
+    };
+    sqlite::insert_total_pages(file, total_pages as i32, conn).await;
+}
+
+pub fn extract_comic_cover(file: &FileInfo) -> Option<image::DynamicImage> {
+    let archive_path = &format!("{}/{}", file.parent_path, file.name);
+    let vec_cover = match reader::get_cbz_image(file, 0) {
+        Some(vec_cover) => vec_cover,
+        None => {
+            // get images list from archive
+            let (image_list, encoding) = extract_comic_image_list(archive_path).ok()?;
+            let Some(image_path_in_achive) = image_list.first() else {
+                warn!("could not retrive cover in archive");
+                return None;
+            };
+            // uncompress corresponding image
+            uncompress_archive_entry(archive_path, image_path_in_achive, encoding)?
+        }
+    };
+    match image::load_from_memory(&vec_cover) {
+        Ok(img) => {
+            let cover = resize_cover(img);
+            Some(cover)
+        }
+        Err(_) => {
+            warn!("I can't decode cover image for file {archive_path}");
+            None
+        }
+    }
+}
+
+/// cover of an images folder : its first image
+pub fn extract_folder_cover(file: &FileInfo) -> Option<image::DynamicImage> {
+    let directory_path = format!("{}/{}", file.parent_path, file.name);
+    let directory_path = Path::new(&directory_path);
+    let first_image = folder_image_list(directory_path).into_iter().next()?;
+    match image::open(directory_path.join(&first_image)) {
+        Ok(img) => Some(resize_cover(img)),
+        Err(e) => {
+            warn!(
+                "I can't decode cover image [{first_image}] for folder {} : {e}",
+                directory_path.to_string_lossy()
+            );
+            None
+        }
+    }
+}
+
+/// a loose image is its own cover
+pub fn extract_image_cover(file: &FileInfo) -> Option<image::DynamicImage> {
+    let full_path = format!("{}/{}", file.parent_path, file.name);
+    match image::open(&full_path) {
+        Ok(img) => Some(resize_cover(img)),
+        Err(e) => {
+            warn!("I can't decode image {full_path} : {e}");
+            None
+        }
+    }
+}
+
+/// content of a simple xml tag, like `<Series>Asterix</Series>`
+fn xml_tag_value(xml: &str, tag: &str) -> Option<String> {
+    let start = xml.find(&format!("<{tag}>"))? + tag.len() + 2;
+    let end = start + xml[start..].find(&format!("</{tag}>"))?;
+    let value = xml[start..end]
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&");
+    let value = value.trim();
+    if value.is_empty() {
         None
+    } else {
+        Some(value.to_string())
     }
 }
 
+/// metadata from the `ComicInfo.xml` file of comic archives
+pub fn extract_comic_metadata(file: &FileInfo) -> Option<FileMetadata> {
+    let archive_path = &format!("{}/{}", file.parent_path, file.name);
+    let (entries, encoding) = list_archive_entries(archive_path).ok()?;
+    let comic_info_path = entries
+        .iter()
+        .find(|path| path.to_lowercase().ends_with("comicinfo.xml"))?;
+    let comic_info = uncompress_archive_entry(archive_path, comic_info_path, encoding)?;
+    let comic_info = String::from_utf8_lossy(&comic_info);
+    Some(FileMetadata {
+        title: xml_tag_value(&comic_info, "Title"),
+        author: xml_tag_value(&comic_info, "Writer"),
+        series: xml_tag_value(&comic_info, "Series"),
+        // issue number, or volume of the series
+        volume: xml_tag_value(&comic_info, "Number")
+            .or_else(|| xml_tag_value(&comic_info, "Volume"))
+            .and_then(|volume| volume.parse().ok()),
+        description: xml_tag_value(&comic_info, "Summary"),
+    })
+}
+
+/// volume markers, before the number : `Vol 01`, `v01`, `T01`, `#1`...
+const VOLUME_MARKERS: [&str; 12] = [
+    "volume ", "volume", "vol. ", "vol.", "vol ", "vol", "tome ", "tome", "book ", "#", "t", "v",
+];
+
+/// series and volume number from a file name : `One Piece v03.cbz`,
+/// `Dragonlance 02 - Dragons of Winter Night.epub`, or `T01 - Asterix le Gaulois.pdf` named
+/// after its directory
+pub fn series_from_filename(name: &str, parent_path: &str) -> Option<(String, f64)> {
+    let stem = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+    // ignore tags like `(2010)` or `[digital]`, and underscores used as spaces
+    let mut cleaned = String::with_capacity(stem.len());
+    let mut depth = 0;
+    for character in stem.chars() {
+        match character {
+            '(' | '[' => depth += 1,
+            ')' | ']' if depth > 0 => depth -= 1,
+            '_' if depth == 0 => cleaned.push(' '),
+            _ if depth == 0 => cleaned.push(character),
+            _ => (),
+        }
+    }
+    // first number with a volume marker, or else the first one separated from words
+    // (4 digits numbers are probably years)
+    let mut found: Option<(String, String)> = None;
+    let mut fallback: Option<(String, String)> = None;
+    let mut characters = cleaned.char_indices().peekable();
+    while let Some((start, character)) = characters.next() {
+        if !character.is_ascii_digit() {
+            continue;
+        }
+        let mut end = start + 1;
+        while let Some((index, character)) = characters.peek() {
+            let decimal_point =
+                *character == '.' && cleaned[index + 1..].starts_with(|c: char| c.is_ascii_digit());
+            if character.is_ascii_digit() || decimal_point {
+                end = index + 1;
+                characters.next();
+            } else {
+                break;
+            }
+        }
+        let before = &cleaned[..start];
+        let lowercase_before = before.to_ascii_lowercase();
+        let marker = VOLUME_MARKERS.iter().find(|marker| {
+            lowercase_before.strip_suffix(**marker).is_some_and(|rest| {
+                rest.is_empty() || rest.ends_with(|c: char| !c.is_alphanumeric())
+            })
+        });
+        let number = cleaned[start..end].to_string();
+        match marker {
+            Some(marker) => {
+                found = Some((before[..before.len() - marker.len()].to_string(), number));
+                break;
+            }
+            None if fallback.is_none()
+                && number.len() < 4
+                && !before.ends_with(|c: char| c.is_alphanumeric()) =>
+            {
+                fallback = Some((before.to_string(), number));
+            }
+            None => (),
+        }
+    }
+    let (series, volume) = found.or(fallback)?;
+    let volume: f64 = volume.parse().ok()?;
+    let series = series.trim_matches(|c: char| c.is_whitespace() || "-_.,:#".contains(c));
+    // no name before the number, use the directory name
+    let series = if series.is_empty() {
+        Path::new(parent_path).file_name()?.to_str()?
+    } else {
+        series
+    };
+    Some((series.to_string(), volume))
+}
+
 // TODO easy testing here...
 pub fn resize_cover(cover: image::DynamicImage) -> image::DynamicImage {
     // see doc https://docs.rs/image/0.24.5/image/imageops/enum.FilterType.html
@@ -951,6 +1814,15 @@ mod tests {
         File::create(library_path.join("H.P. Lovecraft/Le Cauchemar d'Innsmouth (310)/cover.jpg"))?;
         File::create(library_path.join("H.P. Lovecraft/Le Cauchemar d'Innsmouth (310)/Le Cauchemar d'Innsmouth - Howard Phillips Lovecraft.epub"))?;
         fs::create_dir(library_path.join("Dragonlance"))?;
+        fs::create_dir(library_path.join("Blake et Mortimer"))?;
+        File::create(library_path.join("Blake et Mortimer/T01 - Le Secret de l'Espadon.cb7"))?;
+        File::create(
+            library_path.join("Blake et Mortimer/T02 - Le Mystère de la Grande Pyramide.CBT"),
+        )?;
+        fs::create_dir(library_path.join("Jules Verne"))?;
+        File::create(library_path.join("Jules Verne/Vingt mille lieues sous les mers.mobi"))?;
+        File::create(library_path.join("Jules Verne/Michel Strogoff.azw3"))?;
+        File::create(library_path.join("Jules Verne/Le Tour du monde en 80 jours.fb2.zip"))?;
         Ok(())
     }
 
@@ -972,10 +1844,172 @@ mod tests {
         insta::assert_yaml_snapshot!(validation_file, {
             ".added_date" => "[added_date]"
         });
+        // extension is case insensitive
+        let cb7_file = extract_file_infos(
+            "library",
+            &library_path.join("Blake et Mortimer/T01 - Le Secret de l'Espadon.cb7"),
+        );
+        assert_eq!(cb7_file.format, Format::Cb7);
+        let cbt_file = extract_file_infos(
+            "library",
+            &library_path.join("Blake et Mortimer/T02 - Le Mystère de la Grande Pyramide.CBT"),
+        );
+        assert_eq!(cbt_file.format, Format::Cbt);
+        for (name, format) in [
+            ("Vingt mille lieues sous les mers.mobi", Format::Mobi),
+            ("Michel Strogoff.azw3", Format::Azw3),
+            ("Le Tour du monde en 80 jours.fb2.zip", Format::Fb2),
+            ("Notes de lecture.md", Format::Md),
+            // loose images
+            ("Carte du voyage.PNG", Format::Jpg),
+            ("Affiche.webp", Format::Jpg),
+            ("Michel Strogoff.m4b", Format::M4b),
+            ("Cinq semaines en ballon.MP3", Format::Mp3),
+        ] {
+            let file = extract_file_infos("library", &library_path.join("Jules Verne").join(name));
+            assert_eq!(file.format, format);
+        }
         // delete library
         delete_fake_library(library_path).unwrap_or(());
     }
 
+    #[test]
+    fn test_series_from_filename() {
+        let series = |name: &str| series_from_filename(name, "/library/Asterix");
+        assert_eq!(
+            series("One Piece v03.cbz"),
+            Some((String::from("One Piece"), 3.0))
+        );
+        assert_eq!(
+            series("Dragonlance 02 - Dragons of Winter Night.epub"),
+            Some((String::from("Dragonlance"), 2.0))
+        );
+        assert_eq!(
+            series("T01 - Asterix le Gaulois.pdf"),
+            Some((String::from("Asterix"), 1.0))
+        );
+        assert_eq!(series("Vol 12.cbz"), Some((String::from("Asterix"), 12.0)));
+        assert_eq!(
+            series("Batman_(2016)_Vol._4.5_[digital].cbr"),
+            Some((String::from("Batman"), 4.5))
+        );
+        assert_eq!(
+            series("2001 Nights 03.cbz"),
+            Some((String::from("2001 Nights"), 3.0))
+        );
+        assert_eq!(series("Le Cauchemar d'Innsmouth.epub"), None);
+        assert_eq!(series("Neuromancer (1984).epub"), None);
+        assert_eq!(series("MP3 player manual.pdf"), None);
+    }
+
+    #[test]
+    fn test_natural_cmp() {
+        let mut names = vec![
+            "p10.jpg", "Zorro", "p2.jpg", "Issue 10", "élan", "issue 2", "Elan", "p1.jpg",
+            "Issue 02",
+        ];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            names,
+            vec![
+                "Elan", "élan", "Issue 02", "issue 2", "Issue 10", "p1.jpg", "p2.jpg", "p10.jpg",
+                "Zorro"
+            ]
+        );
+        assert_eq!(initial("élan"), 'E');
+        assert_eq!(initial("Zorro"), 'Z');
+        assert_eq!(initial("300"), '#');
+        assert_eq!(initial(""), '#');
+    }
+
+    #[test]
+    fn test_is_image_entry() {
+        assert_eq!(is_image_entry("001.jpg"), Some(true));
+        assert_eq!(is_image_entry("Chapter 1/002.WEBP"), Some(true));
+        assert_eq!(is_image_entry("./002.png"), Some(true));
+        assert_eq!(is_image_entry("pages/003.gif"), Some(true));
+        assert_eq!(is_image_entry("pages/004.bmp"), Some(true));
+        assert_eq!(is_image_entry("__MACOSX/._001.jpg"), Some(false));
+        assert_eq!(is_image_entry("Chapter 1/.cover.png"), Some(false));
+        assert_eq!(is_image_entry("Thumbs.db"), Some(false));
+        assert_eq!(is_image_entry("ComicInfo.xml"), Some(false));
+        assert_eq!(is_image_entry("Chapter.1/"), Some(false));
+        assert_eq!(is_image_entry("Chapter.1/005"), None);
+        assert!(is_image_data(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"));
+        assert!(is_image_data(b"RIFF\0\0\0\0WEBPVP8 "));
+        assert!(!is_image_data(b"<?xml version=\"1.0\"?>"));
+        assert!(is_avif_entry("Chapter 1/003.AVIF"));
+        assert!(!is_avif_entry("__MACOSX/._003.avif"));
+        assert!(!is_avif_entry("003.jpg"));
+    }
+
+    #[test]
+    fn test_detect_name_encoding() {
+        let encode = |encoding: &'static encoding_rs::Encoding, names: &[&str]| -> Vec<Vec<u8>> {
+            names
+                .iter()
+                .map(|name| encoding.encode(name).0.into_owned())
+                .collect()
+        };
+        let shift_jis = encode(
+            encoding_rs::SHIFT_JIS,
+            &["ワンピース 第1巻/", "ワンピース 第1巻/001.jpg"],
+        );
+        assert_eq!(detect_name_encoding(&shift_jis), NameEncoding::ShiftJis);
+        let gbk = encode(encoding_rs::GBK, &["海贼王 第1卷/", "海贼王 第1卷/001.jpg"]);
+        assert_eq!(detect_name_encoding(&gbk), NameEncoding::Gbk);
+        let cp1252 = encode(encoding_rs::WINDOWS_1252, &["Astérix et Obélix/001.jpg"]);
+        assert_eq!(detect_name_encoding(&cp1252), NameEncoding::Cp1252);
+        let cp437 = vec![b"Ast\x82rix le Gaulois/001.jpg".to_vec()];
+        assert_eq!(detect_name_encoding(&cp437), NameEncoding::Cp437);
+        assert_eq!(
+            NameEncoding::Cp437.decode(&cp437[0]).unwrap(),
+            "Astérix le Gaulois/001.jpg"
+        );
+        assert_eq!(NameEncoding::ShiftJis.decode(b"\x82\xa0\x82"), None);
+    }
+
+    #[test]
+    fn test_folder_image_list() {
+        let library_path = Path::new("library_images_folder");
+        fs::create_dir_all(library_path.join("Chapter 1")).unwrap();
+        for name in ["p10.jpg", "p2.PNG", "p1.webp", ".DS_Store"] {
+            File::create(library_path.join("Chapter 1").join(name)).unwrap();
+        }
+        assert_eq!(
+            folder_image_list(&library_path.join("Chapter 1")),
+            vec!["p1.webp", "p2.PNG", "p10.jpg"]
+        );
+        // anything else than images : a simple directory
+        File::create(library_path.join("Chapter 1/notes.txt")).unwrap();
+        assert!(folder_image_list(&library_path.join("Chapter 1")).is_empty());
+        assert!(folder_image_list(library_path).is_empty());
+        delete_fake_library(library_path).unwrap_or(());
+    }
+
+    #[test]
+    fn test_hidden_formats() {
+        let hidden = HiddenFormats::from(String::from("txt,other,unknown"));
+        assert_eq!(hidden, HiddenFormats(vec![Format::Txt, Format::Other]));
+        assert_eq!(hidden.to_string(), "txt,other");
+        assert!(HiddenFormats::from(String::new()).is_empty());
+        let mut hidden = hidden;
+        hidden.extend(&[Format::Jpg, Format::Txt]);
+        assert_eq!(hidden.to_string(), "txt,other,jpg");
+    }
+
+    #[test]
+    fn test_xml_tag_value() {
+        let comic_info = "<ComicInfo><Series>Tom &amp; Jerry</Series><Number>3</Number><Title></Title></ComicInfo>";
+        assert_eq!(
+            xml_tag_value(comic_info, "Series"),
+            Some(String::from("Tom & Jerry"))
+        );
+        assert_eq!(xml_tag_value(comic_info, "Number"), Some(String::from("3")));
+        assert_eq!(xml_tag_value(comic_info, "Title"), None);
+        assert_eq!(xml_tag_value(comic_info, "Writer"), None);
+    }
+
     // #[tokio::test]
     // async fn test_insert_new_file() {
     //     // init database
@@ -992,8 +2026,6 @@ mod tests {
     //         format: "pdf".to_string(),
     //         size: 10,
     //         total_pages: 0,
-    //         read_by: "".to_string(),
-    //         bookmarked_by: "".to_string(),
     //     };
     //     match sqlite::create_sqlite_pool().await {
     //         Ok(conn) => {
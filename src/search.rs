@@ -0,0 +1,339 @@
+use crate::scanner::HiddenFormats;
+use serde::Serialize;
+use time::{Date, Duration, Month, OffsetDateTime};
+
+/// comparison operator of numeric filters : `pages:>200`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Comparison {
+    Lower,
+    LowerOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+impl Comparison {
+    pub fn as_sql(&self) -> &'static str {
+        match &self {
+            Comparison::Lower => "<",
+            Comparison::LowerOrEqual => "<=",
+            Comparison::Equal => "=",
+            Comparison::GreaterOrEqual => ">=",
+            Comparison::Greater => ">",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum ReadingStatus {
+    Read,
+    Unread,
+    Reading,
+}
+impl ReadingStatus {
+    pub fn as_str(&self) -> &str {
+        match &self {
+            ReadingStatus::Read => "read",
+            ReadingStatus::Unread => "unread",
+            ReadingStatus::Reading => "reading",
+        }
+    }
+}
+
+/// smart collection : a saved search query of a user
+#[derive(Debug, Default, Clone, sqlx::FromRow, PartialEq, Eq, Serialize)]
+pub struct Collection {
+    pub id: i64,
+    pub user_id: i64,
+    pub name: String,
+    pub query: String,
+}
+
+/// search query parsed : free text for full text search, and filters on files
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct SearchFilters {
+    pub text: String,
+    pub format: Option<String>,
+    pub library: Option<String>,
+    pub status: Option<ReadingStatus>,
+    pub bookmarked: Option<bool>,
+    pub author: Option<String>,
+    // unix timestamps
+    pub added: Vec<(Comparison, i64)>,
+    pub pages: Vec<(Comparison, i64)>,
+    // bytes
+    pub size: Vec<(Comparison, i64)>,
+    // from user preferences, not from the query
+    #[serde(skip)]
+    pub hidden_formats: HiddenFormats,
+}
+impl SearchFilters {
+    /// directories have none of these fields
+    pub fn has_file_filters(&self) -> bool {
+        self.format.is_some()
+            || self.library.is_some()
+            || self.status.is_some()
+            || self.bookmarked.is_some()
+            || self.author.is_some()
+            || !self.added.is_empty()
+            || !self.pages.is_empty()
+            || !self.size.is_empty()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.text.trim().is_empty() && !self.has_file_filters()
+    }
+}
+
+/// split on whitespaces, except between quotes
+fn split_query(query: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = Vec::new();
+    let mut token = String::new();
+    let mut quoted = false;
+    for character in query.chars() {
+        if character == '"' {
+            quoted = !quoted;
+            token.push(character);
+        } else if character.is_whitespace() && !quoted {
+            if !token.is_empty() {
+                tokens.push(std::mem::take(&mut token));
+            }
+        } else {
+            token.push(character);
+        }
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+    tokens
+}
+
+/// `>200` to (Greater, "200")
+fn split_comparison(value: &str) -> (Comparison, &str) {
+    for (prefix, comparison) in [
+        ("<=", Comparison::LowerOrEqual),
+        (">=", Comparison::GreaterOrEqual),
+        ("<", Comparison::Lower),
+        (">", Comparison::Greater),
+        ("=", Comparison::Equal),
+    ] {
+        if let Some(number) = value.strip_prefix(prefix) {
+            return (comparison, number);
+        }
+    }
+    (Comparison::Equal, value)
+}
+
+/// `50MB` to bytes, units are powers of 1024 like in file infos
+fn parse_size(value: &str) -> Option<i64> {
+    let value = value.to_lowercase();
+    let value = value.trim_end_matches('b');
+    let (number, multiplier) = match value.chars().last()? {
+        'k' => (&value[..value.len() - 1], 1024),
+        'm' => (&value[..value.len() - 1], 1024 * 1024),
+        'g' => (&value[..value.len() - 1], 1024 * 1024 * 1024),
+        _ => (value, 1),
+    };
+    let number: f64 = number.parse().ok()?;
+    Some((number * multiplier as f64) as i64)
+}
+
+/// `2025-01-01` to the unix timestamp of its midnight (UTC), or relative to today : `30d`, `2w`,
+/// `6m` or `1y` ago, so saved searches stay up to date
+fn parse_date(value: &str) -> Option<i64> {
+    let days_per_unit = match value.chars().last()? {
+        'd' => Some(1),
+        'w' => Some(7),
+        'm' => Some(30),
+        'y' => Some(365),
+        _ => None,
+    };
+    if let Some(days_per_unit) = days_per_unit {
+        let count: i64 = value[..value.len() - 1].parse().ok()?;
+        // out of range dates are invalid filters
+        let seconds = count
+            .checked_mul(days_per_unit)?
+            .checked_mul(24 * 60 * 60)?;
+        let date = OffsetDateTime::now_utc()
+            .date()
+            .checked_sub(Duration::seconds(seconds))?;
+        return Some(date.midnight().assume_utc().unix_timestamp());
+    }
+    let mut date = value.splitn(3, '-');
+    let year: i32 = date.next()?.parse().ok()?;
+    let month: u8 = date.next()?.parse().ok()?;
+    let day: u8 = date.next()?.parse().ok()?;
+    let date = Date::from_calendar_date(year, Month::try_from(month).ok()?, day).ok()?;
+    Some(date.midnight().assume_utc().unix_timestamp())
+}
+
+/// a date is a whole day : `>2025-01-01` starts on january 2
+fn date_conditions(comparison: Comparison, day_start: i64) -> Vec<(Comparison, i64)> {
+    let day_end = day_start + 24 * 60 * 60;
+    match comparison {
+        Comparison::Lower => vec![(Comparison::Lower, day_start)],
+        Comparison::LowerOrEqual => vec![(Comparison::Lower, day_end)],
+        Comparison::Equal => vec![
+            (Comparison::GreaterOrEqual, day_start),
+            (Comparison::Lower, day_end),
+        ],
+        Comparison::GreaterOrEqual => vec![(Comparison::GreaterOrEqual, day_start)],
+        Comparison::Greater => vec![(Comparison::GreaterOrEqual, day_end)],
+    }
+}
+
+/// replace filters of a query (dropdowns values), an empty value removes the filter
+pub fn merge_query(query: &str, overrides: &[(&str, Option<String>)]) -> String {
+    let overridden_keys: Vec<&str> = overrides
+        .iter()
+        .filter(|(_, value)| value.is_some())
+        .map(|(key, _)| *key)
+        .collect();
+    let mut tokens: Vec<String> = split_query(query)
+        .into_iter()
+        .filter(|token| match token.split_once(':') {
+            Some((key, _)) => !overridden_keys.contains(&key.to_lowercase().as_str()),
+            None => true,
+        })
+        .collect();
+    for (key, value) in overrides {
+        match value.as_deref() {
+            Some(value) if value.contains(' ') => tokens.push(format!("{key}:\"{value}\"")),
+            Some(value) if !value.is_empty() => tokens.push(format!("{key}:{value}")),
+            _ => (),
+        }
+    }
+    tokens.join(" ")
+}
+
+/// parse `key:value` filters, anything unknown or invalid stays in the free text
+pub fn parse_query(query: &str) -> SearchFilters {
+    let mut filters = SearchFilters::default();
+    let mut text: Vec<String> = Vec::new();
+    for token in split_query(query) {
+        let Some((key, value)) = token.split_once(':') else {
+            text.push(token);
+            continue;
+        };
+        let value = value.trim_matches('"');
+        let parsed = match key.to_lowercase().as_str() {
+            "format" if !value.is_empty() => {
+                filters.format = Some(value.to_lowercase());
+                true
+            }
+            "library" if !value.is_empty() => {
+                filters.library = Some(value.to_string());
+                true
+            }
+            "author" if !value.is_empty() => {
+                filters.author = Some(value.to_string());
+                true
+            }
+            "status" => {
+                filters.status = match value.to_lowercase().as_str() {
+                    "read" => Some(ReadingStatus::Read),
+                    "unread" => Some(ReadingStatus::Unread),
+                    "reading" => Some(ReadingStatus::Reading),
+                    _ => None,
+                };
+                filters.status.is_some()
+            }
+            "bookmarked" => {
+                filters.bookmarked = match value.to_lowercase().as_str() {
+                    "yes" | "true" => Some(true),
+                    "no" | "false" => Some(false),
+                    _ => None,
+                };
+                filters.bookmarked.is_some()
+            }
+            "added" => {
+                let (comparison, date) = split_comparison(value);
+                match parse_date(date) {
+                    Some(day_start) => {
+                        filters
+                            .added
+                            .append(&mut date_conditions(comparison, day_start));
+                        true
+                    }
+                    None => false,
+                }
+            }
+            "pages" => {
+                let (comparison, pages) = split_comparison(value);
+                match pages.parse::<i64>() {
+                    Ok(pages) => {
+                        filters.pages.push((comparison, pages));
+                        true
+                    }
+                    Err(_) => false,
+                }
+            }
+            "size" => {
+                let (comparison, size) = split_comparison(value);
+                match parse_size(size) {
+                    Some(size) => {
+                        filters.size.push((comparison, size));
+                        true
+                    }
+                    None => false,
+                }
+            }
+            _ => false,
+        };
+        if !parsed {
+            text.push(token);
+        }
+    }
+    filters.text = text.join(" ");
+    filters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_query() {
+        insta::assert_yaml_snapshot!(parse_query(
+            r#"dragon "lance saga" format:CBZ library:Manga status:unread bookmarked:yes author:"Margaret Weis""#
+        ));
+        insta::assert_yaml_snapshot!(parse_query(
+            "added:>2025-01-01 pages:>=200 size:<50MB size:>1.5k"
+        ));
+        // invalid filters are searched as text
+        insta::assert_yaml_snapshot!(parse_query(
+            "Re:Zero status:maybe added:yesterday pages:many"
+        ));
+        // relative dates
+        let filters = parse_query("added:>30d");
+        let thirty_days_ago = (OffsetDateTime::now_utc().date() - Duration::days(29))
+            .midnight()
+            .assume_utc()
+            .unix_timestamp();
+        assert_eq!(
+            filters.added,
+            vec![(Comparison::GreaterOrEqual, thirty_days_ago)]
+        );
+        // out of range
+        assert_eq!(parse_date("9999999d"), None);
+        assert_eq!(parse_date("-9999999y"), None);
+        assert_eq!(parse_date(&format!("{}w", i64::MAX)), None);
+        let filters = parse_query("added:>9999999d");
+        assert!(filters.added.is_empty());
+        assert_eq!(filters.text, "added:>9999999d");
+    }
+    #[test]
+    fn test_merge_query() {
+        let overrides = [
+            ("format", Some(String::from("epub"))),
+            ("library", Some(String::from("My Manga"))),
+            ("status", Some(String::new())),
+            ("bookmarked", None),
+        ];
+        assert_eq!(
+            merge_query(
+                r#"dragon "lance saga" format:cbz status:unread bookmarked:yes"#,
+                &overrides
+            ),
+            r#"dragon "lance saga" bookmarked:yes format:epub library:"My Manga""#
+        );
+    }
+}
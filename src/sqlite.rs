@@ -1,14 +1,24 @@
-use crate::http_server::User;
-use crate::scanner::{DirectoryInfo, FileInfo, Library};
+use crate::audio::Chapter;
+use crate::http_server::{Flag, Preferences, SortOrder, Typography, User};
+use crate::scanner::{
+    DirectoryInfo, FileInfo, FileMetadata, Format, HiddenFormats, Library, ProblemFile, Series,
+};
+use crate::search::{Collection, ReadingStatus, SearchFilters};
+use crate::text::TextPagination;
 
+use serde::Serialize;
+use sqlx::migrate::{Migration, Migrator};
 use sqlx::sqlite::SqlitePoolOptions;
 use sqlx::{migrate::MigrateDatabase, Sqlite, SqlitePool};
-use sqlx::{pool::Pool, Row};
+use sqlx::{pool::Pool, FromRow, QueryBuilder, Row};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::Path;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use ulid::Ulid;
 
+static MIGRATOR: Migrator = sqlx::migrate!();
+
 pub async fn create_sqlite_pool() -> Result<Pool<Sqlite>, String> {
     match SqlitePoolOptions::new()
         .max_lifetime(Duration::from_secs(30))
@@ -60,77 +70,90 @@ pub async fn init_database() -> Result<(), String> {
     } else {
         info!("database exists");
     }
-    // tables
-    // TODO check if already created ?
-    // TODO use after connect ?
-    // https://docs.rs/sqlx/0.6.2/sqlx/pool/struct.PoolOptions.html#method.after_connect
-    // ou `create_if_missing`
-    // https://github.com/launchbadge/sqlx/issues/1114
     let conn = match SqlitePool::connect(crate::DB_URL).await {
         Ok(conn) => Ok(conn),
         Err(e) => Err(format!("unable to connect to database : {e}")),
     }?;
-    // TODO create a separated .sql file, and support migrations
-    let schema = r#"
-CREATE TABLE IF NOT EXISTS users (
-  id INTEGER PRIMARY KEY NOT NULL,
-  password_hash TEXT NOT NULL,
-  name TEXT NOT NULL UNIQUE,
-  role TEXT NOT NULL
-);
-CREATE TABLE IF NOT EXISTS directories (
-  id ULID PRIMARY KEY NOT NULL,
-  name TEXT NOT NULL,
-  parent_path TEXT NOT NULL,
-  file_count INTEGER DEFAULT NULL
-);
-CREATE TABLE IF NOT EXISTS files (
-  id ULID PRIMARY KEY NOT NULL,
-  name TEXT NOT NULL,
-  library_name TEXT NOT NULL,
-  parent_path TEXT NOT NULL,
-  scan_me BOOLEAN DEFAULT TRUE,
-  added_date INTEGER NOT NULL,
-  format TEXT DEFAULT NULL,
-  size INTEGER NOT NULL DEFAULT 0,
-  total_pages INTEGER NOT NULL DEFAULT 0,
-  read_by TEXT DEFAULT NULL,
-  bookmarked_by TEXT DEFAULT NULL
-);
-CREATE TABLE IF NOT EXISTS covers (
-  id ULID PRIMARY KEY NOT NULL,
-  cover BLOB DEFAULT NULL
-);
-CREATE TABLE IF NOT EXISTS reading (
-  id INTEGER PRIMARY KEY NOT NULL,
-  file_id ULID NOT NULL,
-  user_id INTEGER NOT NULL,
-  page INTEGER NOT NULL,
-  UNIQUE(file_id, user_id)
-);
-CREATE TABLE IF NOT EXISTS libraries (
-  id INTEGER PRIMARY KEY NOT NULL,
-  name TEXT DEFAULT NULL UNIQUE,
-  path TEXT DEFAULT NULL UNIQUE,
-  scan_lock BOOLEAN DEFAULT FALSE,
-  file_count INTEGER DEFAULT NULL,
-  last_successfull_scan_date INTEGER NOT NULL DEFAULT 0,
-  last_successfull_extract_date INTEGER NOT NULL DEFAULT 0
-);
-    "#;
-    match sqlx::query(schema).execute(&conn).await {
+    run_migrations(&conn).await?;
+    conn.close().await;
+    Ok(())
+}
+
+/// apply pending migrations from the `migrations` directory, the database is backed up first
+async fn run_migrations(conn: &Pool<Sqlite>) -> Result<(), String> {
+    // an error here means the migrations table does not exist yet
+    let applied_versions: HashSet<i64> =
+        match sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success = TRUE;")
+            .fetch_all(conn)
+            .await
+        {
+            Ok(versions) => versions.into_iter().collect(),
+            Err(_) => HashSet::with_capacity(0),
+        };
+    let pending_migrations: Vec<&Migration> = MIGRATOR
+        .iter()
+        .filter(|migration| !applied_versions.contains(&migration.version))
+        .collect();
+    if pending_migrations.is_empty() {
+        info!(
+            "database schema is up to date (version {})",
+            applied_versions.iter().max().unwrap_or(&0)
+        );
+        return Ok(());
+    }
+    // nothing to save in a brand new database
+    let existing_tables: i32 = match sqlx::query(
+        "SELECT count(*) AS found FROM sqlite_master WHERE type = 'table' AND name = 'files';",
+    )
+    .fetch_one(conn)
+    .await
+    {
+        Ok(row) => row.get("found"),
+        Err(e) => return Err(format!("unable to check database tables : {e}")),
+    };
+    if existing_tables > 0 {
+        backup_database(conn).await?;
+    }
+    match MIGRATOR.run(conn).await {
         Ok(_) => {
-            info!("tables successfully created");
+            for migration in pending_migrations {
+                info!(
+                    "migration {} ({}) applied",
+                    migration.version, migration.description
+                );
+            }
             Ok(())
         }
         Err(e) => {
-            let msg = format!("failed to create tables : {e}");
+            let msg = format!("failed to apply migrations : {e}");
             error!("{msg}");
             Err(msg)
         }
-    }?;
-    conn.close().await;
-    Ok(())
+    }
+}
+
+/// copy the database next to itself : `eloran.db.<timestamp>.bak`
+async fn backup_database(conn: &Pool<Sqlite>) -> Result<(), String> {
+    let backup_path = format!(
+        "{}.{}.bak",
+        crate::DB_URL.trim_start_matches("sqlite://"),
+        now_timestamp()
+    );
+    match sqlx::query("VACUUM INTO ?;")
+        .bind(&backup_path)
+        .execute(conn)
+        .await
+    {
+        Ok(_) => {
+            info!("database saved to [{backup_path}] before migration");
+            Ok(())
+        }
+        Err(e) => {
+            let msg = format!("failed to backup database to [{backup_path}] : {e}");
+            error!("{msg}");
+            Err(msg)
+        }
+    }
 }
 
 // TODO delete this when install page will be done
@@ -176,10 +199,26 @@ pub async fn update_user(user: &User, conn: &Pool<Sqlite>) {
     }
 }
 pub async fn delete_user(user: &User, conn: &Pool<Sqlite>) {
-    match sqlx::query("DELETE FROM users WHERE id = ?;")
-        .bind(user.id)
-        .execute(conn)
-        .await
+    match sqlx::query(
+        "DELETE FROM users WHERE id = ?;
+         DELETE FROM user_file_flags WHERE user_id = ?;
+         DELETE FROM user_directory_flags WHERE user_id = ?;
+         DELETE FROM reading WHERE user_id = ?;
+         DELETE FROM collections WHERE user_id = ?;
+         DELETE FROM reading_list_items WHERE list_id IN (SELECT id FROM reading_lists WHERE user_id = ?);
+         DELETE FROM reading_lists WHERE user_id = ?;
+         DELETE FROM user_preferences WHERE user_id = ?;",
+    )
+    .bind(user.id)
+    .bind(user.id)
+    .bind(user.id)
+    .bind(user.id)
+    .bind(user.id)
+    .bind(user.id)
+    .bind(user.id)
+    .bind(user.id)
+    .execute(conn)
+    .await
     {
         Ok(_) => info!("user {} successfully deleted", &user.name),
         Err(e) => error!("failed to delete user {} : {}", &user.name, e),
@@ -202,6 +241,7 @@ pub async fn create_library_path(library_path: Vec<String>) {
             last_successfull_scan_date: 0,
             last_successfull_extract_date: 0,
             file_count: 0,
+            hidden_formats: HiddenFormats::default(),
         };
         debug!("set library path : {path}");
         let conn = SqlitePool::connect(crate::DB_URL).await.unwrap();
@@ -242,11 +282,13 @@ pub async fn update_library_file_count(library: &Library, conn: &Pool<Sqlite>) {
         // count files (not dirs) where library path is present :
         //   SELECT count(id) FROM files WHERE instr(parent_path, 'some_path') > 0;
         // see instr : https://www.sqlite.org/lang_corefunc.html#instr
-        "UPDATE libraries SET file_count = (
-        SELECT count(id) FROM files WHERE instr(parent_path, (
-            SELECT path FROM libraries WHERE id = ?
-            )) > 0
-        ) WHERE id = ?;",
+        &format!(
+            "UPDATE libraries SET file_count = (
+            SELECT count(id) FROM files WHERE instr(parent_path, (
+                SELECT path FROM libraries WHERE id = ?
+                )) > 0 AND NOT {HIDDEN_BY_LIBRARY}
+            ) WHERE id = ?;"
+        ),
     )
     .bind(library.id)
     .bind(library.id)
@@ -261,12 +303,45 @@ pub async fn update_library_file_count(library: &Library, conn: &Pool<Sqlite>) {
     };
 }
 
+/// formats hidden from everyone in a library
+pub async fn set_library_hidden_formats(
+    library: &Library,
+    hidden_formats: &HiddenFormats,
+    conn: &Pool<Sqlite>,
+) {
+    match sqlx::query("UPDATE libraries SET hidden_formats = ? WHERE id = ?;")
+        .bind(hidden_formats.to_string())
+        .bind(library.id)
+        .execute(conn)
+        .await
+    {
+        Ok(_) => info!(
+            "hidden formats of library [{}] set to [{hidden_formats}]",
+            library.name
+        ),
+        Err(e) => error!(
+            "unable to set hidden formats of library [{}] : {e}",
+            library.name
+        ),
+    }
+}
+
 /// delete files of a library name
 pub async fn delete_files_from_library(library_list: &Vec<Library>, conn: &Pool<Sqlite>) {
     for library in library_list {
         debug!("delete files from library {}", library.name);
-        match sqlx::query("DELETE FROM files WHERE library_name = ?;")
-            .bind(library.name.clone())
+        match sqlx::query(
+            "DELETE FROM user_file_flags WHERE file_id IN (SELECT id FROM files WHERE library_name = ?);
+             DELETE FROM reading_list_items WHERE file_id IN (SELECT id FROM files WHERE library_name = ?);
+             DELETE FROM problem_files WHERE file_id IN (SELECT id FROM files WHERE library_name = ?);
+             DELETE FROM audio_chapters WHERE file_id IN (SELECT id FROM files WHERE library_name = ?);
+             DELETE FROM files WHERE library_name = ?;",
+        )
+        .bind(&library.name)
+        .bind(&library.name)
+        .bind(&library.name)
+        .bind(&library.name)
+        .bind(&library.name)
             .execute(conn)
             .await
         {
@@ -388,6 +463,146 @@ pub async fn get_reading_files_from_user_id(user_id: &i64, conn: &Pool<Sqlite>)
     file
 }
 
+/// last read date and current page of files being read or read by a user
+pub async fn get_reading_state(
+    user_id: i64,
+    conn: &Pool<Sqlite>,
+) -> HashMap<String, (i64, Option<i32>)> {
+    match sqlx::query_as::<_, (String, i64, Option<i32>)>(
+        "SELECT file_id, max(last_read), max(page) FROM (
+            SELECT file_id, last_read, page FROM reading WHERE user_id = ?
+            UNION ALL
+            SELECT file_id, flag_date AS last_read, NULL AS page FROM user_file_flags
+            WHERE user_id = ? AND flag = ?
+        ) GROUP BY file_id;",
+    )
+    .bind(user_id)
+    .bind(user_id)
+    .bind(Flag::ReadStatus)
+    .fetch_all(conn)
+    .await
+    {
+        Ok(rows) => rows
+            .into_iter()
+            .map(|(file_id, last_read, page)| (file_id, (last_read, page)))
+            .collect(),
+        Err(e) => {
+            error!("unable to retrieve reading state of user {user_id} : {e}");
+            HashMap::with_capacity(0)
+        }
+    }
+}
+
+/// files being read by a user, most recently read first, with their current page
+pub async fn get_continue_reading(
+    user_id: i64,
+    limit: i64,
+    conn: &Pool<Sqlite>,
+) -> Vec<(FileInfo, i32)> {
+    match sqlx::query(
+        "SELECT files.*, reading.page AS current_page FROM reading
+        INNER JOIN files ON files.id = reading.file_id
+        WHERE reading.user_id = ? ORDER BY reading.last_read DESC LIMIT ?;",
+    )
+    .bind(user_id)
+    .bind(limit)
+    .fetch_all(conn)
+    .await
+    {
+        Ok(rows) => rows
+            .iter()
+            .filter_map(|row| Some((FileInfo::from_row(row).ok()?, row.get("current_page"))))
+            .collect(),
+        Err(e) => {
+            error!("unable to retrieve reading files of user {user_id} : {e}");
+            Vec::with_capacity(0)
+        }
+    }
+}
+
+/// series and directories of the files recently read by a user, most recent first
+pub async fn get_recently_read_groups(
+    user_id: i64,
+    limit: i64,
+    conn: &Pool<Sqlite>,
+) -> Vec<(Option<i64>, String)> {
+    match sqlx::query_as(
+        "SELECT files.series_id, files.parent_path FROM user_file_flags
+        INNER JOIN files ON files.id = user_file_flags.file_id
+        WHERE user_file_flags.user_id = ? AND user_file_flags.flag = ?
+        GROUP BY files.series_id, files.parent_path
+        ORDER BY max(user_file_flags.flag_date) DESC LIMIT ?;",
+    )
+    .bind(user_id)
+    .bind(Flag::ReadStatus)
+    .bind(limit)
+    .fetch_all(conn)
+    .await
+    {
+        Ok(groups) => groups,
+        Err(e) => {
+            error!("unable to retrieve recently read files of user {user_id} : {e}");
+            Vec::with_capacity(0)
+        }
+    }
+}
+
+/// last files added to libraries, of a library or a format
+pub async fn get_recently_added(
+    library_name: Option<&str>,
+    format: Option<&str>,
+    hidden_formats: &HiddenFormats,
+    limit: i64,
+    conn: &Pool<Sqlite>,
+) -> Vec<FileInfo> {
+    let mut query: QueryBuilder<Sqlite> = QueryBuilder::new("SELECT * FROM files WHERE 1 = 1");
+    push_visible_formats(&mut query, hidden_formats);
+    if let Some(library_name) = library_name {
+        query.push(" AND library_name = ").push_bind(library_name);
+    }
+    if let Some(format) = format {
+        query.push(" AND format = ").push_bind(format);
+    }
+    query
+        .push(" ORDER BY added_date DESC, name LIMIT ")
+        .push_bind(limit);
+    match query.build_query_as().fetch_all(conn).await {
+        Ok(files) => files,
+        Err(e) => {
+            error!("unable to retrieve recently added files : {e}");
+            Vec::with_capacity(0)
+        }
+    }
+}
+
+/// random supported files, neither read nor being read by a user
+pub async fn get_random_unread(
+    user_id: i64,
+    hidden_formats: &HiddenFormats,
+    limit: i64,
+    conn: &Pool<Sqlite>,
+) -> Vec<FileInfo> {
+    let mut query: QueryBuilder<Sqlite> =
+        QueryBuilder::new("SELECT * FROM files WHERE format != 'other'");
+    push_visible_formats(&mut query, hidden_formats);
+    query
+        .push(" AND id NOT IN (SELECT file_id FROM user_file_flags WHERE user_id = ")
+        .push_bind(user_id)
+        .push(" AND flag = ")
+        .push_bind(Flag::ReadStatus)
+        .push(") AND id NOT IN (SELECT file_id FROM reading WHERE user_id = ")
+        .push_bind(user_id)
+        .push(") ORDER BY random() LIMIT ")
+        .push_bind(limit);
+    match query.build_query_as().fetch_all(conn).await {
+        Ok(files) => files,
+        Err(e) => {
+            error!("unable to retrieve unread files of user {user_id} : {e}");
+            Vec::with_capacity(0)
+        }
+    }
+}
+
 /// get currentPage from file id (can be usefull for sync)
 pub async fn get_current_page_from_file_id(
     user_id: i64,
@@ -428,12 +643,15 @@ pub async fn set_current_page_for_file_id(
     page: &i32,
     conn: &Pool<Sqlite>,
 ) {
-    match sqlx::query("INSERT OR REPLACE INTO reading(file_id,user_id,page) VALUES (?, ?, ?);")
-        .bind(file_id)
-        .bind(user_id)
-        .bind(page)
-        .execute(conn)
-        .await
+    match sqlx::query(
+        "INSERT OR REPLACE INTO reading(file_id,user_id,page,last_read) VALUES (?, ?, ?, ?);",
+    )
+    .bind(file_id)
+    .bind(user_id)
+    .bind(page)
+    .bind(now_timestamp())
+    .execute(conn)
+    .await
     {
         Ok(_) => debug!(
             "current_page successfully setted to {} for id {}",
@@ -483,6 +701,151 @@ pub async fn insert_total_pages(file: &FileInfo, total_pages: i32, conn: &Pool<S
     };
 }
 
+/// insert metadata (title, author...) for a file, they are indexed for search
+pub async fn insert_metadata(file: &FileInfo, metadata: &FileMetadata, conn: &Pool<Sqlite>) {
+    match sqlx::query(
+        "UPDATE files SET title = ?, author = ?, series = ?, description = ? WHERE id = ?;",
+    )
+    .bind(&metadata.title)
+    .bind(&metadata.author)
+    .bind(&metadata.series)
+    .bind(&metadata.description)
+    .bind(&file.id)
+    .execute(conn)
+    .await
+    {
+        Ok(_) => debug!(
+            "metadata updated for file {}/{}",
+            file.parent_path, file.name
+        ),
+        Err(e) => error!(
+            "failed to update metadata for file {}/{} : {e}",
+            file.parent_path, file.name
+        ),
+    };
+}
+
+/// link a file to its series (created if needed), or unlink it
+pub async fn insert_series(
+    file: &FileInfo,
+    series: Option<(String, Option<f64>)>,
+    conn: &Pool<Sqlite>,
+) {
+    let result = match &series {
+        Some((name, volume)) => {
+            sqlx::query(
+                "INSERT OR IGNORE INTO series(name) VALUES (?);
+                 UPDATE files SET series = ?, volume = ?, series_id = (
+                    SELECT id FROM series WHERE name = ?
+                 ) WHERE id = ?;",
+            )
+            .bind(name)
+            .bind(name)
+            .bind(volume)
+            .bind(name)
+            .bind(&file.id)
+            .execute(conn)
+            .await
+        }
+        None => {
+            sqlx::query("UPDATE files SET series_id = NULL, volume = NULL WHERE id = ?;")
+                .bind(&file.id)
+                .execute(conn)
+                .await
+        }
+    };
+    match result {
+        Ok(_) => debug!(
+            "series {series:?} set for file {}/{}",
+            file.parent_path, file.name
+        ),
+        Err(e) => error!(
+            "failed to set series for file {}/{} : {e}",
+            file.parent_path, file.name
+        ),
+    };
+}
+
+const SERIES_SELECT: &str = "SELECT series.id, series.name, count(files.id) AS file_count,
+    count(user_file_flags.file_id) AS read_count
+    FROM series JOIN files ON files.series_id = series.id
+    LEFT JOIN user_file_flags ON user_file_flags.file_id = files.id
+        AND user_file_flags.user_id = ? AND user_file_flags.flag = ?";
+
+/// all series with files, and their read counts for a user
+pub async fn get_series_list(user_id: i64, conn: &Pool<Sqlite>) -> Vec<Series> {
+    match sqlx::query_as(&format!(
+        "{SERIES_SELECT} GROUP BY series.id ORDER BY series.name;"
+    ))
+    .bind(user_id)
+    .bind(Flag::ReadStatus)
+    .fetch_all(conn)
+    .await
+    {
+        Ok(series_list) => series_list,
+        Err(e) => {
+            error!("unable to retrieve series : {e}");
+            Vec::with_capacity(0)
+        }
+    }
+}
+
+pub async fn get_series(series_id: i64, user_id: i64, conn: &Pool<Sqlite>) -> Option<Series> {
+    match sqlx::query_as(&format!(
+        "{SERIES_SELECT} WHERE series.id = ? GROUP BY series.id;"
+    ))
+    .bind(user_id)
+    .bind(Flag::ReadStatus)
+    .bind(series_id)
+    .fetch_optional(conn)
+    .await
+    {
+        Ok(series) => series,
+        Err(e) => {
+            error!("unable to retrieve series {series_id} : {e}");
+            None
+        }
+    }
+}
+
+/// files of a series, by volume number
+pub async fn get_series_files(series_id: i64, conn: &Pool<Sqlite>) -> Vec<FileInfo> {
+    match sqlx::query_as(
+        "SELECT * FROM files WHERE series_id = ? ORDER BY volume IS NULL, volume, name;",
+    )
+    .bind(series_id)
+    .fetch_all(conn)
+    .await
+    {
+        Ok(files) => files,
+        Err(e) => {
+            error!("unable to retrieve files of series {series_id} : {e}");
+            Vec::with_capacity(0)
+        }
+    }
+}
+
+/// series id, name and volume number of a file
+pub async fn get_file_series(
+    file_id: &str,
+    conn: &Pool<Sqlite>,
+) -> Option<(i64, String, Option<f64>)> {
+    match sqlx::query_as(
+        "SELECT series.id, series.name, files.volume FROM files
+        JOIN series ON series.id = files.series_id WHERE files.id = ?;",
+    )
+    .bind(file_id)
+    .fetch_optional(conn)
+    .await
+    {
+        Ok(series) => series,
+        Err(e) => {
+            error!("unable to retrieve series of file {file_id} : {e}");
+            None
+        }
+    }
+}
+
 /// get cover from id, raw (Vec<u8>)
 pub async fn get_cover_from_id(file: &FileInfo, conn: &Pool<Sqlite>) -> Option<Vec<u8>> {
     match sqlx::query("SELECT cover FROM covers WHERE id = ?;")
@@ -520,187 +883,1575 @@ pub async fn set_scan_flag(file: &FileInfo, flag: i8, conn: &Pool<Sqlite>) {
     };
 }
 
-// pub async fn get_user(name: Option<&str>, id: Option<&str>, conn: &Pool<Sqlite>) -> i32 {
-pub async fn get_user(name: Option<&str>, id: Option<&str>, conn: &Pool<Sqlite>) -> Vec<User> {
-    // TODO optional WHERE ?
-    let where_clause = if name.is_some() {
-        match name {
-            Some(name) => format!("WHERE name = '{}'", name),
-            None => "".to_string(),
-        }
-    } else if id.is_some() {
-        match id {
-            Some(id) => format!("WHERE id = '{}'", id),
-            None => "".to_string(),
-        }
-    } else {
-        "".to_string()
-    };
-    // let user_id: i32 = match sqlx::query(&format!("SELECT id FROM users {};", where_clause))
-    match sqlx::query_as(&format!("SELECT * FROM users {};", where_clause))
-        .fetch_all(conn)
+/// metadata and series of a file to extract again (`series_me`)
+pub async fn set_series_flag(file: &FileInfo, flag: bool, conn: &Pool<Sqlite>) {
+    match sqlx::query("UPDATE files SET series_me = ? WHERE id = ?;")
+        .bind(flag)
+        .bind(&file.id)
+        .execute(conn)
         .await
     {
-        // Ok(id) => id.get("id"),
-        Ok(user) => user,
-        Err(e) => {
-            error!("failed to get user : {e}");
-            Vec::with_capacity(0)
-        }
-    }
+        Ok(_) => debug!(
+            "series flag updated for file {}/{}",
+            file.parent_path, file.name
+        ),
+        Err(e) => error!(
+            "failed to update series flag for file {}/{} : {e}",
+            file.parent_path, file.name
+        ),
+    };
 }
 
-pub async fn set_flag_status(
-    // TODO use flag to standardise this fn
-    flag: &str,
-    user_id: i64,
-    file_id: &str,
-    conn: &Pool<Sqlite>,
-) -> bool {
-    // prepare sql queries
-    let (flag_field, select_query, toggle_query) = if flag == "bookmark" {
-        let flag_field = "bookmarked_by";
-        let select_query = "SELECT bookmarked_by FROM files WHERE id = ?;";
-        let toggle_query = "UPDATE files SET bookmarked_by = ? WHERE id = ?;";
-        (flag_field, select_query, toggle_query)
-    } else if flag == "read_status" {
-        let flag_field = "read_by";
-        let select_query = "SELECT read_by FROM files WHERE id = ?;";
-        let toggle_query = "UPDATE files SET read_by = ? WHERE id = ?;";
-        (flag_field, select_query, toggle_query)
-    } else {
-        let flag_field = "";
-        let select_query = "";
-        let toggle_query = "";
-        (flag_field, select_query, toggle_query)
+/// record a problem found while extracting a file
+pub async fn insert_problem(file: &FileInfo, problem: &str, conn: &Pool<Sqlite>) {
+    match sqlx::query(
+        "INSERT OR REPLACE INTO problem_files(file_id, problem, date) VALUES (?, ?, ?);",
+    )
+    .bind(&file.id)
+    .bind(problem)
+    .bind(now_timestamp())
+    .execute(conn)
+    .await
+    {
+        Ok(_) => debug!(
+            "problem [{problem}] recorded for file {}/{}",
+            file.parent_path, file.name
+        ),
+        Err(e) => error!(
+            "failed to record problem for file {}/{} : {e}",
+            file.parent_path, file.name
+        ),
     };
+}
 
-    // retrieve fav_list for user
-    match sqlx::query(select_query)
-        .bind(file_id)
+/// forget the problems of a file, before extracting it again
+pub async fn delete_problems(file: &FileInfo, conn: &Pool<Sqlite>) {
+    if let Err(e) = sqlx::query("DELETE FROM problem_files WHERE file_id = ?;")
+        .bind(&file.id)
+        .execute(conn)
+        .await
+    {
+        error!(
+            "failed to delete problems of file {}/{} : {e}",
+            file.parent_path, file.name
+        );
+    }
+}
+
+/// chapters of an audiobook, replaced at each extraction
+pub async fn insert_chapters(file: &FileInfo, chapters: &[Chapter], conn: &Pool<Sqlite>) {
+    if let Err(e) = sqlx::query("DELETE FROM audio_chapters WHERE file_id = ?;")
+        .bind(&file.id)
+        .execute(conn)
+        .await
+    {
+        error!(
+            "failed to delete chapters of file {}/{} : {e}",
+            file.parent_path, file.name
+        );
+        return;
+    }
+    if chapters.is_empty() {
+        return;
+    }
+    let mut query_builder =
+        QueryBuilder::new("INSERT INTO audio_chapters(file_id, position, title, start) ");
+    query_builder.push_values(
+        chapters.iter().enumerate(),
+        |mut row, (position, chapter)| {
+            row.push_bind(&file.id)
+                .push_bind(position as i64)
+                .push_bind(&chapter.title)
+                .push_bind(chapter.start);
+        },
+    );
+    match query_builder.build().execute(conn).await {
+        Ok(_) => debug!(
+            "{} chapters inserted for file {}/{}",
+            chapters.len(),
+            file.parent_path,
+            file.name
+        ),
+        Err(e) => error!(
+            "failed to insert chapters of file {}/{} : {e}",
+            file.parent_path, file.name
+        ),
+    }
+}
+
+/// chapters of an audiobook, in order
+pub async fn get_chapters(file_id: &str, conn: &Pool<Sqlite>) -> Vec<Chapter> {
+    match sqlx::query_as(
+        "SELECT title, start FROM audio_chapters WHERE file_id = ? ORDER BY position;",
+    )
+    .bind(file_id)
+    .fetch_all(conn)
+    .await
+    {
+        Ok(chapters) => chapters,
+        Err(e) => {
+            error!("unable to retrieve chapters of file {file_id} : {e}");
+            Vec::with_capacity(0)
+        }
+    }
+}
+
+/// files with extraction problems, most recent first
+pub async fn get_problem_files(conn: &Pool<Sqlite>) -> Vec<ProblemFile> {
+    match sqlx::query_as(
+        "SELECT files.*, problem_files.problem, problem_files.date FROM problem_files
+         INNER JOIN files ON files.id = problem_files.file_id
+         ORDER BY problem_files.date DESC, files.parent_path, files.name;",
+    )
+    .fetch_all(conn)
+    .await
+    {
+        Ok(problem_files) => problem_files,
+        Err(e) => {
+            error!("unable to get problem files : {e}");
+            Vec::new()
+        }
+    }
+}
+
+/// flag files with problems to be extracted again
+pub async fn rescan_problem_files(conn: &Pool<Sqlite>) {
+    match sqlx::query(
+        "UPDATE files SET scan_me = 1 WHERE id IN (SELECT file_id FROM problem_files);",
+    )
+    .execute(conn)
+    .await
+    {
+        Ok(result) => info!(
+            "{} problem files flagged for extraction",
+            result.rows_affected()
+        ),
+        Err(e) => error!("unable to flag problem files for extraction : {e}"),
+    }
+}
+
+/// pagination of text and markdown files, default until set in admin panel
+pub async fn get_text_pagination(conn: &Pool<Sqlite>) -> TextPagination {
+    match sqlx::query_as("SELECT text_page_unit, text_page_size FROM settings WHERE id = 1;")
+        .fetch_optional(conn)
+        .await
+    {
+        Ok(pagination) => pagination.unwrap_or_default(),
+        Err(e) => {
+            error!("unable to retrieve text pagination : {e}");
+            TextPagination::default()
+        }
+    }
+}
+
+pub async fn set_text_pagination(pagination: &TextPagination, conn: &Pool<Sqlite>) {
+    match sqlx::query(
+        "INSERT INTO settings(id, text_page_unit, text_page_size) VALUES (1, ?, ?)
+        ON CONFLICT(id) DO UPDATE
+        SET text_page_unit = excluded.text_page_unit, text_page_size = excluded.text_page_size;",
+    )
+    .bind(pagination.text_page_unit)
+    .bind(pagination.text_page_size)
+    .execute(conn)
+    .await
+    {
+        Ok(_) => info!("text pagination saved"),
+        Err(e) => error!("unable to save text pagination : {e}"),
+    }
+}
+
+/// text and markdown files have to be paginated again
+pub async fn rescan_text_files(conn: &Pool<Sqlite>) {
+    match sqlx::query("UPDATE files SET scan_me = 1 WHERE format IN ('txt', 'md');")
+        .execute(conn)
+        .await
+    {
+        Ok(result) => info!(
+            "{} text files flagged for extraction",
+            result.rows_affected()
+        ),
+        Err(e) => error!("unable to flag text files for extraction : {e}"),
+    }
+}
+
+/// text files being read or read by someone
+pub async fn get_read_text_files(conn: &Pool<Sqlite>) -> Vec<FileInfo> {
+    match sqlx::query_as(
+        "SELECT * FROM files WHERE format IN ('txt', 'md')
+        AND id IN (SELECT file_id FROM reading);",
+    )
+    .fetch_all(conn)
+    .await
+    {
+        Ok(files) => files,
+        Err(e) => {
+            error!("unable to retrieve read text files : {e}");
+            Vec::with_capacity(0)
+        }
+    }
+}
+
+/// current page of each user reading a file : (user id, page)
+pub async fn get_reading_pages(file_id: &str, conn: &Pool<Sqlite>) -> Vec<(i64, i32)> {
+    match sqlx::query_as("SELECT user_id, page FROM reading WHERE file_id = ?;")
+        .bind(file_id)
+        .fetch_all(conn)
+        .await
+    {
+        Ok(pages) => pages,
+        Err(e) => {
+            error!("unable to retrieve reading pages of file {file_id} : {e}");
+            Vec::with_capacity(0)
+        }
+    }
+}
+
+/// move the current page of a user, last read date is kept
+pub async fn update_reading_page(file_id: &str, user_id: i64, page: i32, conn: &Pool<Sqlite>) {
+    match sqlx::query("UPDATE reading SET page = ? WHERE file_id = ? AND user_id = ?;")
+        .bind(page)
+        .bind(file_id)
+        .bind(user_id)
+        .execute(conn)
+        .await
+    {
+        Ok(_) => debug!("page of file {file_id} moved to {page} for user {user_id}"),
+        Err(e) => error!("unable to move page of file {file_id} for user {user_id} : {e}"),
+    }
+}
+
+/// title found in a file
+pub async fn get_file_title(file_id: &str, conn: &Pool<Sqlite>) -> Option<String> {
+    match sqlx::query_scalar("SELECT title FROM files WHERE id = ?;")
+        .bind(file_id)
+        .fetch_optional(conn)
+        .await
+    {
+        Ok(title) => title.flatten(),
+        Err(e) => {
+            error!("unable to retrieve title of file {file_id} : {e}");
+            None
+        }
+    }
+}
+
+// pub async fn get_user(name: Option<&str>, id: Option<&str>, conn: &Pool<Sqlite>) -> i32 {
+pub async fn get_user(name: Option<&str>, id: Option<&str>, conn: &Pool<Sqlite>) -> Vec<User> {
+    // TODO optional WHERE ?
+    let where_clause = if name.is_some() {
+        match name {
+            Some(name) => format!("WHERE name = '{}'", name),
+            None => "".to_string(),
+        }
+    } else if id.is_some() {
+        match id {
+            Some(id) => format!("WHERE id = '{}'", id),
+            None => "".to_string(),
+        }
+    } else {
+        "".to_string()
+    };
+    // let user_id: i32 = match sqlx::query(&format!("SELECT id FROM users {};", where_clause))
+    let mut users: Vec<User> =
+        match sqlx::query_as(&format!("SELECT * FROM users {};", where_clause))
+            .fetch_all(conn)
+            .await
+        {
+            // Ok(id) => id.get("id"),
+            Ok(user) => user,
+            Err(e) => {
+                error!("failed to get user : {e}");
+                Vec::with_capacity(0)
+            }
+        };
+    // collections are printed in menu, useless for users list
+    if !where_clause.is_empty() {
+        for user in &mut users {
+            user.collections = get_collections(user.id, conn).await;
+            user.preferences = get_preferences(user.id, conn).await;
+        }
+    }
+    users
+}
+
+/// api token of a user, if one was generated
+pub async fn get_api_token(user_id: i64, conn: &Pool<Sqlite>) -> Option<String> {
+    match sqlx::query_scalar("SELECT api_token FROM users WHERE id = ?;")
+        .bind(user_id)
+        .fetch_one(conn)
+        .await
+    {
+        Ok(api_token) => api_token,
+        Err(e) => {
+            error!("unable to retrieve api token of user {user_id} : {e}");
+            None
+        }
+    }
+}
+
+/// replace the api token of a user, the previous one is revoked
+pub async fn set_api_token(user_id: i64, api_token: &str, conn: &Pool<Sqlite>) {
+    match sqlx::query("UPDATE users SET api_token = ? WHERE id = ?;")
+        .bind(api_token)
+        .bind(user_id)
+        .execute(conn)
+        .await
+    {
+        Ok(_) => info!("new api token for user {user_id}"),
+        Err(e) => error!("unable to set api token of user {user_id} : {e}"),
+    }
+}
+
+pub async fn get_user_from_api_token(api_token: &str, conn: &Pool<Sqlite>) -> Option<User> {
+    match sqlx::query_as::<_, User>("SELECT * FROM users WHERE api_token = ?;")
+        .bind(api_token)
+        .fetch_optional(conn)
+        .await
+    {
+        Ok(Some(mut user)) => {
+            user.preferences = get_preferences(user.id, conn).await;
+            Some(user)
+        }
+        Ok(None) => None,
+        Err(e) => {
+            error!("unable to retrieve user from api token : {e}");
+            None
+        }
+    }
+}
+
+/// display preferences of a user, defaults when never saved
+pub async fn get_preferences(user_id: i64, conn: &Pool<Sqlite>) -> Preferences {
+    match sqlx::query_as(
+        "SELECT view, readable_only, theme, reader_font, reader_font_size, reader_line_height,
+        reader_margin, reader_justify, hidden_formats FROM user_preferences WHERE user_id = ?;",
+    )
+    .bind(user_id)
+    .fetch_optional(conn)
+    .await
+    {
+        Ok(preferences) => preferences.unwrap_or_default(),
+        Err(e) => {
+            error!("unable to retrieve preferences of user {user_id} : {e}");
+            Preferences::default()
+        }
+    }
+}
+
+pub async fn set_preferences(user_id: i64, preferences: &Preferences, conn: &Pool<Sqlite>) {
+    match sqlx::query(
+        "INSERT INTO user_preferences(user_id, view, readable_only, theme, hidden_formats)
+        VALUES (?, ?, ?, ?, ?)
+        ON CONFLICT(user_id) DO UPDATE
+        SET view = excluded.view, readable_only = excluded.readable_only, theme = excluded.theme,
+        hidden_formats = excluded.hidden_formats;",
+    )
+    .bind(user_id)
+    .bind(preferences.view)
+    .bind(preferences.readable_only)
+    .bind(preferences.theme)
+    .bind(preferences.hidden_formats.to_string())
+    .execute(conn)
+    .await
+    {
+        Ok(_) => info!("preferences of user {user_id} saved"),
+        Err(e) => error!("unable to save preferences of user {user_id} : {e}"),
+    }
+}
+
+pub async fn set_typography(user_id: i64, typography: &Typography, conn: &Pool<Sqlite>) {
+    match sqlx::query(
+        "INSERT INTO user_preferences(user_id, reader_font, reader_font_size, reader_line_height,
+        reader_margin, reader_justify) VALUES (?, ?, ?, ?, ?, ?)
+        ON CONFLICT(user_id) DO UPDATE
+        SET reader_font = excluded.reader_font, reader_font_size = excluded.reader_font_size,
+        reader_line_height = excluded.reader_line_height, reader_margin = excluded.reader_margin,
+        reader_justify = excluded.reader_justify;",
+    )
+    .bind(user_id)
+    .bind(typography.reader_font)
+    .bind(typography.reader_font_size)
+    .bind(typography.reader_line_height)
+    .bind(typography.reader_margin)
+    .bind(typography.reader_justify)
+    .execute(conn)
+    .await
+    {
+        Ok(_) => info!("reader typography of user {user_id} saved"),
+        Err(e) => error!("unable to save reader typography of user {user_id} : {e}"),
+    }
+}
+
+/// smart collections of a user, by name
+pub async fn get_collections(user_id: i64, conn: &Pool<Sqlite>) -> Vec<Collection> {
+    match sqlx::query_as("SELECT * FROM collections WHERE user_id = ? ORDER BY name;")
+        .bind(user_id)
+        .fetch_all(conn)
+        .await
+    {
+        Ok(collections) => collections,
+        Err(e) => {
+            error!("unable to retrieve collections of user {user_id} : {e}");
+            Vec::with_capacity(0)
+        }
+    }
+}
+
+/// a collection, only if owned by user
+pub async fn get_collection(
+    collection_id: i64,
+    user_id: i64,
+    conn: &Pool<Sqlite>,
+) -> Option<Collection> {
+    match sqlx::query_as("SELECT * FROM collections WHERE id = ? AND user_id = ?;")
+        .bind(collection_id)
+        .bind(user_id)
+        .fetch_optional(conn)
+        .await
+    {
+        Ok(collection) => collection,
+        Err(e) => {
+            error!("unable to retrieve collection {collection_id} : {e}");
+            None
+        }
+    }
+}
+
+/// save a search query, a collection with the same name is replaced, return the new id
+pub async fn create_collection(
+    user_id: i64,
+    name: &str,
+    query: &str,
+    conn: &Pool<Sqlite>,
+) -> Option<i64> {
+    match sqlx::query(
+        "INSERT OR REPLACE INTO collections(user_id, name, query) VALUES (?, ?, ?) RETURNING id;",
+    )
+    .bind(user_id)
+    .bind(name)
+    .bind(query)
+    .fetch_one(conn)
+    .await
+    {
+        Ok(row) => {
+            info!("collection [{name}] saved for user {user_id}");
+            Some(row.get("id"))
+        }
+        Err(e) => {
+            error!("failed to save collection [{name}] for user {user_id} : {e}");
+            None
+        }
+    }
+}
+
+pub async fn delete_collection(collection_id: i64, user_id: i64, conn: &Pool<Sqlite>) {
+    match sqlx::query("DELETE FROM collections WHERE id = ? AND user_id = ?;")
+        .bind(collection_id)
+        .bind(user_id)
+        .execute(conn)
+        .await
+    {
+        Ok(_) => info!("collection {collection_id} deleted for user {user_id}"),
+        Err(e) => error!("failed to delete collection {collection_id} : {e}"),
+    }
+}
+
+/// ordered list of files, `owner_name` comes from users table
+#[derive(Debug, Default, Clone, sqlx::FromRow, PartialEq, Eq, Serialize)]
+pub struct ReadingList {
+    pub id: i64,
+    pub user_id: i64,
+    pub name: String,
+    pub shared: bool,
+    pub owner_name: String,
+}
+
+const READING_LISTS_SELECT: &str = "SELECT reading_lists.*, users.name AS owner_name
+    FROM reading_lists JOIN users ON users.id = reading_lists.user_id";
+
+/// lists of a user, and lists shared by others
+pub async fn get_reading_lists(user_id: i64, conn: &Pool<Sqlite>) -> Vec<ReadingList> {
+    match sqlx::query_as(&format!(
+        "{READING_LISTS_SELECT} WHERE reading_lists.user_id = ? OR reading_lists.shared = TRUE
+        ORDER BY reading_lists.user_id != ?, reading_lists.name;"
+    ))
+    .bind(user_id)
+    .bind(user_id)
+    .fetch_all(conn)
+    .await
+    {
+        Ok(reading_lists) => reading_lists,
+        Err(e) => {
+            error!("unable to retrieve reading lists of user {user_id} : {e}");
+            Vec::with_capacity(0)
+        }
+    }
+}
+
+/// a list, only if the user owns it or if it is shared
+pub async fn get_reading_list(
+    list_id: i64,
+    user_id: i64,
+    conn: &Pool<Sqlite>,
+) -> Option<ReadingList> {
+    match sqlx::query_as(&format!(
+        "{READING_LISTS_SELECT} WHERE reading_lists.id = ?
+        AND (reading_lists.user_id = ? OR reading_lists.shared = TRUE);"
+    ))
+    .bind(list_id)
+    .bind(user_id)
+    .fetch_optional(conn)
+    .await
+    {
+        Ok(reading_list) => reading_list,
+        Err(e) => {
+            error!("unable to retrieve reading list {list_id} : {e}");
+            None
+        }
+    }
+}
+
+/// return the new list id
+pub async fn create_reading_list(
+    user_id: i64,
+    name: &str,
+    shared: bool,
+    conn: &Pool<Sqlite>,
+) -> Option<i64> {
+    match sqlx::query(
+        "INSERT INTO reading_lists(user_id, name, shared) VALUES (?, ?, ?) RETURNING id;",
+    )
+    .bind(user_id)
+    .bind(name)
+    .bind(shared)
+    .fetch_one(conn)
+    .await
+    {
+        Ok(row) => {
+            info!("reading list [{name}] created for user {user_id}");
+            Some(row.get("id"))
+        }
+        Err(e) => {
+            error!("failed to create reading list [{name}] for user {user_id} : {e}");
+            None
+        }
+    }
+}
+
+pub async fn update_reading_list(
+    list_id: i64,
+    user_id: i64,
+    name: &str,
+    shared: bool,
+    conn: &Pool<Sqlite>,
+) {
+    match sqlx::query("UPDATE reading_lists SET name = ?, shared = ? WHERE id = ? AND user_id = ?;")
+        .bind(name)
+        .bind(shared)
+        .bind(list_id)
+        .bind(user_id)
+        .execute(conn)
+        .await
+    {
+        Ok(_) => debug!("reading list {list_id} updated"),
+        Err(e) => error!("failed to update reading list {list_id} : {e}"),
+    }
+}
+
+pub async fn delete_reading_list(list_id: i64, user_id: i64, conn: &Pool<Sqlite>) {
+    match sqlx::query(
+        "DELETE FROM reading_list_items WHERE list_id IN (
+            SELECT id FROM reading_lists WHERE id = ? AND user_id = ?
+         );
+         DELETE FROM reading_lists WHERE id = ? AND user_id = ?;",
+    )
+    .bind(list_id)
+    .bind(user_id)
+    .bind(list_id)
+    .bind(user_id)
+    .execute(conn)
+    .await
+    {
+        Ok(_) => info!("reading list {list_id} deleted for user {user_id}"),
+        Err(e) => error!("failed to delete reading list {list_id} : {e}"),
+    }
+}
+
+/// files of a list, in order
+pub async fn get_reading_list_files(list_id: i64, conn: &Pool<Sqlite>) -> Vec<FileInfo> {
+    match sqlx::query_as(
+        "SELECT files.* FROM reading_list_items JOIN files ON files.id = reading_list_items.file_id
+        WHERE reading_list_items.list_id = ? ORDER BY reading_list_items.position;",
+    )
+    .bind(list_id)
+    .fetch_all(conn)
+    .await
+    {
+        Ok(files) => files,
+        Err(e) => {
+            error!("unable to retrieve files of reading list {list_id} : {e}");
+            Vec::with_capacity(0)
+        }
+    }
+}
+
+/// add a file at the end of a list
+pub async fn add_to_reading_list(list_id: i64, file_id: &str, conn: &Pool<Sqlite>) {
+    match sqlx::query(
+        "INSERT OR IGNORE INTO reading_list_items(list_id, file_id, position)
+        SELECT ?, ?, ifnull(max(position), 0) + 1 FROM reading_list_items WHERE list_id = ?;",
+    )
+    .bind(list_id)
+    .bind(file_id)
+    .bind(list_id)
+    .execute(conn)
+    .await
+    {
+        Ok(_) => debug!("file {file_id} added to reading list {list_id}"),
+        Err(e) => error!("failed to add file {file_id} to reading list {list_id} : {e}"),
+    }
+}
+
+pub async fn remove_from_reading_list(list_id: i64, file_id: &str, conn: &Pool<Sqlite>) {
+    match sqlx::query("DELETE FROM reading_list_items WHERE list_id = ? AND file_id = ?;")
+        .bind(list_id)
+        .bind(file_id)
+        .execute(conn)
+        .await
+    {
+        Ok(_) => debug!("file {file_id} removed from reading list {list_id}"),
+        Err(e) => error!("failed to remove file {file_id} from reading list {list_id} : {e}"),
+    }
+}
+
+/// swap a file with the previous (or next) one in a list
+pub async fn move_in_reading_list(list_id: i64, file_id: &str, up: bool, conn: &Pool<Sqlite>) {
+    let (comparison, order) = if up { ("<", "DESC") } else { (">", "ASC") };
+    let request = format!(
+        "WITH item AS (
+            SELECT position FROM reading_list_items WHERE list_id = ?2 AND file_id = ?1
+        ), neighbour AS (
+            SELECT file_id, position FROM reading_list_items
+            WHERE list_id = ?2 AND position {comparison} (SELECT position FROM item)
+            ORDER BY position {order} LIMIT 1
+        ) UPDATE reading_list_items SET position = CASE
+            WHEN file_id = ?1 THEN (SELECT position FROM neighbour)
+            ELSE (SELECT position FROM item) END
+        WHERE list_id = ?2 AND (file_id = ?1 OR file_id = (SELECT file_id FROM neighbour));"
+    );
+    match sqlx::query(&request)
+        .bind(file_id)
+        .bind(list_id)
+        .execute(conn)
+        .await
+    {
+        Ok(_) => debug!("file {file_id} moved in reading list {list_id}"),
+        Err(e) => error!("failed to move file {file_id} in reading list {list_id} : {e}"),
+    }
+}
+
+/// lists owned by a user, and if the file is in it
+pub async fn get_reading_lists_with_file(
+    file_id: &str,
+    user_id: i64,
+    conn: &Pool<Sqlite>,
+) -> Vec<(ReadingList, bool)> {
+    let lists_with_file: HashSet<i64> =
+        match sqlx::query_scalar("SELECT list_id FROM reading_list_items WHERE file_id = ?;")
+            .bind(file_id)
+            .fetch_all(conn)
+            .await
+        {
+            Ok(lists) => lists.into_iter().collect(),
+            Err(e) => {
+                error!("unable to retrieve reading lists of file {file_id} : {e}");
+                HashSet::with_capacity(0)
+            }
+        };
+    get_reading_lists(user_id, conn)
+        .await
+        .into_iter()
+        .filter(|reading_list| reading_list.user_id == user_id)
+        .map(|reading_list| {
+            let with_file = lists_with_file.contains(&reading_list.id);
+            (reading_list, with_file)
+        })
+        .collect()
+}
+
+/// the file following this one in each list visible by the user
+pub async fn get_next_in_reading_lists(
+    file_id: &str,
+    user_id: i64,
+    conn: &Pool<Sqlite>,
+) -> Vec<(ReadingList, FileInfo)> {
+    let mut next_files: Vec<(ReadingList, FileInfo)> = Vec::new();
+    for reading_list in get_reading_lists(user_id, conn).await {
+        let next_file: Option<FileInfo> = match sqlx::query_as(
+            "SELECT files.* FROM reading_list_items JOIN files ON files.id = reading_list_items.file_id
+            WHERE reading_list_items.list_id = ?1 AND reading_list_items.position > (
+                SELECT position FROM reading_list_items WHERE list_id = ?1 AND file_id = ?2
+            ) ORDER BY reading_list_items.position LIMIT 1;",
+        )
+        .bind(reading_list.id)
+        .bind(file_id)
+        .fetch_optional(conn)
+        .await
+        {
+            Ok(next_file) => next_file,
+            Err(e) => {
+                error!("unable to retrieve next file in reading list {} : {e}", reading_list.id);
+                None
+            }
+        };
+        if let Some(next_file) = next_file {
+            next_files.push((reading_list, next_file));
+        }
+    }
+    next_files
+}
+
+/// current date in unixepoch format, used for flags date
+fn now_timestamp() -> i64 {
+    let now = SystemTime::now();
+    let since_the_epoch = now.duration_since(UNIX_EPOCH).expect("Time went backwards");
+    since_the_epoch.as_secs() as i64
+}
+
+/// add or remove a flag on a file for a user, return the new status
+pub async fn set_flag_status(
+    flag: &Flag,
+    user_id: i64,
+    file_id: &str,
+    conn: &Pool<Sqlite>,
+) -> bool {
+    let flag_status = !get_flag_status(flag, user_id, file_id, conn).await;
+    let toggle_query = if flag_status {
+        sqlx::query(
+            "INSERT OR IGNORE INTO user_file_flags(file_id, user_id, flag, flag_date) VALUES (?, ?, ?, ?);",
+        )
+        .bind(file_id)
+        .bind(user_id)
+        .bind(flag)
+        .bind(now_timestamp())
+    } else {
+        sqlx::query("DELETE FROM user_file_flags WHERE file_id = ? AND user_id = ? AND flag = ?;")
+            .bind(file_id)
+            .bind(user_id)
+            .bind(flag)
+    };
+    match toggle_query.execute(conn).await {
+        Ok(_) => {
+            debug!("flag {flag:?} {file_id} setted to {flag_status} for user {user_id}");
+            // remove file from reading list if we set read_status to true
+            if *flag == Flag::ReadStatus && flag_status {
+                remove_file_id_from_reading(file_id, &user_id, conn).await;
+            }
+            flag_status
+        }
+        Err(e) => {
+            error!("failed to set flag {flag:?} {file_id} to user {user_id} : {e}");
+            !flag_status
+        }
+    }
+}
+
+pub async fn get_flag_status(
+    flag: &Flag,
+    user_id: i64,
+    file_id: &str,
+    conn: &Pool<Sqlite>,
+) -> bool {
+    match sqlx::query(
+        "SELECT count(*) AS found FROM user_file_flags WHERE file_id = ? AND user_id = ? AND flag = ?;",
+    )
+    .bind(file_id)
+    .bind(user_id)
+    .bind(flag)
+    .fetch_one(conn)
+    .await
+    {
+        Ok(row) => {
+            let found: i32 = row.get("found");
+            found > 0
+        }
+        Err(e) => {
+            error!("unable to retrieve flag for file {file_id} : {e}");
+            false
+        }
+    }
+}
+
+/// add bookmark and read status of a user to a file list
+/// flags are retrieved by chunks, not with two queries per file
+pub async fn get_files_flags(
+    files_list: Vec<FileInfo>,
+    user_id: i64,
+    conn: &Pool<Sqlite>,
+) -> Vec<(FileInfo, bool, bool)> {
+    let mut bookmarked: HashSet<String> = HashSet::new();
+    let mut read: HashSet<String> = HashSet::new();
+    // stay far from the sqlite variables limit
+    for files_chunk in files_list.chunks(500) {
+        let placeholders = vec!["?"; files_chunk.len()].join(", ");
+        let request = format!(
+            "SELECT file_id, flag FROM user_file_flags WHERE user_id = ? AND file_id IN ({placeholders});"
+        );
+        let mut query = sqlx::query_as::<_, (String, Flag)>(&request).bind(user_id);
+        for file in files_chunk {
+            query = query.bind(&file.id);
+        }
+        match query.fetch_all(conn).await {
+            Ok(flags) => {
+                for (file_id, flag) in flags {
+                    match flag {
+                        Flag::Bookmark => bookmarked.insert(file_id),
+                        Flag::ReadStatus => read.insert(file_id),
+                    };
+                }
+            }
+            Err(e) => error!("unable to retrieve flags for user {user_id} : {e}"),
+        }
+    }
+    files_list
+        .into_iter()
+        .map(|file| {
+            let bookmark_status = bookmarked.contains(&file.id);
+            let read_status = read.contains(&file.id);
+            (file, bookmark_status, read_status)
+        })
+        .collect()
+}
+
+pub async fn bookmarks_for_user_id(id: i64, conn: &Pool<Sqlite>) -> Vec<FileInfo> {
+    match sqlx::query_as(
+        "SELECT files.* FROM user_file_flags
+        INNER JOIN files ON files.id = user_file_flags.file_id
+        WHERE user_file_flags.user_id = ? AND user_file_flags.flag = ?;",
+    )
+    .bind(id)
+    .bind(Flag::Bookmark)
+    .fetch_all(conn)
+    .await
+    {
+        Ok(files_list) => files_list,
+        Err(e) => {
+            error!("unable to find bookmarked files in database for user id {id}: {e}");
+            Vec::with_capacity(0)
+        }
+    }
+}
+
+/// get DirectoryInfo from directory id
+pub async fn get_directory_from_id(
+    directory_id: &str,
+    conn: &Pool<Sqlite>,
+) -> Option<DirectoryInfo> {
+    match sqlx::query_as("SELECT * FROM directories WHERE id = ?;")
+        .bind(directory_id)
         .fetch_one(conn)
         .await
     {
-        Ok(user_list) => {
-            let flag_status: bool;
-            let user_list_string: String = user_list.get(flag_field);
-            let updated_user_list = if user_list_string.is_empty() {
-                flag_status = true;
-                user_id.to_string()
-            } else {
-                // create new list
-                // String `1,2,3,...` to Vec `[1, 2, 3, ...]`
-                let mut user_list_vec: Vec<String> =
-                    user_list_string.split(',').map(|x| x.to_string()).collect();
-                // insert or remove user form list
-                if let Ok(found_user_index) = user_list_vec.binary_search(&user_id.to_string()) {
-                    flag_status = false;
-                    user_list_vec.remove(found_user_index);
-                } else {
-                    flag_status = true;
-                    user_list_vec.push(user_id.to_string());
-                }
-                // Vec `[1, 2, 3, ...]` to String `1,2,3,...`
-                user_list_vec.join(",")
-            };
-            // set status
-            match sqlx::query(toggle_query)
-                .bind(updated_user_list)
-                .bind(file_id)
-                .execute(conn)
-                .await
-            {
-                Ok(_) => {
-                    debug!("flag {} {} setted to user {}", flag, file_id, user_id);
-                    // remove file from reading list if we set read_status to true
-                    if flag_field == "read_by" && flag_status {
-                        remove_file_id_from_reading(file_id, &user_id, conn).await;
-                    }
-                }
-                Err(e) => error!(
-                    "failed to add flag {} {} to user {} : {e}",
-                    flag, file_id, user_id,
-                ),
-            };
+        Ok(directory_found) => Some(directory_found),
+        Err(e) => {
+            error!("unable to retrieve directory infos from id from database : {e}");
+            None
+        }
+    }
+}
+
+pub async fn get_directory_bookmark_status(
+    user_id: i64,
+    directory_id: &str,
+    conn: &Pool<Sqlite>,
+) -> bool {
+    match sqlx::query(
+        "SELECT count(*) AS found FROM user_directory_flags
+        WHERE directory_id = ? AND user_id = ? AND flag = ?;",
+    )
+    .bind(directory_id)
+    .bind(user_id)
+    .bind(Flag::Bookmark)
+    .fetch_one(conn)
+    .await
+    {
+        Ok(row) => {
+            let found: i32 = row.get("found");
+            found > 0
+        }
+        Err(e) => {
+            error!("unable to retrieve bookmark for directory {directory_id} : {e}");
+            false
+        }
+    }
+}
+
+/// add or remove a directory from the bookmarks of a user, return the new status
+pub async fn set_directory_bookmark_status(
+    user_id: i64,
+    directory_id: &str,
+    conn: &Pool<Sqlite>,
+) -> bool {
+    let flag_status = !get_directory_bookmark_status(user_id, directory_id, conn).await;
+    let toggle_query = if flag_status {
+        sqlx::query(
+            "INSERT OR IGNORE INTO user_directory_flags(directory_id, user_id, flag, flag_date) VALUES (?, ?, ?, ?);",
+        )
+        .bind(directory_id)
+        .bind(user_id)
+        .bind(Flag::Bookmark)
+        .bind(now_timestamp())
+    } else {
+        sqlx::query(
+            "DELETE FROM user_directory_flags WHERE directory_id = ? AND user_id = ? AND flag = ?;",
+        )
+        .bind(directory_id)
+        .bind(user_id)
+        .bind(Flag::Bookmark)
+    };
+    match toggle_query.execute(conn).await {
+        Ok(_) => {
+            debug!(
+                "bookmark of directory {directory_id} setted to {flag_status} for user {user_id}"
+            );
             flag_status
         }
         Err(e) => {
             error!(
-                "failed to add {} {} to user {} : {e}",
-                flag, file_id, user_id,
+                "failed to toggle bookmark of directory {directory_id} for user {user_id} : {e}"
             );
-            false
+            !flag_status
         }
     }
 }
 
-pub async fn get_flag_status(flag: &str, user_id: i64, file_id: &str, conn: &Pool<Sqlite>) -> bool {
-    let (request, column) = match flag {
-        "bookmark" => (
-            "SELECT bookmarked_by FROM files WHERE id = ?;",
-            "bookmarked_by",
-        ),
-        "read_status" => ("SELECT read_by FROM files WHERE id = ?;", "read_by"),
-        _ => ("", ""),
-    };
-    match sqlx::query(request).bind(file_id).fetch_one(conn).await {
-        Ok(user_list) => {
-            let user_list: String = user_list.get(column);
-            user_list.contains(&user_id.to_string())
+pub async fn directory_bookmarks_for_user_id(id: i64, conn: &Pool<Sqlite>) -> Vec<DirectoryInfo> {
+    match sqlx::query_as(
+        "SELECT directories.* FROM user_directory_flags
+        INNER JOIN directories ON directories.id = user_directory_flags.directory_id
+        WHERE user_directory_flags.user_id = ? AND user_directory_flags.flag = ?;",
+    )
+    .bind(id)
+    .bind(Flag::Bookmark)
+    .fetch_all(conn)
+    .await
+    {
+        Ok(directories_list) => directories_list,
+        Err(e) => {
+            error!("unable to find bookmarked directories in database for user id {id}: {e}");
+            Vec::with_capacity(0)
+        }
+    }
+}
+
+/// add bookmark status and read count of a user to a directory list
+pub async fn get_directories_flags(
+    directories_list: Vec<DirectoryInfo>,
+    user_id: i64,
+    hidden_formats: &HiddenFormats,
+    conn: &Pool<Sqlite>,
+) -> Vec<(DirectoryInfo, bool, i32)> {
+    let bookmarked: HashSet<String> = directory_bookmarks_for_user_id(user_id, conn)
+        .await
+        .into_iter()
+        .map(|directory| directory.id)
+        .collect();
+    let counts = get_directories_counts(&directories_list, user_id, hidden_formats, conn).await;
+    let mut directories_list_with_status: Vec<(DirectoryInfo, bool, i32)> =
+        Vec::with_capacity(directories_list.capacity());
+    for mut directory in directories_list {
+        let bookmark_status = bookmarked.contains(&directory.id);
+        let (file_count, read_count) = counts.get(&directory.id).copied().unwrap_or_default();
+        // stored count only knows formats hidden by the library
+        if !hidden_formats.is_empty() {
+            directory.file_count = Some(file_count);
         }
+        directories_list_with_status.push((directory, bookmark_status, read_count));
+    }
+    directories_list_with_status
+}
+
+/// visible and read files of directories and their sub directories, by directory id
+async fn get_directories_counts(
+    directories_list: &[DirectoryInfo],
+    user_id: i64,
+    hidden_formats: &HiddenFormats,
+    conn: &Pool<Sqlite>,
+) -> HashMap<String, (i32, i32)> {
+    if directories_list.is_empty() {
+        return HashMap::with_capacity(0);
+    }
+    let mut query: QueryBuilder<Sqlite> = QueryBuilder::new("WITH page(id, path) AS (");
+    query.push_values(directories_list, |mut row, directory| {
+        row.push_bind(directory.id.clone())
+            .push_bind(format!("{}/{}", directory.parent_path, directory.name));
+    });
+    // a simple `instr` would also count files of `Foobar` for directory `Foo`
+    query
+        .push(
+            ") SELECT page.id, count(files.id) AS file_count,
+            count(user_file_flags.file_id) AS read_count
+            FROM page INNER JOIN files ON (files.parent_path = page.path
+            OR substr(files.parent_path, 1, length(page.path) + 1) = page.path || '/')
+            LEFT JOIN user_file_flags ON user_file_flags.file_id = files.id
+            AND user_file_flags.user_id = ",
+        )
+        .push_bind(user_id)
+        .push(" AND user_file_flags.flag = ")
+        .push_bind(Flag::ReadStatus)
+        .push(" WHERE TRUE");
+    push_visible_formats(&mut query, hidden_formats);
+    query.push(" GROUP BY page.id;");
+    match query.build().fetch_all(conn).await {
+        Ok(rows) => rows
+            .iter()
+            .map(|row| {
+                (
+                    row.get("id"),
+                    (row.get("file_count"), row.get("read_count")),
+                )
+            })
+            .collect(),
         Err(e) => {
-            error!("unable to retrieve flag for file {file_id} : {e}");
-            false
+            error!("unable to retrieve file and read counts of directories : {e}");
+            HashMap::with_capacity(0)
         }
     }
 }
 
-pub async fn bookmarks_for_user_id(id: i64, conn: &Pool<Sqlite>) -> Vec<FileInfo> {
-    let request = format!("SELECT * FROM files WHERE bookmarked_by LIKE '%{}%';", id);
-    let results: Vec<FileInfo> = match sqlx::query_as(&request).fetch_all(conn).await {
-        Ok(files_list) => files_list,
+/// count files in a directory and all its sub directories
+pub async fn get_directory_file_count(
+    directory: &DirectoryInfo,
+    hidden_formats: &HiddenFormats,
+    conn: &Pool<Sqlite>,
+) -> i32 {
+    let directory_full_path = format!("{}/{}", directory.parent_path, directory.name);
+    // a simple `instr` would also count files of `Foobar` for directory `Foo`
+    let mut query: QueryBuilder<Sqlite> =
+        QueryBuilder::new("SELECT count(*) AS file_count FROM files WHERE (files.parent_path = ");
+    query
+        .push_bind(directory_full_path.clone())
+        .push(" OR substr(files.parent_path, 1, length(")
+        .push_bind(format!("{directory_full_path}/"))
+        .push(")) = ")
+        .push_bind(format!("{directory_full_path}/"))
+        .push(")");
+    push_visible_formats(&mut query, hidden_formats);
+    match query.build().fetch_one(conn).await {
+        Ok(row) => row.get("file_count"),
+        Err(e) => {
+            error!("unable to retrieve file number for directory [{directory_full_path}] : {e}");
+            0
+        }
+    }
+}
+
+/// store the number of files of a directory, formats hidden by its library excluded
+pub async fn update_directory_file_count(directory: &DirectoryInfo, conn: &Pool<Sqlite>) {
+    let directory_full_path = format!("{}/{}", directory.parent_path, directory.name);
+    let directory_file_count =
+        get_directory_file_count(directory, &HiddenFormats::default(), conn).await;
+    match sqlx::query("UPDATE directories SET file_count = ? WHERE id = ?;")
+        .bind(directory_file_count)
+        .bind(&directory.id)
+        .execute(conn)
+        .await
+    {
+        Ok(_) => debug!(
+            "insert file count {} for directory [{}]",
+            directory_file_count, directory_full_path
+        ),
+        Err(e) => error!(
+            "unable to set file count for directory [{}] : {e}",
+            directory_full_path
+        ),
+    }
+}
+
+/// store the number of files of every directory of a library
+pub async fn update_library_directories_file_count(library: &Library, conn: &Pool<Sqlite>) {
+    let library_path = library.path.trim_end_matches('/');
+    let directories: Vec<DirectoryInfo> = match sqlx::query_as(
+        "SELECT * FROM directories WHERE parent_path = ? OR substr(parent_path, 1, length(?)) = ?;",
+    )
+    .bind(library_path)
+    .bind(format!("{library_path}/"))
+    .bind(format!("{library_path}/"))
+    .fetch_all(conn)
+    .await
+    {
+        Ok(directories) => directories,
         Err(e) => {
             error!(
-                "unable to find bookmarked files in database for user id {}: {e}",
-                id
+                "unable to retrieve directories of library [{}] : {e}",
+                library.name
             );
             Vec::with_capacity(0)
         }
     };
-    results
+    for directory in directories {
+        update_directory_file_count(&directory, conn).await;
+    }
 }
 
-pub async fn search_file_from_string(search_query: &str, conn: &Pool<Sqlite>) -> Vec<FileInfo> {
-    let request = format!(
-        "SELECT * FROM files WHERE name LIKE '%{}%' OR parent_path LIKE '%{}%';",
-        search_query, search_query
-    );
-    let results: Vec<FileInfo> = match sqlx::query_as(&request).fetch_all(conn).await {
-        Ok(files_list) => files_list,
+/// number of files directly in a directory, formats hidden excluded
+pub async fn count_files_in_path(
+    parent_path: &str,
+    hidden_formats: &HiddenFormats,
+    conn: &Pool<Sqlite>,
+) -> i64 {
+    let mut query: QueryBuilder<Sqlite> =
+        QueryBuilder::new("SELECT count(*) AS file_count FROM files WHERE files.parent_path = ");
+    query.push_bind(parent_path.to_string());
+    push_visible_formats(&mut query, hidden_formats);
+    match query.build().fetch_one(conn).await {
+        Ok(row) => row.get("file_count"),
+        Err(e) => {
+            error!("unable to count files of directory [{parent_path}] : {e}");
+            0
+        }
+    }
+}
+
+/// a page of the files directly in a directory, sorted by date added or size, formats hidden
+/// excluded
+pub async fn get_files_page_in_path(
+    parent_path: &str,
+    hidden_formats: &HiddenFormats,
+    sort: SortOrder,
+    offset: usize,
+    limit: usize,
+    conn: &Pool<Sqlite>,
+) -> Vec<FileInfo> {
+    let mut query: QueryBuilder<Sqlite> =
+        QueryBuilder::new("SELECT * FROM files WHERE files.parent_path = ");
+    query.push_bind(parent_path.to_string());
+    push_visible_formats(&mut query, hidden_formats);
+    query
+        .push(match sort {
+            SortOrder::Size => " ORDER BY files.size DESC, files.name COLLATE NOCASE",
+            _ => " ORDER BY files.added_date DESC, files.name COLLATE NOCASE",
+        })
+        .push(" LIMIT ")
+        .push_bind(limit as i64)
+        .push(" OFFSET ")
+        .push_bind(offset as i64);
+    match query.build_query_as().fetch_all(conn).await {
+        Ok(files) => files,
         Err(e) => {
-            error!("unable to search files in database : {e}");
+            error!("unable to retrieve files of directory [{parent_path}] : {e}");
             Vec::with_capacity(0)
         }
-    };
-    results
+    }
+}
+
+/// ids of the files read by a user
+pub async fn get_read_file_ids(user_id: i64, conn: &Pool<Sqlite>) -> HashSet<String> {
+    match sqlx::query_scalar("SELECT file_id FROM user_file_flags WHERE user_id = ? AND flag = ?;")
+        .bind(user_id)
+        .bind(Flag::ReadStatus)
+        .fetch_all(conn)
+        .await
+    {
+        Ok(file_ids) => file_ids.into_iter().collect(),
+        Err(e) => {
+            error!("unable to retrieve files read by user {user_id} : {e}");
+            HashSet::with_capacity(0)
+        }
+    }
 }
 
-pub async fn search_directory_from_string(
-    search_query: &str,
+/// count files read by a user in a directory and all its sub directories
+pub async fn get_directory_read_count(
+    user_id: i64,
+    directory: &DirectoryInfo,
+    hidden_formats: &HiddenFormats,
     conn: &Pool<Sqlite>,
-) -> Vec<DirectoryInfo> {
-    let request = format!(
-        "SELECT * FROM directories WHERE name LIKE '%{}%' OR parent_path LIKE '%{}%';",
-        search_query, search_query
+) -> i32 {
+    let directory_full_path = format!("{}/{}", directory.parent_path, directory.name);
+    let mut query: QueryBuilder<Sqlite> = QueryBuilder::new(
+        "SELECT count(*) AS read_count FROM user_file_flags
+        INNER JOIN files ON files.id = user_file_flags.file_id
+        WHERE user_file_flags.user_id = ",
     );
-    let results: Vec<DirectoryInfo> = match sqlx::query_as(&request).fetch_all(conn).await {
-        Ok(directories_list) => directories_list,
+    query
+        .push_bind(user_id)
+        .push(" AND user_file_flags.flag = ")
+        .push_bind(Flag::ReadStatus)
+        .push(" AND (files.parent_path = ")
+        .push_bind(directory_full_path.clone())
+        .push(" OR substr(files.parent_path, 1, length(")
+        .push_bind(format!("{directory_full_path}/"))
+        .push(")) = ")
+        .push_bind(format!("{directory_full_path}/"))
+        .push(")");
+    push_visible_formats(&mut query, hidden_formats);
+    match query.build().fetch_one(conn).await {
+        Ok(row) => row.get("read_count"),
         Err(e) => {
-            error!("unable to search directories in database : {e}");
-            Vec::with_capacity(0)
+            error!("unable to retrieve read count for directory [{directory_full_path}] : {e}");
+            0
+        }
+    }
+}
+
+/// set read status of all files in a directory and its sub directories for a user
+pub async fn set_directory_read_status(
+    user_id: i64,
+    directory: &DirectoryInfo,
+    read_status: bool,
+    conn: &Pool<Sqlite>,
+) {
+    let directory_full_path = format!("{}/{}", directory.parent_path, directory.name);
+    let request = if read_status {
+        // files marked as read are removed from reading list
+        sqlx::query(
+            "INSERT OR IGNORE INTO user_file_flags(file_id, user_id, flag, flag_date)
+            SELECT id, ?, ?, ? FROM files
+            WHERE parent_path = ? OR substr(parent_path, 1, length(?)) = ?;
+            DELETE FROM reading WHERE user_id = ? AND file_id IN (
+                SELECT id FROM files WHERE parent_path = ? OR substr(parent_path, 1, length(?)) = ?
+            );",
+        )
+        .bind(user_id)
+        .bind(Flag::ReadStatus)
+        .bind(now_timestamp())
+        .bind(&directory_full_path)
+        .bind(format!("{directory_full_path}/"))
+        .bind(format!("{directory_full_path}/"))
+        .bind(user_id)
+        .bind(&directory_full_path)
+        .bind(format!("{directory_full_path}/"))
+        .bind(format!("{directory_full_path}/"))
+    } else {
+        sqlx::query(
+            "DELETE FROM user_file_flags WHERE user_id = ? AND flag = ? AND file_id IN (
+                SELECT id FROM files WHERE parent_path = ? OR substr(parent_path, 1, length(?)) = ?
+            );",
+        )
+        .bind(user_id)
+        .bind(Flag::ReadStatus)
+        .bind(&directory_full_path)
+        .bind(format!("{directory_full_path}/"))
+        .bind(format!("{directory_full_path}/"))
+    };
+    match request.execute(conn).await {
+        Ok(_) => debug!(
+            "read status of directory [{directory_full_path}] setted to {read_status} for user {user_id}"
+        ),
+        Err(e) => error!(
+            "failed to set read status of directory [{directory_full_path}] for user {user_id} : {e}"
+        ),
+    }
+}
+
+/// number of results in a page of the collections api
+pub const SEARCH_PAGE_SIZE: i64 = 50;
+/// markers around matches in highlighted names, from Unicode private use area
+pub const HIGHLIGHT_START: char = '\u{E000}';
+pub const HIGHLIGHT_END: char = '\u{E001}';
+
+/// words of chinese, japanese or korean scripts, written without spaces
+fn is_cjk(word: &str) -> bool {
+    word.chars().any(|character| {
+        matches!(character,
+            '\u{3040}'..='\u{30FF}' // hiragana, katakana
+            | '\u{3400}'..='\u{4DBF}' // cjk extension A
+            | '\u{4E00}'..='\u{9FFF}' // cjk ideographs
+            | '\u{AC00}'..='\u{D7AF}' // hangul
+            | '\u{F900}'..='\u{FAFF}' // cjk compatibility
+            | '\u{FF66}'..='\u{FF9F}' // halfwidth katakana
+        )
+    })
+}
+
+/// terms of user input, with `true` for `"quoted phrases"`
+fn query_terms(search_query: &str) -> Vec<(String, bool)> {
+    let mut terms: Vec<(String, bool)> = Vec::new();
+    // odd elements are between quotes, a missing closing quote ends the phrase
+    for (index, part) in search_query.split('"').enumerate() {
+        if index % 2 == 1 {
+            if !part.trim().is_empty() {
+                terms.push((part.trim().to_string(), true));
+            }
+        } else {
+            for word in part.split_whitespace() {
+                terms.push((word.to_string(), false));
+            }
+        }
+    }
+    terms
+}
+
+/// build a FTS5 query from user input : `"quoted phrases"` are kept as is, other words match as
+/// prefix, cjk terms are left to `cjk_terms`
+fn fts_query(search_query: &str) -> String {
+    query_terms(search_query)
+        .into_iter()
+        .filter(|(term, _)| !is_cjk(term))
+        .map(|(term, quoted)| match quoted {
+            true => format!("\"{term}\""),
+            false => format!("\"{term}\"*"),
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// cjk terms of user input, matched anywhere in names and metadata
+fn cjk_terms(search_query: &str) -> Vec<String> {
+    query_terms(search_query)
+        .into_iter()
+        .filter(|(term, _)| is_cjk(term))
+        .map(|(term, _)| term)
+        .collect()
+}
+
+/// rows of `table` (`files` or `directories`) holding every cjk term : with the trigram index
+/// from 3 characters, shorter ones are searched in the columns
+fn push_cjk_terms(builder: &mut QueryBuilder<'_, Sqlite>, table: &str, cjk_terms: &[String]) {
+    let columns: &[&str] = match table {
+        "files" => &["name", "parent_path", "title", "author", "series"],
+        _ => &["name", "parent_path"],
+    };
+    for term in cjk_terms {
+        if term.chars().count() >= 3 {
+            builder
+                .push(format!(
+                    " AND {table}.rowid IN (SELECT rowid FROM {table}_trigram WHERE {table}_trigram MATCH "
+                ))
+                .push_bind(format!("\"{term}\""))
+                .push(")");
+        } else {
+            builder.push(" AND (FALSE");
+            for column in columns {
+                builder
+                    .push(format!(" OR instr({table}.{column}, "))
+                    .push_bind(term.clone())
+                    .push(") > 0");
+            }
+            builder.push(")");
+        }
+    }
+}
+
+/// files of a format hidden by their library, `hidden_formats` is a comma separated list
+const HIDDEN_BY_LIBRARY: &str = "EXISTS (SELECT 1 FROM libraries
+    WHERE libraries.name = files.library_name
+    AND instr(',' || libraries.hidden_formats || ',', ',' || files.format || ',') > 0)";
+
+/// hide files of formats hidden by a user or by their library
+fn push_visible_formats(builder: &mut QueryBuilder<'_, Sqlite>, hidden_formats: &HiddenFormats) {
+    if !hidden_formats.is_empty() {
+        builder.push(" AND files.format NOT IN (");
+        let mut formats = builder.separated(", ");
+        for format in &hidden_formats.0 {
+            formats.push_bind(format.clone());
+        }
+        formats.push_unseparated(")");
+    }
+    builder.push(" AND NOT ").push(HIDDEN_BY_LIBRARY);
+}
+
+/// add `SearchFilters` conditions on files to a query, values are binded
+fn push_files_filters(
+    builder: &mut QueryBuilder<'_, Sqlite>,
+    filters: &SearchFilters,
+    user_id: i64,
+) {
+    push_visible_formats(builder, &filters.hidden_formats);
+    if let Some(format) = &filters.format {
+        builder
+            .push(" AND files.format = ")
+            .push_bind(format.clone());
+    }
+    if let Some(library) = &filters.library {
+        builder
+            .push(" AND files.library_name = ")
+            .push_bind(library.clone())
+            .push(" COLLATE NOCASE");
+    }
+    let user_flag_exists = |builder: &mut QueryBuilder<'_, Sqlite>, exists: bool, flag: Flag| {
+        builder
+            .push(if exists {
+                " AND EXISTS"
+            } else {
+                " AND NOT EXISTS"
+            })
+            .push(" (SELECT 1 FROM user_file_flags WHERE user_file_flags.file_id = files.id AND user_file_flags.user_id = ")
+            .push_bind(user_id)
+            .push(" AND user_file_flags.flag = ")
+            .push_bind(flag)
+            .push(")");
+    };
+    match &filters.status {
+        Some(ReadingStatus::Read) => user_flag_exists(builder, true, Flag::ReadStatus),
+        Some(ReadingStatus::Unread) => user_flag_exists(builder, false, Flag::ReadStatus),
+        Some(ReadingStatus::Reading) => {
+            builder
+                .push(" AND EXISTS (SELECT 1 FROM reading WHERE reading.file_id = files.id AND reading.user_id = ")
+                .push_bind(user_id)
+                .push(")");
+        }
+        None => (),
+    }
+    if let Some(bookmarked) = filters.bookmarked {
+        user_flag_exists(builder, bookmarked, Flag::Bookmark);
+    }
+    for (column, conditions) in [
+        ("added_date", &filters.added),
+        ("total_pages", &filters.pages),
+        ("size", &filters.size),
+    ] {
+        for (comparison, value) in conditions {
+            builder
+                .push(format!(" AND files.{column} {} ", comparison.as_sql()))
+                .push_bind(*value);
+        }
+    }
+}
+
+/// ranked search in files and directories, return a page of results and the total number of
+/// results
+pub async fn search(
+    filters: &SearchFilters,
+    user_id: i64,
+    page: i64,
+    page_size: i64,
+    conn: &Pool<Sqlite>,
+) -> (SearchResults, i64) {
+    let mut results = SearchResults::default();
+    if filters.is_empty() {
+        return (results, 0);
+    }
+    let mut fts_query = fts_query(&filters.text);
+    if let Some(author) = &filters.author {
+        fts_query.push_str(&format!(" author : \"{}\"", author.replace('"', "")));
+    }
+    let fts_query = fts_query.trim().to_string();
+    let cjk_terms = cjk_terms(&filters.text);
+    // filters are about files only
+    let with_directories = !filters.has_file_filters();
+    // nothing left to search, a query of quotes only for instance
+    if fts_query.is_empty() && cjk_terms.is_empty() && with_directories {
+        return (results, 0);
+    }
+    // matching rows of a table, ranked by `fts_query` if any
+    let push_matches = |builder: &mut QueryBuilder<'_, Sqlite>, table: &str| {
+        if fts_query.is_empty() {
+            builder.push(format!(" FROM {table} WHERE TRUE"));
+        } else {
+            builder
+                .push(format!(
+                    " FROM {table}_search JOIN {table} ON {table}.rowid = {table}_search.rowid
+                    WHERE {table}_search MATCH "
+                ))
+                .push_bind(fts_query.clone());
+        }
+        push_cjk_terms(builder, table, &cjk_terms);
+    };
+
+    // name matches weigh more than metadata, then path
+    let mut builder: QueryBuilder<Sqlite> =
+        QueryBuilder::new("SELECT kind, id, highlighted FROM (");
+    if with_directories {
+        if fts_query.is_empty() {
+            builder.push(
+                "SELECT 'directory' AS kind, directories.id AS id, directories.name AS highlighted, 0.0 AS score",
+            );
+        } else {
+            builder
+                .push("SELECT 'directory' AS kind, directories.id AS id, highlight(directories_search, 0, ")
+                .push_bind(HIGHLIGHT_START.to_string())
+                .push(", ")
+                .push_bind(HIGHLIGHT_END.to_string())
+                .push(") AS highlighted, bm25(directories_search, 10.0, 1.0) AS score");
+        }
+        push_matches(&mut builder, "directories");
+        builder.push(" UNION ALL ");
+    }
+    if fts_query.is_empty() {
+        builder
+            .push("SELECT 'file' AS kind, files.id AS id, files.name AS highlighted, 0.0 AS score");
+    } else {
+        builder
+            .push("SELECT 'file' AS kind, files.id AS id, highlight(files_search, 0, ")
+            .push_bind(HIGHLIGHT_START.to_string())
+            .push(", ")
+            .push_bind(HIGHLIGHT_END.to_string())
+            .push(") AS highlighted, bm25(files_search, 10.0, 1.0, 5.0, 3.0, 3.0, 1.0) AS score");
+    }
+    push_matches(&mut builder, "files");
+    push_files_filters(&mut builder, filters, user_id);
+    builder
+        .push(") ORDER BY score, highlighted LIMIT ")
+        .push_bind(page_size)
+        .push(" OFFSET ")
+        .push_bind(page.max(0).saturating_mul(page_size));
+    let ranked: Vec<(String, String, String)> = match builder.build_query_as().fetch_all(conn).await
+    {
+        Ok(ranked) => ranked,
+        Err(e) => {
+            error!("unable to search {filters:?} in database : {e}");
+            return (results, 0);
+        }
+    };
+
+    let mut builder: QueryBuilder<Sqlite> = QueryBuilder::new("SELECT ");
+    if with_directories {
+        builder.push("(SELECT count(*)");
+        push_matches(&mut builder, "directories");
+        builder.push(") + ");
+    }
+    builder.push("(SELECT count(*)");
+    push_matches(&mut builder, "files");
+    push_files_filters(&mut builder, filters, user_id);
+    builder.push(")");
+    let total_results: i64 = match builder.build_query_scalar().fetch_one(conn).await {
+        Ok(total_results) => total_results,
+        Err(e) => {
+            error!("unable to count results of search {filters:?} : {e}");
+            0
         }
     };
-    results
+    // keep the rank order
+    for (kind, id, highlighted) in ranked {
+        if kind == "directory" {
+            if let Some(directory) = get_directory_from_id(&id, conn).await {
+                results.directories.push(directory);
+            }
+        } else if let Some(file) = get_files_from_file_id(&id, conn).await {
+            results.files.push(file);
+        }
+        results.highlights.insert(id, highlighted);
+    }
+    (results, total_results)
+}
+
+/// files and directories found, with their name highlighted (see `HIGHLIGHT_START`)
+#[derive(Default)]
+pub struct SearchResults {
+    pub files: Vec<FileInfo>,
+    pub directories: Vec<DirectoryInfo>,
+    pub highlights: HashMap<String, String>,
+}
+
+/// loose images of a directory, in reading order
+pub async fn get_directory_images(parent_path: &str, conn: &Pool<Sqlite>) -> Vec<FileInfo> {
+    match sqlx::query_as("SELECT * FROM files WHERE parent_path = ? AND format = ?;")
+        .bind(parent_path)
+        .bind(Format::Jpg)
+        .fetch_all(conn)
+        .await
+    {
+        Ok(mut images) => {
+            images.sort();
+            images
+        }
+        Err(e) => {
+            error!("unable to retrieve images of directory {parent_path} : {e}");
+            Vec::with_capacity(0)
+        }
+    }
 }
 
 /// get all file in a directory path from database
@@ -764,8 +2515,8 @@ pub async fn insert_new_file(file: &mut FileInfo, ulid: Option<&str>, conn: &Poo
     };
     file.id = ulid;
     match sqlx::query(
-        "INSERT OR REPLACE INTO files(id, name, library_name, parent_path, size, added_date, scan_me, format, total_pages, read_by, bookmarked_by)
-                    VALUES(?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?);")
+        "INSERT OR REPLACE INTO files(id, name, library_name, parent_path, size, added_date, scan_me, format, total_pages)
+                    VALUES(?, ?, ?, ?, ?, ?, ?, ?, ?);")
         .bind(&file.id)
         .bind(&file.name)
         .bind(&file.library_name)
@@ -775,8 +2526,6 @@ pub async fn insert_new_file(file: &mut FileInfo, ulid: Option<&str>, conn: &Poo
         .bind(file.scan_me)
         .bind(&file.format)
         .bind(file.total_pages)
-        .bind(&file.read_by)
-        .bind(&file.bookmarked_by)
         .execute(conn).await {
         Ok(_) => {
             debug!("file insertion successfull ({}/{})", &file.parent_path, &file.id)
@@ -787,11 +2536,33 @@ pub async fn insert_new_file(file: &mut FileInfo, ulid: Option<&str>, conn: &Poo
 
 /// delete a file in database
 pub async fn delete_file(file: &FileInfo, conn: &Pool<Sqlite>) {
-    match sqlx::query("DELETE FROM files WHERE name = ? AND parent_path = ?;")
-        .bind(&file.name)
-        .bind(&file.parent_path)
-        .execute(conn)
-        .await
+    match sqlx::query(
+        "DELETE FROM user_file_flags WHERE file_id IN (
+            SELECT id FROM files WHERE name = ? AND parent_path = ?
+         );
+         DELETE FROM reading_list_items WHERE file_id IN (
+            SELECT id FROM files WHERE name = ? AND parent_path = ?
+         );
+         DELETE FROM problem_files WHERE file_id IN (
+            SELECT id FROM files WHERE name = ? AND parent_path = ?
+         );
+         DELETE FROM audio_chapters WHERE file_id IN (
+            SELECT id FROM files WHERE name = ? AND parent_path = ?
+         );
+         DELETE FROM files WHERE name = ? AND parent_path = ?;",
+    )
+    .bind(&file.name)
+    .bind(&file.parent_path)
+    .bind(&file.name)
+    .bind(&file.parent_path)
+    .bind(&file.name)
+    .bind(&file.parent_path)
+    .bind(&file.name)
+    .bind(&file.parent_path)
+    .bind(&file.name)
+    .bind(&file.parent_path)
+    .execute(conn)
+    .await
     {
         Ok(_) => {
             info!("file {}/{} deleted", file.name, file.parent_path)
@@ -819,13 +2590,24 @@ pub async fn get_registered_directories(conn: &Pool<Sqlite>) -> Vec<DirectoryInf
 
 /// delete a directory in database
 pub async fn delete_directory(directory: &DirectoryInfo, conn: &Pool<Sqlite>) {
+    let directory_full_path = format!("{}/{}", directory.parent_path, directory.name);
     match sqlx::query(
         "DELETE FROM directories WHERE name = ? AND parent_path = ?;
-         DELETE FROM files WHERE parent_path = ?;",
+         DELETE FROM user_file_flags WHERE file_id IN (SELECT id FROM files WHERE parent_path = ?);
+         DELETE FROM problem_files WHERE file_id IN (SELECT id FROM files WHERE parent_path = ?);
+         DELETE FROM audio_chapters WHERE file_id IN (SELECT id FROM files WHERE parent_path = ?);
+         DELETE FROM reading_list_items WHERE file_id IN (SELECT id FROM files WHERE parent_path = ?);
+         DELETE FROM files WHERE parent_path = ?;
+         DELETE FROM user_directory_flags WHERE directory_id = ?;",
     )
     .bind(&directory.name)
     .bind(&directory.parent_path)
-    .bind(format!("{}/{}", &directory.parent_path, &directory.name))
+    .bind(&directory_full_path)
+    .bind(&directory_full_path)
+    .bind(&directory_full_path)
+    .bind(&directory_full_path)
+    .bind(&directory_full_path)
+    .bind(&directory.id)
     .execute(conn)
     .await
     {
@@ -996,3 +2778,28 @@ pub async fn reset_scan_lock(conn: &Pool<Sqlite>) -> Result<(), String> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fts_query() {
+        assert_eq!(fts_query(""), "");
+        assert_eq!(fts_query("  dragon lance "), r#""dragon"* "lance"*"#);
+        assert_eq!(
+            fts_query(r#"élan "vital 01" tome"#),
+            r#""élan"* "vital 01" "tome"*"#
+        );
+        // unbalanced quote
+        assert_eq!(fts_query(r#"dragon "lance"#), r#""dragon"* "lance""#);
+    }
+
+    #[test]
+    fn test_cjk_terms() {
+        let query = r#"進撃 巨人 "l'attaque des titans" 진격의거인"#;
+        assert_eq!(fts_query(query), r#""l'attaque des titans""#);
+        assert_eq!(cjk_terms(query), ["進撃", "巨人", "진격의거인"]);
+        assert!(cjk_terms("dragon lance").is_empty());
+    }
+}
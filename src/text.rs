@@ -0,0 +1,351 @@
+//! plain text and markdown files : decoded, split in pages, markdown rendered to sanitized html
+
+use crate::html_render::xml_escape;
+use crate::scanner::{detect_name_encoding, NameEncoding};
+
+use pulldown_cmark::{CowStr, Event, Options, Parser, Tag};
+use serde::Deserialize;
+use std::fs;
+
+/// unit of the size of text pages
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum TextPageUnit {
+    #[default]
+    Lines,
+    Characters,
+}
+impl TextPageUnit {
+    pub const ALL: [TextPageUnit; 2] = [TextPageUnit::Lines, TextPageUnit::Characters];
+    pub fn as_str(&self) -> &str {
+        match self {
+            TextPageUnit::Lines => "lines",
+            TextPageUnit::Characters => "characters",
+        }
+    }
+    /// allowed page sizes
+    pub fn size_range(&self) -> (i64, i64) {
+        match self {
+            TextPageUnit::Lines => (10, 1000),
+            TextPageUnit::Characters => (500, 50000),
+        }
+    }
+}
+
+/// pagination of text and markdown files, set in admin panel
+#[derive(Debug, Clone, PartialEq, Deserialize, sqlx::FromRow)]
+pub struct TextPagination {
+    pub text_page_unit: TextPageUnit,
+    pub text_page_size: i64,
+}
+impl Default for TextPagination {
+    fn default() -> Self {
+        TextPagination {
+            text_page_unit: TextPageUnit::Lines,
+            text_page_size: 60,
+        }
+    }
+}
+
+/// text from its bytes : byte order mark, utf-8, or a guessed legacy encoding
+pub fn decode_text(bytes: &[u8]) -> String {
+    if let Some((encoding, _)) = encoding_rs::Encoding::for_bom(bytes) {
+        return encoding.decode(bytes).0.to_string();
+    }
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return text.to_string();
+    }
+    let encoding = detect_name_encoding(&[bytes.to_vec()]);
+    if matches!(encoding, NameEncoding::ShiftJis | NameEncoding::Gbk) {
+        return encoding.decode(bytes).unwrap_or_default();
+    }
+    // cyrillic letters are all non ascii, accented latin letters are a few
+    let non_ascii = bytes.iter().filter(|byte| !byte.is_ascii()).count();
+    let ascii_letters = bytes
+        .iter()
+        .filter(|byte| byte.is_ascii_alphabetic())
+        .count();
+    if non_ascii > ascii_letters {
+        return encoding_rs::WINDOWS_1251.decode(bytes).0.to_string();
+    }
+    encoding.decode(bytes).unwrap_or_default()
+}
+
+/// decoded content of a text file
+pub fn read_text(path: &str) -> Result<String, String> {
+    fs::read(path)
+        .map(|bytes| decode_text(&bytes))
+        .map_err(|e| format!("unable to read file : {e}"))
+}
+
+/// markdown code blocks fences, pages don't end inside them
+fn is_fence(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("```") || line.starts_with("~~~")
+}
+
+/// split a text in pages, at the end of lines
+pub fn paginate<'a>(text: &'a str, pagination: &TextPagination, markdown: bool) -> Vec<&'a str> {
+    let size = pagination.text_page_size.max(1) as usize;
+    let mut pages = Vec::new();
+    let (mut page_start, mut position) = (0, 0);
+    let (mut lines, mut characters) = (0, 0);
+    let mut in_fence = false;
+    for line in text.split_inclusive('\n') {
+        position += line.len();
+        lines += 1;
+        characters += line.chars().count();
+        if markdown && is_fence(line) {
+            in_fence = !in_fence;
+        }
+        let full = match pagination.text_page_unit {
+            TextPageUnit::Lines => lines >= size,
+            TextPageUnit::Characters => characters >= size,
+        };
+        if full && !in_fence {
+            pages.push(&text[page_start..position]);
+            page_start = position;
+            lines = 0;
+            characters = 0;
+        }
+    }
+    // last page, an empty text still has one page
+    if page_start < text.len() || pages.is_empty() {
+        pages.push(&text[page_start..]);
+    }
+    pages
+}
+
+/// page of the `new` pagination holding the beginning of `page` of the `old` one
+pub fn convert_page(
+    text: &str,
+    old: &TextPagination,
+    new: &TextPagination,
+    markdown: bool,
+    page: usize,
+) -> usize {
+    let offset: usize = paginate(text, old, markdown)
+        .iter()
+        .take(page)
+        .map(|page| page.len())
+        .sum();
+    let pages = paginate(text, new, markdown);
+    let mut end = 0;
+    pages
+        .iter()
+        .position(|page| {
+            end += page.len();
+            offset < end
+        })
+        .unwrap_or(pages.len() - 1)
+}
+
+/// links and images to scripts are removed
+fn is_safe_url(url: &str) -> bool {
+    let url = url.trim().to_lowercase();
+    !["javascript:", "vbscript:", "data:"]
+        .iter()
+        .any(|scheme| url.starts_with(scheme))
+        || url.starts_with("data:image/")
+}
+
+/// markdown to html, raw html of the markdown is escaped
+pub fn markdown_to_html(markdown: &str) -> String {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_FOOTNOTES;
+    let parser = Parser::new_ext(markdown, options).map(|event| match event {
+        Event::Html(html) | Event::InlineHtml(html) => Event::Text(html),
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) if !is_safe_url(&dest_url) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: CowStr::Borrowed("#"),
+            title,
+            id,
+        }),
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) if !is_safe_url(&dest_url) => Event::Start(Tag::Image {
+            link_type,
+            dest_url: CowStr::Borrowed(""),
+            title,
+            id,
+        }),
+        event => event,
+    });
+    let mut html = String::with_capacity(markdown.len() * 3 / 2);
+    pulldown_cmark::html::push_html(&mut html, parser);
+    html
+}
+
+/// plain text to html paragraphs, keeping lines breaks
+pub fn text_to_html(text: &str) -> String {
+    text.replace("\r\n", "\n")
+        .split("\n\n")
+        .filter(|paragraph| !paragraph.trim().is_empty())
+        .map(|paragraph| {
+            format!(
+                "<p>{}</p>",
+                xml_escape(paragraph.trim_matches('\n')).replace('\n', "<br />")
+            )
+        })
+        .collect()
+}
+
+/// title of a markdown file, its first heading
+pub fn markdown_title(markdown: &str) -> Option<String> {
+    markdown
+        .lines()
+        .map(str::trim)
+        .find(|line| line.starts_with("# "))
+        .map(|heading| heading.trim_start_matches('#').trim().to_string())
+        .filter(|title| !title.is_empty())
+}
+
+/// cover placeholder of text files : their title on a page
+pub fn cover_svg(title: &str, format: &str) -> String {
+    const LINE_LENGTH: usize = 16;
+    const MAX_LINES: usize = 7;
+    let mut lines: Vec<String> = Vec::new();
+    for word in title.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if line.chars().count() + 1 + word.chars().count() <= LINE_LENGTH => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.chars().take(LINE_LENGTH).collect()),
+        }
+    }
+    if lines.len() > MAX_LINES {
+        lines.truncate(MAX_LINES);
+        if let Some(line) = lines.last_mut() {
+            line.push('…');
+        }
+    }
+    let first_line_y = 150 - (lines.len() as i32 - 1) * 13;
+    let title_lines: String = lines
+        .iter()
+        .enumerate()
+        .map(|(index, line)| {
+            format!(
+                "<text x=\"100\" y=\"{}\">{}</text>",
+                first_line_y + index as i32 * 26,
+                xml_escape(line)
+            )
+        })
+        .collect();
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"200\" height=\"300\" viewBox=\"0 0 200 300\">\
+         <rect width=\"200\" height=\"300\" fill=\"#f4ecd8\"/>\
+         <rect x=\"10\" y=\"10\" width=\"180\" height=\"280\" fill=\"none\" stroke=\"#5b4636\" stroke-width=\"2\"/>\
+         <g font-family=\"serif\" font-size=\"20\" text-anchor=\"middle\" fill=\"#5b4636\">{title_lines}</g>\
+         <text x=\"100\" y=\"270\" font-family=\"sans-serif\" font-size=\"14\" text-anchor=\"middle\" fill=\"#5b4636\">{}</text>\
+         </svg>",
+        xml_escape(&format.to_uppercase())
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_text() {
+        assert_eq!(decode_text("Café".as_bytes()), "Café");
+        assert_eq!(decode_text(b"\xEF\xBB\xBFCaf\xC3\xA9"), "Café");
+        assert_eq!(decode_text(b"\xFF\xFEC\0a\0f\0\xE9\0"), "Café");
+        let (windows_1252, _, _) = encoding_rs::WINDOWS_1252.encode("Un café à Paris");
+        assert_eq!(decode_text(&windows_1252), "Un café à Paris");
+        let (windows_1251, _, _) = encoding_rs::WINDOWS_1251.encode("Война и мир");
+        assert_eq!(decode_text(&windows_1251), "Война и мир");
+        let (shift_jis, _, _) = encoding_rs::SHIFT_JIS.encode("こんにちは、世界");
+        assert_eq!(decode_text(&shift_jis), "こんにちは、世界");
+    }
+
+    #[test]
+    fn test_paginate() {
+        let text = "1\n2\n3\n4\n5";
+        let lines = |size| TextPagination {
+            text_page_unit: TextPageUnit::Lines,
+            text_page_size: size,
+        };
+        assert_eq!(
+            paginate(text, &lines(2), false),
+            vec!["1\n2\n", "3\n4\n", "5"]
+        );
+        assert_eq!(paginate(text, &lines(5), false), vec![text]);
+        assert_eq!(paginate("", &lines(5), false), vec![""]);
+        let characters = TextPagination {
+            text_page_unit: TextPageUnit::Characters,
+            text_page_size: 7,
+        };
+        assert_eq!(
+            paginate("abc\ndéfgh\nij\n", &characters, false),
+            vec!["abc\ndéfgh\n", "ij\n"]
+        );
+        // code blocks are not split
+        let markdown = "a\n```\nb\nc\n```\nd\n";
+        assert_eq!(
+            paginate(markdown, &lines(2), true),
+            vec!["a\n```\nb\nc\n```\n", "d\n"]
+        );
+        assert_eq!(paginate(markdown, &lines(2), false).len(), 3);
+    }
+
+    #[test]
+    fn test_convert_page() {
+        let text = "1\n2\n3\n4\n5\n6\n";
+        let lines = |size| TextPagination {
+            text_page_unit: TextPageUnit::Lines,
+            text_page_size: size,
+        };
+        assert_eq!(convert_page(text, &lines(2), &lines(3), false, 0), 0);
+        // line 5 is on the second page of 3 lines
+        assert_eq!(convert_page(text, &lines(2), &lines(3), false, 2), 1);
+        assert_eq!(convert_page(text, &lines(3), &lines(2), false, 1), 1);
+        // pages past the end go to the last one
+        assert_eq!(convert_page(text, &lines(2), &lines(3), false, 10), 1);
+    }
+
+    #[test]
+    fn test_markdown_to_html() {
+        assert_eq!(
+            markdown_to_html("# Title\n\nSome *text* <script>alert(1)</script>"),
+            "<h1>Title</h1>\n<p>Some <em>text</em> &lt;script&gt;alert(1)&lt;/script&gt;</p>\n"
+        );
+        assert_eq!(
+            markdown_to_html("[link](javascript:alert(1)) [ok](https://example.org)"),
+            "<p><a href=\"#\">link</a> <a href=\"https://example.org\">ok</a></p>\n"
+        );
+        assert_eq!(
+            markdown_to_html("<div onclick=\"x()\">\nhi\n</div>"),
+            "&lt;div onclick=\"x()\"&gt;\nhi\n&lt;/div&gt;"
+        );
+        assert_eq!(
+            markdown_title("intro\n# My *book*\n## part"),
+            Some("My *book*".to_string())
+        );
+    }
+
+    #[test]
+    fn test_text_to_html_and_cover() {
+        assert_eq!(
+            text_to_html("line 1\nline <2>\n\n\nparagraph 2\n"),
+            "<p>line 1<br />line &lt;2&gt;</p><p>paragraph 2</p>"
+        );
+        let svg = cover_svg("Le Tour du monde en quatre-vingts jours & autres", "txt");
+        assert!(svg.contains("<text x=\"100\" y=\"124\">Le Tour du monde</text>"));
+        assert!(svg.contains(">en quatre-vingts</text>"));
+        assert!(svg.contains(">jours &amp; autres</text>"));
+        assert!(svg.contains(">TXT</text>"));
+    }
+}
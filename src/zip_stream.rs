@@ -0,0 +1,259 @@
+//! zip archives of stored (uncompressed) entries written as they are read, without seeking :
+//! sizes and crc of each entry follow its content in a data descriptor, zip64 records are added
+//! when sizes or offsets no longer fit in 32 bits
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use time::OffsetDateTime;
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x04034b50;
+const DATA_DESCRIPTOR_SIGNATURE: u32 = 0x08074b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x02014b50;
+const ZIP64_END_SIGNATURE: u32 = 0x06064b50;
+const ZIP64_LOCATOR_SIGNATURE: u32 = 0x07064b50;
+const END_SIGNATURE: u32 = 0x06054b50;
+// sizes in data descriptor, utf-8 names
+const FLAGS: u16 = 0x0808;
+const VERSION: u16 = 20;
+const VERSION_ZIP64: u16 = 45;
+
+/// entry written, for the central directory
+struct Entry {
+    name: String,
+    crc: u32,
+    size: u64,
+    offset: u64,
+    zip64: bool,
+    time: u16,
+    date: u16,
+}
+
+/// writer counting bytes written, offsets of entries in the archive
+struct CountingWriter<W: Write> {
+    writer: W,
+    written: u64,
+}
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.writer.write(buf)?;
+        self.written += written as u64;
+        Ok(written)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// modification date of a file in ms-dos format : (time, date)
+fn dos_date_time(file: &File) -> (u16, u16) {
+    let Some(date) = file
+        .metadata()
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .map(OffsetDateTime::from)
+        .filter(|date| (1980..2108).contains(&date.year()))
+    else {
+        // 1980-01-01 00:00
+        return (0, 0x21);
+    };
+    let time =
+        ((date.hour() as u16) << 11) | ((date.minute() as u16) << 5) | (date.second() as u16 / 2);
+    let day =
+        (((date.year() - 1980) as u16) << 9) | ((date.month() as u16) << 5) | date.day() as u16;
+    (time, day)
+}
+
+/// write `entries` (name in archive, path on disk) to `writer` as a zip archive
+pub fn write_stored_zip<W: Write>(writer: W, entries: &[(String, PathBuf)]) -> io::Result<()> {
+    let mut writer = CountingWriter { writer, written: 0 };
+    let mut written_entries: Vec<Entry> = Vec::with_capacity(entries.len());
+    let mut buffer = vec![0u8; 64 * 1024];
+    for (name, path) in entries {
+        let mut file = File::open(path)?;
+        let declared_size = file.metadata()?.len();
+        let offset = writer.written;
+        // sizes are known only after the content in a stream, zip64 is decided from the file size
+        let zip64 = declared_size >= u32::MAX as u64 || offset >= u32::MAX as u64;
+        let (time, date) = dos_date_time(&file);
+        // local file header
+        let mut header = Vec::with_capacity(30 + name.len() + 20);
+        header.extend(LOCAL_HEADER_SIGNATURE.to_le_bytes());
+        header.extend(if zip64 { VERSION_ZIP64 } else { VERSION }.to_le_bytes());
+        header.extend(FLAGS.to_le_bytes());
+        // stored
+        header.extend(0u16.to_le_bytes());
+        header.extend(time.to_le_bytes());
+        header.extend(date.to_le_bytes());
+        // crc and sizes are in the data descriptor
+        header.extend(0u32.to_le_bytes());
+        let local_size: u32 = if zip64 { u32::MAX } else { 0 };
+        header.extend(local_size.to_le_bytes());
+        header.extend(local_size.to_le_bytes());
+        header.extend((name.len() as u16).to_le_bytes());
+        header.extend((if zip64 { 20u16 } else { 0 }).to_le_bytes());
+        header.extend(name.as_bytes());
+        if zip64 {
+            header.extend(1u16.to_le_bytes());
+            header.extend(16u16.to_le_bytes());
+            header.extend([0u8; 16]);
+        }
+        writer.write_all(&header)?;
+        // content
+        let mut hasher = crc32fast::Hasher::new();
+        let mut size: u64 = 0;
+        loop {
+            let read = file.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+            writer.write_all(&buffer[..read])?;
+            size += read as u64;
+        }
+        if !zip64 && size >= u32::MAX as u64 {
+            return Err(io::Error::other(format!("{name} grew while zipped")));
+        }
+        let crc = hasher.finalize();
+        // data descriptor
+        let mut descriptor = Vec::with_capacity(24);
+        descriptor.extend(DATA_DESCRIPTOR_SIGNATURE.to_le_bytes());
+        descriptor.extend(crc.to_le_bytes());
+        if zip64 {
+            descriptor.extend(size.to_le_bytes());
+            descriptor.extend(size.to_le_bytes());
+        } else {
+            descriptor.extend((size as u32).to_le_bytes());
+            descriptor.extend((size as u32).to_le_bytes());
+        }
+        writer.write_all(&descriptor)?;
+        written_entries.push(Entry {
+            name: name.clone(),
+            crc,
+            size,
+            offset,
+            zip64,
+            time,
+            date,
+        });
+    }
+    // central directory
+    let central_offset = writer.written;
+    for entry in &written_entries {
+        let large_size = entry.size >= u32::MAX as u64;
+        let large_offset = entry.offset >= u32::MAX as u64;
+        let mut extra = Vec::with_capacity(28);
+        if large_size || large_offset {
+            let mut fields = Vec::with_capacity(24);
+            if large_size {
+                fields.extend(entry.size.to_le_bytes());
+                fields.extend(entry.size.to_le_bytes());
+            }
+            if large_offset {
+                fields.extend(entry.offset.to_le_bytes());
+            }
+            extra.extend(1u16.to_le_bytes());
+            extra.extend((fields.len() as u16).to_le_bytes());
+            extra.extend(fields);
+        }
+        let version = if entry.zip64 { VERSION_ZIP64 } else { VERSION };
+        let size = if large_size {
+            u32::MAX
+        } else {
+            entry.size as u32
+        };
+        let mut header = Vec::with_capacity(46 + entry.name.len() + extra.len());
+        header.extend(CENTRAL_HEADER_SIGNATURE.to_le_bytes());
+        header.extend(VERSION_ZIP64.to_le_bytes());
+        header.extend(version.to_le_bytes());
+        header.extend(FLAGS.to_le_bytes());
+        header.extend(0u16.to_le_bytes());
+        header.extend(entry.time.to_le_bytes());
+        header.extend(entry.date.to_le_bytes());
+        header.extend(entry.crc.to_le_bytes());
+        header.extend(size.to_le_bytes());
+        header.extend(size.to_le_bytes());
+        header.extend((entry.name.len() as u16).to_le_bytes());
+        header.extend((extra.len() as u16).to_le_bytes());
+        // comment, disk, internal and external attributes
+        header.extend([0u8; 10]);
+        header.extend(
+            (if large_offset {
+                u32::MAX
+            } else {
+                entry.offset as u32
+            })
+            .to_le_bytes(),
+        );
+        header.extend(entry.name.as_bytes());
+        header.extend(extra);
+        writer.write_all(&header)?;
+    }
+    let central_size = writer.written - central_offset;
+    let count = written_entries.len() as u64;
+    let mut end = Vec::with_capacity(98);
+    if count >= u16::MAX as u64
+        || central_offset >= u32::MAX as u64
+        || central_size >= u32::MAX as u64
+    {
+        let zip64_end_offset = writer.written;
+        end.extend(ZIP64_END_SIGNATURE.to_le_bytes());
+        // size of the record after this field
+        end.extend(44u64.to_le_bytes());
+        end.extend(VERSION_ZIP64.to_le_bytes());
+        end.extend(VERSION_ZIP64.to_le_bytes());
+        // disks
+        end.extend([0u8; 8]);
+        end.extend(count.to_le_bytes());
+        end.extend(count.to_le_bytes());
+        end.extend(central_size.to_le_bytes());
+        end.extend(central_offset.to_le_bytes());
+        end.extend(ZIP64_LOCATOR_SIGNATURE.to_le_bytes());
+        end.extend(0u32.to_le_bytes());
+        end.extend(zip64_end_offset.to_le_bytes());
+        // total disks
+        end.extend(1u32.to_le_bytes());
+    }
+    end.extend(END_SIGNATURE.to_le_bytes());
+    end.extend([0u8; 4]);
+    let count = count.min(u16::MAX as u64) as u16;
+    end.extend(count.to_le_bytes());
+    end.extend(count.to_le_bytes());
+    end.extend((central_size.min(u32::MAX as u64) as u32).to_le_bytes());
+    end.extend((central_offset.min(u32::MAX as u64) as u32).to_le_bytes());
+    // comment
+    end.extend(0u16.to_le_bytes());
+    writer.write_all(&end)?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_write_stored_zip() {
+        let directory = std::env::temp_dir().join("eloran_test_zip_stream");
+        std::fs::create_dir_all(&directory).unwrap();
+        let pages = [("001.jpg", vec![1u8; 100_000]), ("002 été.png", vec![])];
+        for (name, content) in &pages {
+            std::fs::write(directory.join(name), content).unwrap();
+        }
+        let entries: Vec<(String, PathBuf)> = pages
+            .iter()
+            .map(|(name, _)| (name.to_string(), directory.join(name)))
+            .collect();
+        let mut zip = Vec::new();
+        write_stored_zip(&mut zip, &entries).unwrap();
+        let mut archive = zip::ZipArchive::new(Cursor::new(zip)).unwrap();
+        assert_eq!(archive.len(), 2);
+        for (name, content) in &pages {
+            let mut entry = archive.by_name(name).unwrap();
+            let mut read = Vec::new();
+            entry.read_to_end(&mut read).unwrap();
+            assert_eq!(&read, content);
+        }
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}